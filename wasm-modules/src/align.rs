@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Block alignment - which paragraph of one version became which of another
+//
+// Both documents are normalized with the default policy and split into
+// paragraphs as `segment::paragraphs` does, so indices line up with
+// `split_paragraphs`. Matching runs in two passes:
+//
+// 1. Identical paragraphs pair up with score 1.0, the n-th copy in A with
+//    the n-th copy in B.
+// 2. The remaining paragraphs are scored pairwise with `similarity` and
+//    paired greedily, best score first, ties going to the lower A index and
+//    then the lower B index. Pairs under `min_score` are never matched.
+//
+// Greedy matching is not guaranteed to maximise the total score, but it is
+// deterministic and for lightly edited documents gives the same answer.
+
+use crate::normalize::{self, NormalizeOptions};
+use crate::segment;
+use crate::similarity;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Options for `align`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct AlignOptions {
+    /// Lowest similarity at which two different paragraphs still match
+    pub min_score: f64,
+}
+
+impl Default for AlignOptions {
+    fn default() -> Self {
+        AlignOptions { min_score: 0.5 }
+    }
+}
+
+/// One matched pair of paragraphs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BlockMatch {
+    pub a_index: usize,
+    pub b_index: usize,
+    pub score: f64,
+}
+
+/// Result of `align`
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Alignment {
+    /// Matched pairs in order of `a_index`
+    pub matches: Vec<BlockMatch>,
+    /// Paragraphs of A with no counterpart in B, ascending
+    pub unmatched_a: Vec<usize>,
+    /// Paragraphs of B with no counterpart in A, ascending
+    pub unmatched_b: Vec<usize>,
+}
+
+/// Align the paragraphs of `a` with those of `b`
+pub fn align(a: &str, b: &str, options: &AlignOptions) -> Result<Alignment, String> {
+    if !(0.0..=1.0).contains(&options.min_score) {
+        return Err(format!("min_score must be between 0 and 1, got {}", options.min_score));
+    }
+
+    let normalize_options = NormalizeOptions::default();
+    let a = normalize::normalize(a, &normalize_options);
+    let b = normalize::normalize(b, &normalize_options);
+    let a_blocks: Vec<&str> = segment::paragraphs(&a).into_iter().map(|span| span.text).collect();
+    let b_blocks: Vec<&str> = segment::paragraphs(&b).into_iter().map(|span| span.text).collect();
+
+    let mut a_match: Vec<Option<usize>> = vec![None; a_blocks.len()];
+    let mut b_matched = vec![false; b_blocks.len()];
+    let mut matches = Vec::new();
+
+    // Exact pass: queue the A copies of each text, consume them in B order
+    let mut copies: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (index, text) in a_blocks.iter().enumerate().rev() {
+        copies.entry(text).or_default().push(index);
+    }
+    for (b_index, text) in b_blocks.iter().enumerate() {
+        if let Some(a_index) = copies.get_mut(text).and_then(Vec::pop) {
+            a_match[a_index] = Some(b_index);
+            b_matched[b_index] = true;
+            matches.push(BlockMatch { a_index, b_index, score: 1.0 });
+        }
+    }
+
+    // Fuzzy pass over what is left
+    let a_words: Vec<Vec<&str>> = a_blocks.iter().map(|text| similarity::words(text)).collect();
+    let b_words: Vec<Vec<&str>> = b_blocks.iter().map(|text| similarity::words(text)).collect();
+    let mut candidates = Vec::new();
+    for a_index in (0..a_blocks.len()).filter(|&i| a_match[i].is_none()) {
+        for b_index in (0..b_blocks.len()).filter(|&i| !b_matched[i]) {
+            let score = similarity::word_similarity(&a_words[a_index], &b_words[b_index]);
+            if score > 0.0 && score >= options.min_score {
+                candidates.push(BlockMatch { a_index, b_index, score });
+            }
+        }
+    }
+    candidates.sort_by(|x, y| {
+        y.score
+            .total_cmp(&x.score)
+            .then(x.a_index.cmp(&y.a_index))
+            .then(x.b_index.cmp(&y.b_index))
+    });
+    for candidate in candidates {
+        if a_match[candidate.a_index].is_none() && !b_matched[candidate.b_index] {
+            a_match[candidate.a_index] = Some(candidate.b_index);
+            b_matched[candidate.b_index] = true;
+            matches.push(candidate);
+        }
+    }
+
+    matches.sort_by_key(|m| m.a_index);
+    Ok(Alignment {
+        matches,
+        unmatched_a: (0..a_blocks.len()).filter(|&i| a_match[i].is_none()).collect(),
+        unmatched_b: (0..b_blocks.len()).filter(|&i| !b_matched[i]).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTRO: &str = "The quarterly report covers revenue, costs and the outlook for next year.";
+    const COSTS: &str = "Costs were flat because the new warehouse opened later than planned.";
+    const OUTLOOK: &str = "We expect growth to continue as the two new regions come online in spring.";
+
+    fn pairs(alignment: &Alignment) -> Vec<(usize, usize)> {
+        alignment.matches.iter().map(|m| (m.a_index, m.b_index)).collect()
+    }
+
+    fn align_default(a: &str, b: &str) -> Alignment {
+        align(a, b, &AlignOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_reordered_paragraphs_align_exactly() {
+        let a = [INTRO, COSTS, OUTLOOK].join("\n\n");
+        let b = [OUTLOOK, INTRO, COSTS].join("\n\n\n");
+        let alignment = align_default(&a, &b);
+        assert_eq!(pairs(&alignment), [(0, 1), (1, 2), (2, 0)]);
+        assert!(alignment.matches.iter().all(|m| m.score == 1.0));
+        assert!(alignment.unmatched_a.is_empty() && alignment.unmatched_b.is_empty());
+    }
+
+    #[test]
+    fn test_deleted_paragraph_is_unmatched() {
+        let a = [INTRO, COSTS, OUTLOOK].join("\n\n");
+        let b = [INTRO, OUTLOOK].join("\n\n");
+        let alignment = align_default(&a, &b);
+        assert_eq!(pairs(&alignment), [(0, 0), (2, 1)]);
+        assert_eq!(alignment.unmatched_a, [1]);
+        assert!(alignment.unmatched_b.is_empty());
+    }
+
+    #[test]
+    fn test_lightly_edited_paragraph_matches_fuzzily() {
+        let edited = "Costs were flat because the new warehouse opened much later than planned.";
+        let a = [INTRO, COSTS, OUTLOOK].join("\n\n");
+        let b = [OUTLOOK, edited, INTRO].join("\n\n");
+        let alignment = align_default(&a, &b);
+        assert_eq!(pairs(&alignment), [(0, 2), (1, 1), (2, 0)]);
+
+        let fuzzy = &alignment.matches[1];
+        assert!(fuzzy.score < 1.0 && fuzzy.score >= 0.5, "score {}", fuzzy.score);
+
+        // Raising the threshold above the edit's score leaves both sides unmatched
+        let strict = align(&a, &b, &AlignOptions { min_score: 0.95 }).unwrap();
+        assert_eq!(pairs(&strict), [(0, 2), (2, 0)]);
+        assert_eq!((strict.unmatched_a.as_slice(), strict.unmatched_b.as_slice()), (&[1][..], &[1][..]));
+    }
+
+    #[test]
+    fn test_exact_matches_win_over_fuzzy_ones() {
+        let near = "The quarterly report covers revenue, costs and the outlook for the next year.";
+        // B has a near copy of INTRO before the exact one; the exact one must get it
+        let alignment = align_default(INTRO, &[near, INTRO].join("\n\n"));
+        assert_eq!(pairs(&alignment), [(0, 1)]);
+        assert_eq!(alignment.unmatched_b, [0]);
+    }
+
+    #[test]
+    fn test_duplicates_pair_in_order_and_result_is_deterministic() {
+        let a = [COSTS, INTRO, COSTS].join("\n\n");
+        let b = [COSTS, COSTS, OUTLOOK].join("\n\n");
+        let first = align_default(&a, &b);
+        assert_eq!(pairs(&first), [(0, 0), (2, 1)]);
+        assert_eq!((first.unmatched_a.as_slice(), first.unmatched_b.as_slice()), (&[1][..], &[2][..]));
+        for _ in 0..10 {
+            assert_eq!(align_default(&a, &b), first);
+        }
+    }
+
+    #[test]
+    fn test_options() {
+        assert_eq!(align_default("", ""), Alignment::default());
+        assert!(align("a", "b", &AlignOptions { min_score: 1.5 }).is_err());
+        assert!(align("a", "b", &AlignOptions { min_score: f64::NAN }).is_err());
+        // Unrelated paragraphs never match, even with no threshold
+        let loose = align(INTRO, OUTLOOK, &AlignOptions { min_score: 0.0 }).unwrap();
+        assert!(loose.matches.is_empty());
+    }
+}
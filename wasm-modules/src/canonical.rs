@@ -0,0 +1,303 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Canonical JSON - RFC 8785 JSON Canonicalization Scheme (JCS)
+//
+// Two JSON texts with the same data canonicalize to the same bytes:
+// object members are sorted by the UTF-16 code units of their names,
+// insignificant whitespace is dropped, strings use the minimal escapes of
+// ECMAScript `JSON.stringify`, and numbers are IEEE 754 doubles printed the
+// way ECMAScript `Number.prototype.toString` prints them. Duplicate object
+// names are rejected (RFC 8785 §3.1 requires I-JSON input). Nesting depth
+// is bounded by serde_json's recursion limit, so hostile input is an error
+// rather than a stack overflow.
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Parsed JSON value with object members kept in input order
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl<'de> Deserialize<'de> for Json {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(JsonVisitor)
+    }
+}
+
+struct JsonVisitor;
+
+impl<'de> Visitor<'de> for JsonVisitor {
+    type Value = Json;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Json, E> {
+        Ok(Json::Null)
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Json, E> {
+        Ok(Json::Bool(value))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Json, E> {
+        Ok(Json::Number(value as f64))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Json, E> {
+        Ok(Json::Number(value as f64))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Json, E> {
+        if !value.is_finite() {
+            return Err(E::custom("NaN and Infinity are not valid JSON numbers"));
+        }
+        Ok(Json::Number(value))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Json, E> {
+        Ok(Json::String(value.to_string()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Json, E> {
+        Ok(Json::String(value))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Json, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Json::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Json, A::Error> {
+        let mut members = Vec::new();
+        let mut seen = HashSet::new();
+        while let Some(name) = map.next_key::<String>()? {
+            if !seen.insert(name.clone()) {
+                return Err(de::Error::custom(format!("duplicate object member {:?}", name)));
+            }
+            members.push((name, map.next_value()?));
+        }
+        Ok(Json::Object(members))
+    }
+}
+
+/// Canonicalize a JSON text per RFC 8785
+///
+/// Errors describe the problem and the byte offset where it was found.
+pub fn canonicalize(json: &str) -> Result<String, String> {
+    let value: Json = serde_json::from_str(json).map_err(|e| describe_error(json, &e))?;
+    let mut out = String::with_capacity(json.len());
+    write_value(&value, &mut out);
+    Ok(out)
+}
+
+/// Turn a serde_json error into a message with a byte offset
+fn describe_error(json: &str, error: &serde_json::Error) -> String {
+    let message = error.to_string();
+    if error.line() == 0 {
+        return format!("Invalid JSON: {}", message);
+    }
+
+    let suffix = format!(" at line {} column {}", error.line(), error.column());
+    let message = message.strip_suffix(&suffix).unwrap_or(&message);
+    format!("Invalid JSON at byte {}: {}", byte_offset(json, error.line(), error.column()), message)
+}
+
+/// Byte offset of a 1-based line and byte column reported by serde_json
+fn byte_offset(json: &str, line: usize, column: usize) -> usize {
+    let line_start: usize = json.split_inclusive('\n').take(line - 1).map(str::len).sum();
+    (line_start + column.saturating_sub(1)).min(json.len())
+}
+
+fn write_value(value: &Json, out: &mut String) {
+    match value {
+        Json::Null => out.push_str("null"),
+        Json::Bool(true) => out.push_str("true"),
+        Json::Bool(false) => out.push_str("false"),
+        Json::Number(n) => out.push_str(&format_number(*n)),
+        Json::String(s) => write_string(s, out),
+        Json::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(item, out);
+            }
+            out.push(']');
+        }
+        Json::Object(members) => {
+            let mut sorted: Vec<&(String, Json)> = members.iter().collect();
+            sorted.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push('{');
+            for (i, (name, value)) in sorted.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(name, out);
+                out.push(':');
+                write_value(value, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+/// String serialization of RFC 8785 §3.2.2.2
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{08}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{0c}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            c if c < '\u{20}' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// ECMAScript `Number.prototype.toString` for a finite double
+///
+/// ryu-js follows the ECMAScript rules exactly, including round-half-even
+/// when two shortest digit strings are equally close (std's `Display`
+/// rounds those ties up).
+fn format_number(n: f64) -> String {
+    ryu_js::Buffer::new().format_finite(n).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc8785_number_vectors() {
+        // RFC 8785 Appendix B
+        let vectors: &[(u64, &str)] = &[
+            (0x0000000000000000, "0"),
+            (0x8000000000000000, "0"),
+            (0x0000000000000001, "5e-324"),
+            (0x8000000000000001, "-5e-324"),
+            (0x7fefffffffffffff, "1.7976931348623157e+308"),
+            (0xffefffffffffffff, "-1.7976931348623157e+308"),
+            (0x4340000000000000, "9007199254740992"),
+            (0xc340000000000000, "-9007199254740992"),
+            (0x4430000000000000, "295147905179352830000"),
+            (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+            (0x44b52d02c7e14af6, "1e+23"),
+            (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+            (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+            (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+            (0x444b1ae4d6e2ef50, "1e+21"),
+            (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+            (0x3eb0c6f7a0b5ed8d, "0.000001"),
+            (0x41b3de4355555553, "333333333.3333332"),
+            (0x41b3de4355555554, "333333333.33333325"),
+            (0x41b3de4355555555, "333333333.3333333"),
+            (0x41b3de4355555556, "333333333.3333334"),
+            (0x41b3de4355555557, "333333333.33333343"),
+            (0xbecbf647612f3696, "-0.0000033333333333333333"),
+            (0x43143ff3c1cb0959, "1424953923781206.2"),
+        ];
+        for &(bits, expected) in vectors {
+            assert_eq!(format_number(f64::from_bits(bits)), expected, "{:016x}", bits);
+        }
+    }
+
+    #[test]
+    fn test_rfc8785_primitive_example() {
+        // RFC 8785 §3.2.2
+        let input = r#"{
+          "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+          "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+          "literals": [null, true, false]
+        }"#;
+        assert_eq!(
+            canonicalize(input).unwrap(),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+    }
+
+    #[test]
+    fn test_rfc8785_sorting_example() {
+        // RFC 8785 §3.2.3: names sort by UTF-16 code units, so the emoji
+        // (a surrogate pair starting 0xD83D) sorts before U+FB33
+        let input = r#"{
+          "\u20ac": "Euro Sign",
+          "\r": "Carriage Return",
+          "\ufb33": "Hebrew Letter Dalet With Dagesh",
+          "1": "One",
+          "\ud83d\ude00": "Emoji: Grinning Face",
+          "\u0080": "Control",
+          "\u00f6": "Latin Small Letter O With Diaeresis"
+        }"#;
+        assert_eq!(
+            canonicalize(input).unwrap(),
+            "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",\
+             \"\u{f6}\":\"Latin Small Letter O With Diaeresis\",\"\u{20ac}\":\"Euro Sign\",\
+             \"\u{1f600}\":\"Emoji: Grinning Face\",\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}"
+        );
+    }
+
+    #[test]
+    fn test_semantically_equal_documents() {
+        let a = r#"{"id": 7, "tags": ["x", "y"], "meta": {"b": 1.0, "a": -0}}"#;
+        let b = "{\n  \"meta\" : { \"a\" : 0, \"b\" : 1e0 },\n  \"tags\":[\"x\",\"y\"], \"id\":7.00\n}";
+        assert_eq!(canonicalize(a).unwrap(), canonicalize(b).unwrap());
+        assert_eq!(canonicalize(a).unwrap(), r#"{"id":7,"meta":{"a":0,"b":1},"tags":["x","y"]}"#);
+        assert_ne!(canonicalize(a).unwrap(), canonicalize(r#"{"id": 8}"#).unwrap());
+    }
+
+    #[test]
+    fn test_large_integers_become_doubles() {
+        assert_eq!(canonicalize("[9007199254740993]").unwrap(), "[9007199254740992]");
+        assert_eq!(canonicalize("[18446744073709551615]").unwrap(), "[18446744073709552000]");
+        assert_eq!(canonicalize("[-9223372036854775808]").unwrap(), "[-9223372036854776000]");
+    }
+
+    #[test]
+    fn test_invalid_json_reports_byte_offset() {
+        let err = canonicalize("{\"a\": 1,\n \"b\": }").unwrap_err();
+        assert!(err.starts_with("Invalid JSON at byte 15:"), "{}", err);
+
+        let err = canonicalize("[1, 2").unwrap_err();
+        assert!(err.starts_with("Invalid JSON at byte 4: EOF"), "{}", err);
+    }
+
+    #[test]
+    fn test_rejects_non_finite_and_duplicates() {
+        assert!(canonicalize("[NaN]").is_err());
+        assert!(canonicalize("[Infinity]").is_err());
+        assert!(canonicalize("[-Infinity]").is_err());
+        assert!(canonicalize("[1e400]").is_err());
+
+        let err = canonicalize(r#"{"a": 1, "a": 2}"#).unwrap_err();
+        assert!(err.contains("duplicate object member \"a\""), "{}", err);
+    }
+
+    #[test]
+    fn test_deep_nesting_is_an_error_not_a_crash() {
+        let deep = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+        assert!(canonicalize(&deep).unwrap_err().contains("recursion limit"));
+
+        let ok = format!("{}{}", "[".repeat(100), "]".repeat(100));
+        assert_eq!(canonicalize(&ok).unwrap(), ok);
+    }
+}
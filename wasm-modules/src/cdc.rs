@@ -0,0 +1,194 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Content-defined chunking - FastCDC
+//
+// Implements FastCDC (Xia et al., USENIX ATC 2016) with normalized
+// chunking: a Gear rolling hash `h = (h << 1) + GEAR[byte]` is tested
+// against a stricter mask below the target average size and a looser one
+// above it, which pulls chunk sizes towards the average. Masks use the
+// high bits of the hash so every tested bit depends on a full window of
+// recent bytes. Boundaries depend only on content, so inserting bytes
+// near the start of a document only changes the chunks around the edit.
+
+use crate::hashing::{sha256, to_hex};
+use serde::{Deserialize, Serialize};
+
+/// A content-defined chunk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chunk {
+    /// Byte offset into the input
+    pub offset: usize,
+    /// Length in bytes
+    pub length: usize,
+    /// SHA-256 of the chunk as lowercase hex
+    pub hash: String,
+}
+
+/// Gear table, fixed so boundaries are identical across builds
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0x6a09_e667_f3bc_c908;
+    let mut i = 0;
+    while i < 256 {
+        // SplitMix64
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+/// Mask selecting the top `bits` bits of the hash
+fn high_mask(bits: u32) -> u64 {
+    match bits {
+        0 => 0,
+        64.. => u64::MAX,
+        _ => u64::MAX << (64 - bits),
+    }
+}
+
+/// Split `data` into content-defined chunks
+///
+/// Requires `0 < min <= avg <= max`. Input shorter than `min` is a single
+/// chunk; empty input yields no chunks.
+pub fn chunk(data: &[u8], min: usize, avg: usize, max: usize) -> Result<Vec<Chunk>, String> {
+    if min == 0 || min > avg || avg > max {
+        return Err(format!(
+            "Chunk sizes must satisfy 0 < min <= avg <= max (got min={}, avg={}, max={})",
+            min, avg, max
+        ));
+    }
+
+    let bits = avg.ilog2();
+    let mask_small = high_mask(bits + 1);
+    let mask_large = high_mask(bits.saturating_sub(1));
+
+    let mut chunks = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let length = cut_point(&data[offset..], min, avg, max, mask_small, mask_large);
+        chunks.push(Chunk {
+            offset,
+            length,
+            hash: to_hex(&sha256(&data[offset..offset + length])),
+        });
+        offset += length;
+    }
+
+    Ok(chunks)
+}
+
+/// Length of the next chunk at the start of `data`
+fn cut_point(data: &[u8], min: usize, avg: usize, max: usize, mask_small: u64, mask_large: u64) -> usize {
+    if data.len() <= min {
+        return data.len();
+    }
+
+    let end = data.len().min(max);
+    let normal = avg.min(end);
+    let mut hash: u64 = 0;
+
+    for (i, &byte) in data.iter().enumerate().take(normal).skip(min) {
+        hash = (hash << 1).wrapping_add(GEAR[byte as usize]);
+        if hash & mask_small == 0 {
+            return i + 1;
+        }
+    }
+    for (i, &byte) in data.iter().enumerate().take(end).skip(normal) {
+        hash = (hash << 1).wrapping_add(GEAR[byte as usize]);
+        if hash & mask_large == 0 {
+            return i + 1;
+        }
+    }
+
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::random_bytes;
+    use std::collections::HashSet;
+
+    fn assert_covers(data: &[u8], chunks: &[Chunk], min: usize, max: usize) {
+        let mut offset = 0;
+        for (i, c) in chunks.iter().enumerate() {
+            assert_eq!(c.offset, offset);
+            assert!(c.length <= max);
+            if i + 1 < chunks.len() {
+                assert!(c.length >= min);
+            }
+            assert_eq!(c.hash, to_hex(&sha256(&data[c.offset..c.offset + c.length])));
+            offset += c.length;
+        }
+        assert_eq!(offset, data.len());
+    }
+
+    #[test]
+    fn test_invalid_sizes() {
+        assert!(chunk(b"abc", 0, 4, 8).is_err());
+        assert!(chunk(b"abc", 8, 4, 16).is_err());
+        assert!(chunk(b"abc", 2, 16, 8).is_err());
+        assert!(chunk(b"abc", 4, 4, 4).is_ok());
+    }
+
+    #[test]
+    fn test_small_and_empty_input() {
+        assert!(chunk(b"", 16, 64, 256).unwrap().is_empty());
+
+        let chunks = chunk(b"tiny", 16, 64, 256).unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!((chunks[0].offset, chunks[0].length), (0, 4));
+        assert_eq!(chunks[0].hash, to_hex(&sha256(b"tiny")));
+    }
+
+    #[test]
+    fn test_boundaries_cover_input_and_respect_limits() {
+        let data = random_bytes(300_000, 1);
+        let chunks = chunk(&data, 2048, 8192, 32768).unwrap();
+        assert_covers(&data, &chunks, 2048, 32768);
+
+        let mean = data.len() / chunks.len();
+        assert!(mean > 4096 && mean < 16384, "mean chunk size {}", mean);
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let data = random_bytes(100_000, 2);
+        assert_eq!(chunk(&data, 512, 2048, 8192).unwrap(), chunk(&data, 512, 2048, 8192).unwrap());
+    }
+
+    #[test]
+    fn test_uniform_input_cuts_at_max() {
+        let data = vec![0u8; 10_000];
+        let chunks = chunk(&data, 64, 256, 1024).unwrap();
+        assert_covers(&data, &chunks, 64, 1024);
+        // No content boundary ever matches, so every cut is forced at max
+        assert_eq!(chunks.len(), 10);
+        assert!(chunks[..chunks.len() - 1].iter().all(|c| c.length == 1024));
+        assert_eq!(chunks.last().unwrap().length, 10_000 - 9 * 1024);
+    }
+
+    #[test]
+    fn test_insertion_only_changes_leading_chunks() {
+        let data = random_bytes(1 << 20, 3);
+        let mut edited = b"0123456789".to_vec();
+        edited.extend_from_slice(&data);
+
+        let (min, avg, max) = (2048, 8192, 65536);
+        let before = chunk(&data, min, avg, max).unwrap();
+        let after = chunk(&edited, min, avg, max).unwrap();
+        assert_covers(&edited, &after, min, max);
+
+        let known: HashSet<&str> = before.iter().map(|c| c.hash.as_str()).collect();
+        let changed = after.iter().filter(|c| !known.contains(c.hash.as_str())).count();
+        assert!(before.len() > 50);
+        assert!(changed <= 2, "{} of {} chunks changed", changed, after.len());
+        assert!(!known.contains(after[0].hash.as_str()));
+    }
+}
@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Hash chains - tamper-evident logs of reconciliation decisions
+//
+// Each head commits to the previous head and to the new entry:
+//
+//     head_0 = 32 zero bytes (GENESIS)
+//     head_n = SHA-256(head_{n-1} || SHA-256(entry_n))
+//
+// where `||` joins the two raw 32-byte digests. Changing, dropping,
+// inserting or reordering any entry changes every head after it, so
+// replaying the log and comparing the final head detects tampering.
+
+use crate::hashing::{digest_from_hex, digests_equal, sha256, to_hex};
+use serde::{Deserialize, Serialize};
+
+/// Head of an empty chain
+pub const GENESIS: [u8; 32] = [0; 32];
+
+/// A chain's head and length
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashChain {
+    head: [u8; 32],
+    len: usize,
+}
+
+/// Persistent form of a chain: the hex head and the number of entries
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainState {
+    pub head: String,
+    pub length: usize,
+}
+
+impl HashChain {
+    pub fn new() -> Self {
+        HashChain { head: GENESIS, len: 0 }
+    }
+
+    /// Add an entry and return the new head
+    pub fn append(&mut self, entry: &[u8]) -> [u8; 32] {
+        let mut input = [0u8; 64];
+        input[..32].copy_from_slice(&self.head);
+        input[32..].copy_from_slice(&sha256(entry));
+        self.head = sha256(&input);
+        self.len += 1;
+        self.head
+    }
+
+    /// The current head, `GENESIS` for an empty chain
+    pub fn head(&self) -> [u8; 32] {
+        self.head
+    }
+
+    /// Number of entries appended
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no entry has been appended
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The head and length in their persistent form
+    pub fn state(&self) -> ChainState {
+        ChainState { head: to_hex(&self.head), length: self.len }
+    }
+
+    /// Resume a chain from a saved state
+    ///
+    /// The head must be a 64-character hex digest, and an empty chain must
+    /// have the genesis head.
+    pub fn from_state(state: &ChainState) -> Result<Self, String> {
+        let head = digest_from_hex(&state.head).ok_or_else(|| format!("Invalid chain head: {:?}", state.head))?;
+        if state.length == 0 && head != GENESIS {
+            return Err("An empty chain must have the genesis head".to_string());
+        }
+        Ok(HashChain { head, len: state.length })
+    }
+}
+
+impl Default for HashChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether replaying `entries` from genesis ends at `expected_head`
+///
+/// The comparison is constant-time.
+pub fn verify(entries: impl IntoIterator<Item = impl AsRef<[u8]>>, expected_head: &[u8; 32]) -> bool {
+    let mut chain = HashChain::new();
+    for entry in entries {
+        chain.append(entry.as_ref());
+    }
+    digests_equal(&chain.head, expected_head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: [&str; 5] = [
+        r#"{"doc":"a","decision":"keep-local"}"#,
+        r#"{"doc":"b","decision":"keep-remote"}"#,
+        r#"{"doc":"c","decision":"merge"}"#,
+        r#"{"doc":"d","decision":"keep-local"}"#,
+        r#"{"doc":"e","decision":"delete"}"#,
+    ];
+
+    fn head_of(entries: &[&str]) -> [u8; 32] {
+        let mut chain = HashChain::new();
+        for entry in entries {
+            chain.append(entry.as_bytes());
+        }
+        chain.head()
+    }
+
+    #[test]
+    fn test_first_head_follows_the_documented_rule() {
+        let mut chain = HashChain::new();
+        assert_eq!(chain.head(), GENESIS);
+        assert!(chain.is_empty());
+
+        let head = chain.append(b"abc");
+        let mut input = vec![0u8; 32];
+        input.extend(sha256(b"abc"));
+        assert_eq!(head, sha256(&input));
+        assert_eq!(chain.len(), 1);
+    }
+
+    #[test]
+    fn test_verify_replays_the_log() {
+        let head = head_of(&LOG);
+        assert!(verify(LOG, &head));
+        assert!(verify(Vec::<&str>::new(), &GENESIS));
+        assert!(!verify(&LOG[..4], &head));
+    }
+
+    #[test]
+    fn test_tampering_with_any_middle_entry_is_detected() {
+        let head = head_of(&LOG);
+        for i in 1..LOG.len() - 1 {
+            let mut tampered = LOG;
+            let edited = tampered[i].replace("keep", "drop").replace("merge", "mergf");
+            tampered[i] = &edited;
+            assert!(!verify(tampered, &head), "entry {} modified", i);
+
+            let mut dropped = LOG.to_vec();
+            dropped.remove(i);
+            assert!(!verify(&dropped, &head), "entry {} dropped", i);
+
+            let mut swapped = LOG;
+            swapped.swap(i, i + 1);
+            assert!(!verify(swapped, &head), "entries {} and {} swapped", i, i + 1);
+        }
+    }
+
+    #[test]
+    fn test_state_round_trips() {
+        let mut chain = HashChain::new();
+        chain.append(LOG[0].as_bytes());
+        chain.append(LOG[1].as_bytes());
+
+        let state = chain.state();
+        assert_eq!(state.length, 2);
+        let json = serde_json::to_string(&state).unwrap();
+        let mut resumed = HashChain::from_state(&serde_json::from_str(&json).unwrap()).unwrap();
+        assert_eq!(resumed, chain);
+
+        for entry in &LOG[2..] {
+            resumed.append(entry.as_bytes());
+        }
+        assert_eq!(resumed.head(), head_of(&LOG));
+        assert_eq!(resumed.len(), 5);
+    }
+
+    #[test]
+    fn test_invalid_states_rejected() {
+        assert!(HashChain::from_state(&ChainState { head: "abc".to_string(), length: 1 }).is_err());
+        let head = to_hex(&head_of(&LOG));
+        assert!(HashChain::from_state(&ChainState { head, length: 0 }).is_err());
+        assert!(HashChain::from_state(&ChainState { head: to_hex(&GENESIS), length: 0 }).is_ok());
+    }
+}
@@ -0,0 +1,197 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Native API - the main operations as plain Rust functions
+//
+// These are what the WASM exports call, so a server built with
+// `--no-default-features` computes the same hashes as the browser. Digests
+// come back as raw bytes; `hashing::to_hex` gives the hex the WASM side
+// returns. Batch functions take `&[String]` and return results in input
+// order.
+//
+// The size limits guard the WASM boundary and are not applied here.
+
+use crate::dedupe::{self, Consensus, DuplicateGroup};
+use crate::error::ReconError;
+use crate::fold::{self, FoldOptions};
+use crate::hashing::{self, DigestEncoding, HashAlgorithm};
+use crate::{canonical, fingerprint, keyed, normalize};
+
+pub use crate::normalize::NormalizeOptions as Options;
+
+/// SHA-256 of UTF-8 content
+pub fn hash_content(content: &str) -> [u8; 32] {
+    hash_bytes(content.as_bytes())
+}
+
+/// SHA-256 of raw bytes
+pub fn hash_bytes(data: &[u8]) -> [u8; 32] {
+    hashing::sha256(data)
+}
+
+/// SHA-256 of the RFC 8785 (JCS) canonical form of a JSON document
+pub fn hash_json_canonical(json: &str) -> Result<[u8; 32], ReconError> {
+    let canonical = canonical::canonicalize(json).map_err(ReconError::decode)?;
+    Ok(hash_content(&canonical))
+}
+
+/// Normalize `content` according to `options`
+pub fn normalize(content: &str, options: &Options) -> String {
+    normalize::normalize(content, options)
+}
+
+/// Hash the normalized form of `content`, encoded as the algorithm's hex
+pub fn normalize_and_hash(content: &str, options: &Options, algorithm: HashAlgorithm) -> String {
+    algorithm.hash(normalize(content, options).as_bytes())
+}
+
+/// SHA-256 of `content` folded with every folding step on
+pub fn match_key(content: &str) -> [u8; 32] {
+    hash_content(&fold::fold(content, &FoldOptions::default()))
+}
+
+/// Hash every document as UTF-8
+pub fn batch_hash(documents: &[String], algorithm: HashAlgorithm, encoding: DigestEncoding) -> Vec<String> {
+    documents
+        .iter()
+        .map(|doc| encoding.encode(&algorithm.digest(doc.as_bytes())))
+        .collect()
+}
+
+/// Normalize every document with one set of options
+pub fn batch_normalize(documents: &[String], options: &Options) -> Vec<String> {
+    documents.iter().map(|doc| normalize(doc, options)).collect()
+}
+
+/// `normalize_and_hash` of every document
+pub fn batch_normalize_and_hash(documents: &[String], options: &Options, algorithm: HashAlgorithm) -> Vec<String> {
+    documents
+        .iter()
+        .map(|doc| normalize_and_hash(doc, options, algorithm))
+        .collect()
+}
+
+/// Group identical documents after normalization
+pub fn dedupe_documents(
+    documents: &[String],
+    options: &Options,
+    algorithm: HashAlgorithm,
+    include_singletons: bool,
+) -> Vec<DuplicateGroup> {
+    let hashes = documents.iter().map(|doc| normalize_and_hash(doc, options, algorithm));
+    dedupe::group_by_hash(hashes, include_singletons)
+}
+
+/// Find the version most replicas agree on
+///
+/// `options` of `None` compares the content exactly as given.
+pub fn consensus(replicas: &[String], options: Option<&Options>, algorithm: HashAlgorithm) -> Consensus {
+    let hashes = replicas.iter().map(|doc| match options {
+        Some(options) => normalize_and_hash(doc, options, algorithm),
+        None => algorithm.hash(doc.as_bytes()),
+    });
+    dedupe::consensus(hashes)
+}
+
+/// SimHash fingerprint of every document, as 16 hex characters each
+pub fn batch_fingerprint(documents: &[String]) -> Vec<String> {
+    documents
+        .iter()
+        .map(|doc| fingerprint::to_hex(fingerprint::simhash(doc)))
+        .collect()
+}
+
+/// HMAC-SHA256 of every document under one key, as hex
+pub fn batch_hmac(key: &[u8], documents: &[String]) -> Vec<String> {
+    documents.iter().map(|doc| keyed::hmac_bytes(key, doc.as_bytes())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashing::to_hex;
+
+    /// Published SHA-256 vectors, also checked against the WASM exports
+    const SHA256_VECTORS: [(&str, &str); 4] = [
+        ("", "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+        ("abc", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+        ("Hello, World!", "dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f"),
+        (
+            "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        ),
+    ];
+
+    fn documents() -> Vec<String> {
+        ["  Hello  \r\n\r\n\r\nWorld  ", "Hello\n\nWorld", "Straße", "{\"b\": 1, \"a\": [1.0]}", ""]
+            .iter()
+            .map(|doc| doc.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_sha256_vectors() {
+        for (input, expected) in SHA256_VECTORS {
+            assert_eq!(to_hex(&hash_content(input)), expected, "{:?}", input);
+            assert_eq!(to_hex(&hash_bytes(input.as_bytes())), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_normalize_and_hash() {
+        let options = Options::default();
+        assert_eq!(normalize("  Hello  \r\n\r\n\r\nWorld  ", &options), "Hello\n\nWorld");
+        assert_eq!(
+            normalize_and_hash("  Hello  \r\n\r\n\r\nWorld  ", &options, HashAlgorithm::Sha256),
+            to_hex(&hash_content("Hello\n\nWorld"))
+        );
+        assert_eq!(match_key("STRASSE"), hash_content("strasse"));
+        assert_eq!(hash_json_canonical(r#"{"b": 1, "a": [1.0]}"#).unwrap(), hash_content(r#"{"a":[1],"b":1}"#));
+        assert!(hash_json_canonical("{").is_err());
+    }
+
+    #[test]
+    fn test_batches_match_single_calls() {
+        let docs = documents();
+        let options = Options::default();
+
+        let hashes = batch_hash(&docs, HashAlgorithm::Sha256, DigestEncoding::Hex);
+        let expected: Vec<String> = docs.iter().map(|doc| to_hex(&hash_content(doc))).collect();
+        assert_eq!(hashes, expected);
+
+        assert_eq!(
+            batch_normalize_and_hash(&docs, &options, HashAlgorithm::Blake3),
+            batch_hash(&batch_normalize(&docs, &options), HashAlgorithm::Blake3, DigestEncoding::Hex)
+        );
+
+        let groups = dedupe_documents(&docs, &options, HashAlgorithm::Sha256, false);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].indices, vec![0, 1]);
+
+        let replicas: Vec<String> = ["v1", "v2", "v2 "].iter().map(|doc| doc.to_string()).collect();
+        assert_eq!(consensus(&replicas, Some(&options), HashAlgorithm::Sha256).groups[0].indices, vec![1, 2]);
+        assert_eq!(consensus(&replicas, None, HashAlgorithm::Sha256).groups.len(), 3);
+
+        assert_eq!(batch_fingerprint(&docs).len(), docs.len());
+        assert_eq!(batch_hmac(b"key", &docs)[4], keyed::hmac_bytes(b"key", b""));
+    }
+
+    /// The WASM exports must produce exactly the native output
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn test_wasm_exports_match() {
+        use crate::wasm;
+
+        for (input, _) in SHA256_VECTORS {
+            assert_eq!(wasm::hash_content(input).unwrap(), to_hex(&hash_content(input)));
+            assert_eq!(wasm::hash_bytes(input.as_bytes()).unwrap(), to_hex(&hash_bytes(input.as_bytes())));
+        }
+        for doc in documents() {
+            assert_eq!(wasm::normalize_content(&doc).unwrap(), normalize(&doc, &Options::default()));
+            assert_eq!(wasm::match_key(&doc).unwrap(), to_hex(&match_key(&doc)));
+            assert_eq!(wasm::fingerprint(&doc).unwrap(), batch_fingerprint(std::slice::from_ref(&doc))[0]);
+            assert_eq!(wasm::hmac_content(b"key", &doc).unwrap(), batch_hmac(b"key", std::slice::from_ref(&doc))[0]);
+        }
+        let json = r#"{"b": 1, "a": [1.0]}"#;
+        assert_eq!(wasm::hash_json_canonical(json).unwrap(), to_hex(&hash_json_canonical(json).unwrap()));
+    }
+}
@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Deduplication - group documents by content hash
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Indices of documents sharing one hash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DuplicateGroup {
+    pub hash: String,
+    /// Input indices in ascending order
+    pub indices: Vec<usize>,
+}
+
+/// Group input positions by hash
+///
+/// Groups are ordered by the first index in each, so output is
+/// deterministic for a given input order. Groups of one are dropped unless
+/// `include_singletons` is set.
+pub fn group_by_hash(hashes: impl IntoIterator<Item = String>, include_singletons: bool) -> Vec<DuplicateGroup> {
+    let mut groups: Vec<DuplicateGroup> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for (index, hash) in hashes.into_iter().enumerate() {
+        match positions.get(&hash) {
+            Some(&position) => groups[position].indices.push(index),
+            None => {
+                positions.insert(hash.clone(), groups.len());
+                groups.push(DuplicateGroup { hash, indices: vec![index] });
+            }
+        }
+    }
+
+    if !include_singletons {
+        groups.retain(|group| group.indices.len() > 1);
+    }
+    groups
+}
+
+/// How far a set of replicas agree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Consensus {
+    /// Every distinct version, largest group first, ties broken by hash
+    pub groups: Vec<DuplicateGroup>,
+    /// Position in `groups` of the version held by more than half of the
+    /// replicas (always 0 when present)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub majority_index_group: Option<usize>,
+    /// Whether two or more versions share the largest group size
+    pub tie: bool,
+}
+
+/// Group replica hashes and find the majority version
+///
+/// A version held by exactly half of the replicas, or by the largest of
+/// several minority groups, is not a majority; `tie` tells those two cases
+/// apart.
+pub fn consensus(hashes: impl IntoIterator<Item = String>) -> Consensus {
+    let mut groups = group_by_hash(hashes, true);
+    let replicas: usize = groups.iter().map(|group| group.indices.len()).sum();
+    groups.sort_by(|a, b| b.indices.len().cmp(&a.indices.len()).then_with(|| a.hash.cmp(&b.hash)));
+
+    let tie = groups.len() > 1 && groups[0].indices.len() == groups[1].indices.len();
+    let majority_index_group = groups.first().filter(|group| group.indices.len() * 2 > replicas).map(|_| 0);
+    Consensus { groups, majority_index_group, tie }
+}
+
+/// Indices of documents whose hashes share a prefix
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PrefixCollision {
+    pub prefix: String,
+    /// Input indices in ascending order
+    pub indices: Vec<usize>,
+}
+
+/// Group input positions by the first `hex_chars` characters of their hash
+///
+/// Same ordering as `group_by_hash`; only groups of two or more are
+/// returned. Identical documents always collide, so the groups that matter
+/// for choosing a prefix length are those whose full hashes differ.
+pub fn prefix_collisions(hashes: impl IntoIterator<Item = String>, hex_chars: usize) -> Vec<PrefixCollision> {
+    let prefixes = hashes.into_iter().map(|mut hash| {
+        hash.truncate(hex_chars);
+        hash
+    });
+    group_by_hash(prefixes, false)
+        .into_iter()
+        .map(|group| PrefixCollision { prefix: group.hash, indices: group.indices })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{hash_content, normalize_and_hash};
+    use crate::hashing::{to_hex, HashAlgorithm};
+    use crate::normalize::NormalizeOptions;
+
+    fn hashes(docs: &[String]) -> Vec<String> {
+        docs.iter()
+            .map(|doc| normalize_and_hash(doc, &NormalizeOptions::default(), HashAlgorithm::Sha256))
+            .collect()
+    }
+
+    #[test]
+    fn test_groups_follow_input_order() {
+        let docs: Vec<String> = ["b", "a", "b  \r\n", "c", "  a", "b"].iter().map(|s| s.to_string()).collect();
+        let groups = group_by_hash(hashes(&docs), false);
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].indices, vec![0, 2, 5]);
+        assert_eq!(groups[0].hash, to_hex(&hash_content("b")));
+        assert_eq!(groups[1].indices, vec![1, 4]);
+    }
+
+    #[test]
+    fn test_singletons_flag() {
+        let docs: Vec<String> = ["x", "y", "x"].iter().map(|s| s.to_string()).collect();
+        let with = group_by_hash(hashes(&docs), true);
+        assert_eq!(with.iter().map(|g| g.indices.clone()).collect::<Vec<_>>(), vec![vec![0, 2], vec![1]]);
+        assert_eq!(group_by_hash(hashes(&docs), false).len(), 1);
+        assert!(group_by_hash(Vec::new(), true).is_empty());
+    }
+
+    fn consensus_of(docs: &[&str]) -> Consensus {
+        let docs: Vec<String> = docs.iter().map(|s| s.to_string()).collect();
+        consensus(hashes(&docs))
+    }
+
+    fn group_indices(consensus: &Consensus) -> Vec<Vec<usize>> {
+        consensus.groups.iter().map(|group| group.indices.clone()).collect()
+    }
+
+    #[test]
+    fn test_consensus_clean_majority() {
+        let result = consensus_of(&["v2", "v1", "v2  \n", "v2", "v3"]);
+        assert_eq!(group_indices(&result), vec![vec![0, 2, 3], vec![1], vec![4]]);
+        assert_eq!(result.majority_index_group, Some(0));
+        assert!(!result.tie);
+        assert_eq!(result.groups[0].hash, to_hex(&hash_content("v2")));
+    }
+
+    #[test]
+    fn test_consensus_two_two_one_tie() {
+        let result = consensus_of(&["b", "a", "b", "a", "c"]);
+        assert_eq!(result.majority_index_group, None);
+        assert!(result.tie);
+        // Equal-sized groups are ordered by hash
+        let (a, b) = (to_hex(&hash_content("a")), to_hex(&hash_content("b")));
+        let expected = if a < b { vec![vec![1, 3], vec![0, 2], vec![4]] } else { vec![vec![0, 2], vec![1, 3], vec![4]] };
+        assert_eq!(group_indices(&result), expected);
+
+        // The order of versions does not depend on which replica came first
+        let reordered = consensus_of(&["a", "b", "b", "a", "c"]);
+        let hashes = |c: &Consensus| c.groups.iter().map(|g| g.hash.clone()).collect::<Vec<_>>();
+        assert_eq!(hashes(&reordered), hashes(&result));
+    }
+
+    #[test]
+    fn test_consensus_all_identical_and_all_distinct() {
+        let same = consensus_of(&["x", "x ", "\nx"]);
+        assert_eq!(group_indices(&same), vec![vec![0, 1, 2]]);
+        assert_eq!((same.majority_index_group, same.tie), (Some(0), false));
+
+        let distinct = consensus_of(&["x", "y", "z", "w"]);
+        assert_eq!(distinct.groups.len(), 4);
+        assert_eq!((distinct.majority_index_group, distinct.tie), (None, true));
+        let hashes: Vec<&String> = distinct.groups.iter().map(|g| &g.hash).collect();
+        assert!(hashes.windows(2).all(|pair| pair[0] < pair[1]));
+
+        // Half is not a majority
+        let half = consensus_of(&["x", "x", "y", "z"]);
+        assert_eq!((half.majority_index_group, half.tie), (None, false));
+
+        let empty = consensus_of(&[]);
+        assert!(empty.groups.is_empty());
+        assert_eq!((empty.majority_index_group, empty.tie), (None, false));
+    }
+
+    #[test]
+    fn test_prefix_collisions() {
+        // SHA-256 of "doc-17633" and "doc-29150" share their first 8 hex
+        // characters (23590e50) but not the first 9
+        let docs = ["doc-17633", "unrelated", "doc-29150", "doc-17633"];
+        let hashes = || docs.iter().map(|doc| to_hex(&hash_content(doc)));
+
+        let short = prefix_collisions(hashes(), 8);
+        assert_eq!(short, vec![PrefixCollision { prefix: "23590e50".to_string(), indices: vec![0, 2, 3] }]);
+
+        // At 16 characters only the true duplicate remains
+        let long = prefix_collisions(hashes(), 16);
+        assert_eq!(long.len(), 1);
+        assert_eq!(long[0].indices, vec![0, 3]);
+        assert_eq!(long[0].prefix, &to_hex(&hash_content("doc-17633"))[..16]);
+
+        assert_eq!(prefix_collisions(hashes(), 9)[0].indices, vec![0, 3]);
+    }
+
+    #[test]
+    fn test_large_synthetic_corpus() {
+        // 5000 documents drawn from 1000 distinct bodies with whitespace noise
+        let docs: Vec<String> = (0..5000)
+            .map(|i| {
+                let n = i % 1000;
+                let body = format!("record {}\nowner team-{}\nstatus active\n", n, n % 7);
+                if i % 2 == 0 {
+                    body
+                } else {
+                    format!("  {}\r\n\r\n", body.replace('\n', "  \r\n"))
+                }
+            })
+            .collect();
+
+        let groups = group_by_hash(hashes(&docs), true);
+        assert_eq!(groups.len(), 1000);
+        for (g, group) in groups.iter().enumerate() {
+            assert_eq!(group.indices, (0..5).map(|k| g + 1000 * k).collect::<Vec<_>>());
+        }
+    }
+}
@@ -0,0 +1,332 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Binary deltas - copy/insert patches for content that is not text
+//
+// A patch is:
+//
+//     "RDLT"                  magic
+//     0x01                    format version
+//     varint                  source length
+//     varint                  target length
+//     32 bytes                SHA-256 of the target
+//     opcodes until the end:
+//       0x01 varint varint    COPY length bytes from this source offset
+//       0x02 varint bytes     INSERT this many literal bytes
+//
+// Varints are unsigned LEB128. Matching follows rsync: the source is
+// indexed by its aligned BLOCK-byte blocks, every offset of the target is
+// looked up, and each hit is extended in both directions byte by byte. A
+// local edit costs a few bytes of opcodes around the changed bytes, and a
+// patch between identical inputs is the header, about 40 bytes and mostly
+// the digest, plus one COPY.
+//
+// Applying checks the source length, bounds-checks every COPY and compares
+// the SHA-256 of the result with the one recorded, so a corrupted patch or
+// the wrong source document fails instead of producing different bytes.
+
+use crate::error::{ErrorCode, ReconError};
+use crate::hashing::{digests_equal, sha256};
+use crate::varint::{self, VarintError};
+use std::collections::HashMap;
+
+const MAGIC: &[u8; 4] = b"RDLT";
+/// Current patch format version
+pub const VERSION: u8 = 1;
+
+const COPY: u8 = 0x01;
+const INSERT: u8 = 0x02;
+
+/// Size of the source blocks that seed matches
+const BLOCK: usize = 16;
+
+/// Patch turning `old` into `new`
+pub fn diff(old: &[u8], new: &[u8]) -> Vec<u8> {
+    let mut patch = Vec::with_capacity(64);
+    patch.extend_from_slice(MAGIC);
+    patch.push(VERSION);
+    varint::write(old.len() as u64, &mut patch);
+    varint::write(new.len() as u64, &mut patch);
+    patch.extend_from_slice(&sha256(new));
+
+    // First occurrence of every aligned block of the source
+    let mut blocks: HashMap<&[u8], usize> = HashMap::with_capacity(old.len() / BLOCK);
+    for (i, block) in old.chunks_exact(BLOCK).enumerate() {
+        blocks.entry(block).or_insert(i * BLOCK);
+    }
+
+    let mut literal_start = 0;
+    let mut pos = 0;
+    while pos + BLOCK <= new.len() {
+        let Some(&found) = blocks.get(&new[pos..pos + BLOCK]) else {
+            pos += 1;
+            continue;
+        };
+
+        let (mut start, mut source) = (pos, found);
+        while start > literal_start && source > 0 && new[start - 1] == old[source - 1] {
+            start -= 1;
+            source -= 1;
+        }
+        let mut end = pos + BLOCK;
+        while end < new.len() && source + (end - start) < old.len() && new[end] == old[source + (end - start)] {
+            end += 1;
+        }
+
+        write_insert(&mut patch, &new[literal_start..start]);
+        patch.push(COPY);
+        varint::write(source as u64, &mut patch);
+        varint::write((end - start) as u64, &mut patch);
+        literal_start = end;
+        pos = end;
+    }
+    write_insert(&mut patch, &new[literal_start..]);
+    patch
+}
+
+/// Rebuild the target of `patch` from `old`
+///
+/// Fails with `decode_error` for a malformed patch, `unsupported_version`
+/// for a newer format, `patch_rejected` when `old` is not the source the
+/// patch was made from, and `integrity_mismatch` when the result does not
+/// hash to the recorded digest.
+pub fn apply(old: &[u8], patch: &[u8]) -> Result<Vec<u8>, ReconError> {
+    let mut reader = Reader { data: patch, pos: 0 };
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(ReconError::decode("Not a binary patch"));
+    }
+    let version = reader.byte()?;
+    if version != VERSION {
+        return Err(ReconError::new(
+            ErrorCode::UnsupportedVersion,
+            format!("Unsupported binary patch version {}", version),
+        ));
+    }
+    let source_len = reader.length()?;
+    let target_len = reader.length()?;
+    let digest: [u8; 32] = reader.take(32)?.try_into().expect("took 32 bytes");
+    if source_len != old.len() {
+        return Err(rejected(format!("Patch is for a {}-byte source, got {} bytes", source_len, old.len())));
+    }
+
+    // Never trust the header with a large allocation
+    let mut target = Vec::with_capacity(target_len.min(old.len() + patch.len()));
+    while !reader.is_empty() {
+        let op_offset = reader.pos;
+        let piece = match reader.byte()? {
+            COPY => {
+                let (offset, len) = (reader.length()?, reader.length()?);
+                old.get(offset..offset.saturating_add(len))
+                    .ok_or_else(|| rejected(format!("Copy of {} bytes at {} is outside the source", len, offset)))?
+            }
+            INSERT => {
+                let len = reader.length()?;
+                reader.take(len)?
+            }
+            op => return Err(ReconError::decode(format!("Unknown opcode {:#04x} at byte {}", op, op_offset))),
+        };
+        if piece.len() > target_len - target.len() {
+            return Err(ReconError::decode(format!("Opcode at byte {} overruns the target length", op_offset)));
+        }
+        target.extend_from_slice(piece);
+    }
+
+    if target.len() != target_len {
+        return Err(ReconError::decode(format!("Patch produced {} bytes, header says {}", target.len(), target_len)));
+    }
+    if !digests_equal(&sha256(&target), &digest) {
+        return Err(ReconError::new(ErrorCode::IntegrityMismatch, "Patched content does not match its SHA-256"));
+    }
+    Ok(target)
+}
+
+fn rejected(message: String) -> ReconError {
+    ReconError::new(ErrorCode::PatchRejected, message)
+}
+
+fn write_insert(patch: &mut Vec<u8>, literal: &[u8]) {
+    if literal.is_empty() {
+        return;
+    }
+    patch.push(INSERT);
+    varint::write(literal.len() as u64, patch);
+    patch.extend_from_slice(literal);
+}
+
+/// Cursor over patch bytes; running out is a `decode_error`
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn is_empty(&self) -> bool {
+        self.pos == self.data.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ReconError> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos.saturating_add(len))
+            .ok_or_else(|| ReconError::decode(format!("Binary patch truncated at byte {}", self.data.len())))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, ReconError> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// A minimally-encoded varint that must fit in `usize`
+    fn length(&mut self) -> Result<usize, ReconError> {
+        let start = self.pos;
+        let (value, len) = varint::read(&self.data[start..], varint::MAX_LEN).map_err(|e| match e {
+            VarintError::Truncated => ReconError::decode(format!("Binary patch truncated at byte {}", self.data.len())),
+            VarintError::TooLong => ReconError::decode(format!("Varint at byte {} is too long", start)),
+            VarintError::NotMinimal => ReconError::decode(format!("Varint at byte {} is not minimally encoded", start)),
+            VarintError::Overflow => ReconError::decode(format!("Varint at byte {} is too large", start)),
+        })?;
+        self.pos += len;
+        usize::try_from(value).map_err(|_| ReconError::decode(format!("Varint at byte {} is too large", start)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::random_bytes;
+
+    fn round_trip(old: &[u8], new: &[u8]) -> Vec<u8> {
+        let patch = diff(old, new);
+        assert_eq!(apply(old, &patch).unwrap(), new);
+        patch
+    }
+
+    #[test]
+    fn test_identical_inputs_give_a_tiny_patch() {
+        let blob = random_bytes(1 << 20, 1);
+        // 43 header bytes at this size, 5 for the COPY
+        assert_eq!(round_trip(&blob, &blob).len(), 48);
+        assert_eq!(round_trip(b"", b"").len(), 4 + 1 + 1 + 1 + 32);
+    }
+
+    #[test]
+    fn test_one_byte_change_in_a_megabyte() {
+        let old = random_bytes(1 << 20, 2);
+        let mut new = old.clone();
+        new[700_001] ^= 0x40;
+        assert!(round_trip(&old, &new).len() < 80);
+
+        let mut inserted = old.clone();
+        inserted.insert(12_345, 0xaa);
+        assert!(round_trip(&old, &inserted).len() < 80);
+    }
+
+    #[test]
+    fn test_random_round_trips() {
+        for seed in 1..40u64 {
+            let old = random_bytes((seed as usize * 997) % 5000, seed);
+            let noise = random_bytes(64, seed + 1000);
+            let mut new = old.clone();
+            for (i, &b) in noise.iter().enumerate().take((seed % 8) as usize) {
+                let at = (b as usize * 31 + i * 101) % (new.len() + 1);
+                match i % 3 {
+                    0 => new.insert(at, b),
+                    1 if at < new.len() => {
+                        new.remove(at);
+                    }
+                    _ if at < new.len() => new[at] ^= b | 1,
+                    _ => new.push(b),
+                }
+            }
+            round_trip(&old, &new);
+            // Unrelated content degrades to one literal insert
+            round_trip(&old, &random_bytes(old.len() / 2, seed + 7));
+            round_trip(&[], &old);
+            round_trip(&old, &[]);
+        }
+    }
+
+    #[test]
+    fn test_reordered_blocks_are_copied() {
+        let a = random_bytes(4096, 3);
+        let b = random_bytes(4096, 4);
+        let old = [a.clone(), b.clone()].concat();
+        let new = [b, a].concat();
+        assert!(round_trip(&old, &new).len() < 60);
+    }
+
+    #[test]
+    fn test_corruption_is_detected() {
+        let old = random_bytes(10_000, 5);
+        let mut new = old.clone();
+        new.splice(5_000..5_010, b"0123456789abcdef".iter().copied());
+        let patch = diff(&old, &new);
+
+        let code = |old: &[u8], patch: &[u8]| apply(old, patch).unwrap_err().code;
+
+        // A flipped literal byte, and a flipped digest byte
+        let literal = patch.windows(16).position(|w| w == b"0123456789abcdef").unwrap();
+        let mut corrupted = patch.clone();
+        corrupted[literal + 3] ^= 1;
+        assert_eq!(code(&old, &corrupted), ErrorCode::IntegrityMismatch);
+        let mut corrupted = patch.clone();
+        corrupted[12] ^= 1;
+        assert_eq!(code(&old, &corrupted), ErrorCode::IntegrityMismatch);
+
+        // A different source of the same length
+        let mut other = old.clone();
+        other[100] ^= 1;
+        assert_eq!(code(&other, &patch), ErrorCode::IntegrityMismatch);
+        assert_eq!(code(&old[1..], &patch), ErrorCode::PatchRejected);
+
+        assert_eq!(code(&old, &patch[..patch.len() - 1]), ErrorCode::DecodeError);
+        assert_eq!(code(&old, b"nope"), ErrorCode::DecodeError);
+        let mut future = patch.clone();
+        future[4] = 2;
+        assert_eq!(code(&old, &future), ErrorCode::UnsupportedVersion);
+    }
+
+    #[test]
+    fn test_hostile_headers_and_copies() {
+        let mut patch = MAGIC.to_vec();
+        patch.push(VERSION);
+        varint::write(3, &mut patch);
+        varint::write(u32::MAX as u64, &mut patch);
+        patch.extend_from_slice(&[0; 32]);
+        let mut copy = patch.clone();
+        copy.push(COPY);
+        varint::write(2, &mut copy);
+        varint::write(u32::MAX as u64, &mut copy);
+        assert_eq!(apply(b"abc", &copy).unwrap_err().code, ErrorCode::PatchRejected);
+        // Claims four billion bytes but holds none
+        assert_eq!(apply(b"abc", &patch).unwrap_err().code, ErrorCode::DecodeError);
+        let mut overlong = patch.clone();
+        overlong.push(INSERT);
+        overlong.extend_from_slice(&[0xff; 11]);
+        assert_eq!(apply(b"abc", &overlong).unwrap_err().code, ErrorCode::DecodeError);
+    }
+
+    #[test]
+    fn test_lengths_must_be_canonical() {
+        let header = |source_len: &[u8]| {
+            let mut patch = MAGIC.to_vec();
+            patch.push(VERSION);
+            patch.extend_from_slice(source_len);
+            patch.push(0);
+            patch.extend_from_slice(&sha256(b""));
+            apply(b"abc", &patch)
+        };
+        assert_eq!(header(&[0x03]).unwrap(), b"");
+        // 3 padded with a zero group
+        let err = header(&[0x83, 0x00]).unwrap_err();
+        assert_eq!(err.code, ErrorCode::DecodeError);
+        assert_eq!(err.message, "Varint at byte 5 is not minimally encoded");
+        // 3 plus bits past the 64th, which a lax decoder drops
+        let mut wrapped = vec![0x83];
+        wrapped.extend_from_slice(&[0x80; 8]);
+        wrapped.push(0x02);
+        let err = header(&wrapped).unwrap_err();
+        assert_eq!(err.code, ErrorCode::DecodeError);
+        assert_eq!(err.message, "Varint at byte 5 is too large");
+    }
+}
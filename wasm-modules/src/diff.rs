@@ -0,0 +1,580 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Document diffing - Myers diff over lines, words or grapheme clusters
+//
+// Uses the linear-space variant of Myers' O(ND) algorithm: common
+// prefixes/suffixes are stripped, then the middle snake of the remaining
+// region is found and both halves are solved recursively.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::{Index, IndexMut, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Kind of change a hunk describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+/// A contiguous run of tokens sharing the same operation
+///
+/// Starts are 0-based token indices; for inserts `old_start` is the position
+/// in the old document the tokens go before, and likewise `new_start` for
+/// deletes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hunk {
+    pub op: DiffOp,
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    /// Tokens covered by the hunk; line tokens keep their terminator so
+    /// concatenation reproduces the document exactly
+    pub lines: Vec<String>,
+}
+
+/// Unit of comparison for text diffs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    /// Lines, each keeping its terminator
+    #[default]
+    Line,
+    /// UAX #29 word boundaries; runs of whitespace and punctuation are
+    /// tokens of their own
+    Word,
+    /// Extended grapheme clusters, so emoji sequences and combining marks
+    /// are never split
+    Char,
+}
+
+/// Options for text diffs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiffOptions {
+    /// Convert `\r\n` and lone `\r` to `\n` on both sides before diffing
+    pub normalize_line_endings: bool,
+    pub granularity: Granularity,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions {
+            normalize_line_endings: true,
+            granularity: Granularity::Line,
+        }
+    }
+}
+
+/// An operation over index ranges of the two token sequences
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub op: DiffOp,
+    pub old: Range<usize>,
+    pub new: Range<usize>,
+}
+
+/// Diff between two documents at the granularity set in `options`
+pub fn diff_text(old: &str, new: &str, options: &DiffOptions) -> Vec<Hunk> {
+    let (old, new) = if options.normalize_line_endings {
+        (normalize_line_endings(old), normalize_line_endings(new))
+    } else {
+        (old.to_string(), new.to_string())
+    };
+
+    let old_tokens = tokenize(&old, options.granularity);
+    let new_tokens = tokenize(&new, options.granularity);
+
+    diff_tokens(&old_tokens, &new_tokens)
+}
+
+/// Split text into tokens whose concatenation is the original text
+pub fn tokenize(text: &str, granularity: Granularity) -> Vec<&str> {
+    match granularity {
+        Granularity::Line => text.split_inclusive('\n').collect(),
+        Granularity::Word => text.split_word_bounds().collect(),
+        Granularity::Char => text.graphemes(true).collect(),
+    }
+}
+
+/// Diff two token sequences and materialize the hunks
+pub fn diff_tokens(old: &[&str], new: &[&str]) -> Vec<Hunk> {
+    // Intern tokens so the diff compares integers rather than strings
+    let mut ids: HashMap<&str, usize> = HashMap::new();
+    let old_ids = intern(old, &mut ids);
+    let new_ids = intern(new, &mut ids);
+
+    diff_slices(&old_ids, &new_ids)
+        .into_iter()
+        .map(|span| {
+            let tokens = match span.op {
+                DiffOp::Insert => &new[span.new.clone()],
+                DiffOp::Equal | DiffOp::Delete => &old[span.old.clone()],
+            };
+            Hunk {
+                op: span.op,
+                old_start: span.old.start,
+                old_lines: span.old.len(),
+                new_start: span.new.start,
+                new_lines: span.new.len(),
+                lines: tokens.iter().map(|t| t.to_string()).collect(),
+            }
+        })
+        .collect()
+}
+
+/// Map each token to a small integer id shared across both sides
+pub(crate) fn intern<'a>(tokens: &[&'a str], ids: &mut HashMap<&'a str, usize>) -> Vec<usize> {
+    tokens
+        .iter()
+        .map(|token| {
+            let next = ids.len();
+            *ids.entry(token).or_insert(next)
+        })
+        .collect()
+}
+
+/// Minimal edit script between two slices, as merged spans in order
+pub fn diff_slices<T: Eq>(old: &[T], new: &[T]) -> Vec<Span> {
+    let max_d = max_d(old.len(), new.len());
+    let mut vf = V::new(max_d);
+    let mut vb = V::new(max_d);
+    let mut recorder = Recorder::default();
+
+    conquer(old, 0..old.len(), new, 0..new.len(), &mut vf, &mut vb, &mut recorder);
+
+    canonicalize(recorder.spans)
+}
+
+/// Rewrite every run of inserts/deletes between equal spans as one delete
+/// followed by one insert
+fn canonicalize(spans: Vec<Span>) -> Vec<Span> {
+    let mut out = Recorder::default();
+    let mut change: Option<(Range<usize>, Range<usize>)> = None;
+
+    let flush = |out: &mut Recorder, change: &mut Option<(Range<usize>, Range<usize>)>| {
+        if let Some((old, new)) = change.take() {
+            out.push(DiffOp::Delete, old.clone(), new.start..new.start);
+            out.push(DiffOp::Insert, old.end..old.end, new);
+        }
+    };
+
+    for span in spans {
+        if span.op == DiffOp::Equal {
+            flush(&mut out, &mut change);
+            out.push(span.op, span.old, span.new);
+            continue;
+        }
+        change = Some(match change {
+            Some((old, new)) => (old.start..span.old.end, new.start..span.new.end),
+            None => (span.old, span.new),
+        });
+    }
+    flush(&mut out, &mut change);
+
+    out.spans
+}
+
+/// Convert `\r\n` and lone `\r` to `\n`
+pub fn normalize_line_endings(content: &str) -> String {
+    content.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Collects operations, merging adjacent runs of the same kind
+#[derive(Default)]
+struct Recorder {
+    spans: Vec<Span>,
+}
+
+impl Recorder {
+    fn push(&mut self, op: DiffOp, old: Range<usize>, new: Range<usize>) {
+        if old.is_empty() && new.is_empty() {
+            return;
+        }
+        if let Some(last) = self.spans.last_mut() {
+            if last.op == op && last.old.end == old.start && last.new.end == new.start {
+                last.old.end = old.end;
+                last.new.end = new.end;
+                return;
+            }
+        }
+        self.spans.push(Span { op, old, new });
+    }
+}
+
+/// Diagonal-indexed vector of furthest-reaching x positions
+struct V {
+    offset: isize,
+    v: Vec<usize>,
+}
+
+impl V {
+    fn new(max_d: usize) -> Self {
+        V {
+            offset: max_d as isize,
+            v: vec![0; 2 * max_d + 1],
+        }
+    }
+}
+
+impl Index<isize> for V {
+    type Output = usize;
+
+    fn index(&self, k: isize) -> &usize {
+        &self.v[(k + self.offset) as usize]
+    }
+}
+
+impl IndexMut<isize> for V {
+    fn index_mut(&mut self, k: isize) -> &mut usize {
+        &mut self.v[(k + self.offset) as usize]
+    }
+}
+
+fn max_d(old_len: usize, new_len: usize) -> usize {
+    (old_len + new_len).div_ceil(2) + 1
+}
+
+fn common_prefix_len<T: Eq>(old: &[T], new: &[T]) -> usize {
+    old.iter().zip(new).take_while(|(a, b)| a == b).count()
+}
+
+fn common_suffix_len<T: Eq>(old: &[T], new: &[T]) -> usize {
+    old.iter().rev().zip(new.iter().rev()).take_while(|(a, b)| a == b).count()
+}
+
+/// Find a point on an optimal edit path splitting the problem in two
+fn find_middle_snake<T: Eq>(
+    old: &[T],
+    old_range: Range<usize>,
+    new: &[T],
+    new_range: Range<usize>,
+    vf: &mut V,
+    vb: &mut V,
+) -> Option<(usize, usize)> {
+    let n = old_range.len();
+    let m = new_range.len();
+    let delta = n as isize - m as isize;
+    let odd = delta & 1 == 1;
+
+    vf[1] = 0;
+    vb[1] = 0;
+
+    for d in 0..max_d(n, m) as isize {
+        // Forward search
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && vf[k - 1] < vf[k + 1]) {
+                vf[k + 1]
+            } else {
+                vf[k - 1] + 1
+            };
+            let y = (x as isize - k) as usize;
+            let (x0, y0) = (x, y);
+            if x < n && y < m {
+                x += common_prefix_len(
+                    &old[old_range.start + x..old_range.end],
+                    &new[new_range.start + y..new_range.end],
+                );
+            }
+            vf[k] = x;
+            if odd && (k - delta).abs() < d && vf[k] + vb[-(k - delta)] >= n {
+                return Some((x0 + old_range.start, y0 + new_range.start));
+            }
+        }
+
+        // Backward search
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && vb[k - 1] < vb[k + 1]) {
+                vb[k + 1]
+            } else {
+                vb[k - 1] + 1
+            };
+            let mut y = (x as isize - k) as usize;
+            if x < n && y < m {
+                let advance = common_suffix_len(
+                    &old[old_range.start..old_range.start + n - x],
+                    &new[new_range.start..new_range.start + m - y],
+                );
+                x += advance;
+                y += advance;
+            }
+            vb[k] = x;
+            if !odd && (k - delta).abs() <= d && vb[k] + vf[-(k - delta)] >= n {
+                return Some((n - x + old_range.start, m - y + new_range.start));
+            }
+        }
+    }
+
+    None
+}
+
+fn conquer<T: Eq>(
+    old: &[T],
+    mut old_range: Range<usize>,
+    new: &[T],
+    mut new_range: Range<usize>,
+    vf: &mut V,
+    vb: &mut V,
+    recorder: &mut Recorder,
+) {
+    // Common prefix
+    let prefix = common_prefix_len(&old[old_range.clone()], &new[new_range.clone()]);
+    if prefix > 0 {
+        recorder.push(
+            DiffOp::Equal,
+            old_range.start..old_range.start + prefix,
+            new_range.start..new_range.start + prefix,
+        );
+        old_range.start += prefix;
+        new_range.start += prefix;
+    }
+
+    // Common suffix, recorded after the middle is solved
+    let suffix = common_suffix_len(&old[old_range.clone()], &new[new_range.clone()]);
+    let old_suffix = old_range.end - suffix..old_range.end;
+    let new_suffix = new_range.end - suffix..new_range.end;
+    old_range.end -= suffix;
+    new_range.end -= suffix;
+
+    if old_range.is_empty() {
+        recorder.push(DiffOp::Insert, old_range.start..old_range.start, new_range);
+    } else if new_range.is_empty() {
+        recorder.push(DiffOp::Delete, old_range, new_range.start..new_range.start);
+    } else if let Some((x, y)) =
+        find_middle_snake(old, old_range.clone(), new, new_range.clone(), vf, vb)
+    {
+        conquer(old, old_range.start..x, new, new_range.start..y, vf, vb, recorder);
+        conquer(old, x..old_range.end, new, y..new_range.end, vf, vb, recorder);
+    } else {
+        recorder.push(DiffOp::Delete, old_range.clone(), new_range.start..new_range.start);
+        recorder.push(DiffOp::Insert, old_range.end..old_range.end, new_range);
+    }
+
+    if suffix > 0 {
+        recorder.push(DiffOp::Equal, old_suffix, new_suffix);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Rng;
+
+    /// Apply hunks to `old`, copying equal runs from the old document itself
+    fn apply(old: &str, hunks: &[Hunk], granularity: Granularity) -> String {
+        let old_lines = tokenize(old, granularity);
+        let mut out = String::new();
+        for hunk in hunks {
+            match hunk.op {
+                DiffOp::Equal => {
+                    for line in &old_lines[hunk.old_start..hunk.old_start + hunk.old_lines] {
+                        out.push_str(line);
+                    }
+                }
+                DiffOp::Insert => out.extend(hunk.lines.iter().map(String::as_str)),
+                DiffOp::Delete => {}
+            }
+        }
+        out
+    }
+
+    /// Number of inserted plus deleted tokens
+    fn edit_count(spans: &[Span]) -> usize {
+        spans
+            .iter()
+            .filter(|s| s.op != DiffOp::Equal)
+            .map(|s| s.old.len() + s.new.len())
+            .sum()
+    }
+
+    /// Reference LCS length via quadratic DP
+    fn lcs_len(a: &[u8], b: &[u8]) -> usize {
+        let mut dp = vec![vec![0; b.len() + 1]; a.len() + 1];
+        for i in 0..a.len() {
+            for j in 0..b.len() {
+                dp[i + 1][j + 1] = if a[i] == b[j] { dp[i][j] + 1 } else { dp[i][j + 1].max(dp[i + 1][j]) };
+            }
+        }
+        dp[a.len()][b.len()]
+    }
+
+    fn assert_round_trip(old: &str, new: &str) {
+        let options = DiffOptions { normalize_line_endings: false, ..DiffOptions::default() };
+        let hunks = diff_text(old, new, &options);
+        assert_eq!(apply(old, &hunks, Granularity::Line), new, "old {:?} new {:?}", old, new);
+
+        // Within a change, deletes always precede inserts
+        for pair in hunks.windows(2) {
+            assert!(!(pair[0].op == DiffOp::Insert && pair[1].op == DiffOp::Delete));
+        }
+
+        // Positions are contiguous on both sides
+        let (mut o, mut n) = (0, 0);
+        for hunk in &hunks {
+            assert_eq!((hunk.old_start, hunk.new_start), (o, n));
+            o += hunk.old_lines;
+            n += hunk.new_lines;
+        }
+    }
+
+    #[test]
+    fn test_diff_identical() {
+        let hunks = diff_text("a\nb\n", "a\nb\n", &DiffOptions::default());
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].op, DiffOp::Equal);
+        assert_eq!(hunks[0].lines, vec!["a\n", "b\n"]);
+    }
+
+    #[test]
+    fn test_diff_single_change() {
+        let hunks = diff_text("a\nb\nc\n", "a\nx\nc\n", &DiffOptions::default());
+        let ops: Vec<DiffOp> = hunks.iter().map(|h| h.op).collect();
+        assert_eq!(ops, vec![DiffOp::Equal, DiffOp::Delete, DiffOp::Insert, DiffOp::Equal]);
+        assert_eq!(hunks[1].lines, vec!["b\n"]);
+        assert_eq!((hunks[1].old_start, hunks[1].old_lines), (1, 1));
+        assert_eq!(hunks[2].lines, vec!["x\n"]);
+        assert_eq!((hunks[2].new_start, hunks[2].new_lines), (1, 1));
+    }
+
+    #[test]
+    fn test_diff_empty_sides() {
+        assert!(diff_text("", "", &DiffOptions::default()).is_empty());
+
+        let inserted = diff_text("", "a\nb", &DiffOptions::default());
+        assert_eq!(inserted.len(), 1);
+        assert_eq!(inserted[0].op, DiffOp::Insert);
+        assert_eq!(inserted[0].new_lines, 2);
+
+        let deleted = diff_text("a\nb", "", &DiffOptions::default());
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].op, DiffOp::Delete);
+        assert_eq!(deleted[0].old_lines, 2);
+    }
+
+    #[test]
+    fn test_diff_missing_trailing_newline() {
+        let hunks = diff_text("a\nb\n", "a\nb", &DiffOptions::default());
+        let ops: Vec<DiffOp> = hunks.iter().map(|h| h.op).collect();
+        assert_eq!(ops, vec![DiffOp::Equal, DiffOp::Delete, DiffOp::Insert]);
+        assert_eq!(hunks[2].lines, vec!["b"]);
+        assert_round_trip("a\nb\n", "a\nb");
+        assert_round_trip("a\nb", "a\nb\n");
+    }
+
+    #[test]
+    fn test_diff_normalizes_line_endings() {
+        let hunks = diff_text("a\r\nb\r\n", "a\nb\n", &DiffOptions::default());
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].op, DiffOp::Equal);
+
+        let raw = diff_text("a\r\nb\r\n", "a\nb\n", &DiffOptions { normalize_line_endings: false, ..DiffOptions::default() });
+        assert!(raw.iter().any(|h| h.op != DiffOp::Equal));
+    }
+
+    #[test]
+    fn test_diff_round_trip_fixtures() {
+        let fixtures = [
+            ("", ""),
+            ("", "x\n"),
+            ("x\n", ""),
+            ("a\nb\nc\nd\n", "a\nc\nd\ne\n"),
+            ("one\ntwo\nthree", "zero\none\nthree\nfour"),
+            ("a\na\na\nb\n", "b\na\na\na\n"),
+            ("same\n", "same\n"),
+        ];
+        for (old, new) in fixtures {
+            assert_round_trip(old, new);
+            assert_round_trip(new, old);
+        }
+    }
+
+    #[test]
+    fn test_diff_random_round_trip_and_minimality() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..500 {
+            let old: Vec<u8> = (0..rng.next() % 30).map(|_| b'a' + (rng.next() % 4) as u8).collect();
+            let new: Vec<u8> = (0..rng.next() % 30).map(|_| b'a' + (rng.next() % 4) as u8).collect();
+
+            let spans = diff_slices(&old, &new);
+            let lcs = lcs_len(&old, &new);
+            assert_eq!(edit_count(&spans), old.len() + new.len() - 2 * lcs);
+
+            let to_doc = |bytes: &[u8]| bytes.iter().map(|b| format!("{}\n", *b as char)).collect::<String>();
+            assert_round_trip(&to_doc(&old), &to_doc(&new));
+        }
+    }
+
+    #[test]
+    fn test_diff_large_input() {
+        let old: String = (0..20_000).map(|i| format!("line {}\n", i)).collect();
+        let new: String = (0..20_000)
+            .map(|i| if i % 1000 == 0 { format!("changed {}\n", i) } else { format!("line {}\n", i) })
+            .collect();
+        assert_round_trip(&old, &new);
+    }
+
+    fn word_diff(old: &str, new: &str) -> Vec<Hunk> {
+        diff_text(old, new, &DiffOptions { granularity: Granularity::Word, ..DiffOptions::default() })
+    }
+
+    fn char_diff(old: &str, new: &str) -> Vec<Hunk> {
+        diff_text(old, new, &DiffOptions { granularity: Granularity::Char, ..DiffOptions::default() })
+    }
+
+    #[test]
+    fn test_word_diff_is_tight_around_changed_word() {
+        let sentence = "The reconciliation engine compares every document in the bundle against its canonical source. ";
+        let old = format!("{}The quick brown fox jumps over the lazy dog. {}", sentence.repeat(5), sentence.repeat(5));
+        let new = old.replacen("quick", "quack", 1);
+
+        let hunks = word_diff(&old, &new);
+        let ops: Vec<DiffOp> = hunks.iter().map(|h| h.op).collect();
+        assert_eq!(ops, vec![DiffOp::Equal, DiffOp::Delete, DiffOp::Insert, DiffOp::Equal]);
+        assert_eq!(hunks[1].lines, vec!["quick"]);
+        assert_eq!(hunks[2].lines, vec!["quack"]);
+        assert_eq!(hunks[0].lines.concat(), old[..old.find("quick").unwrap()]);
+        assert_eq!(apply(&old, &hunks, Granularity::Word), new);
+    }
+
+    #[test]
+    fn test_word_diff_keeps_whitespace_and_punctuation() {
+        let hunks = word_diff("Hello, world!", "Hello,  world?");
+        let changed: Vec<&str> =
+            hunks.iter().filter(|h| h.op != DiffOp::Equal).flat_map(|h| h.lines.iter().map(String::as_str)).collect();
+        assert_eq!(changed, vec![" ", "  ", "!", "?"]);
+        assert_eq!(apply("Hello, world!", &hunks, Granularity::Word), "Hello,  world?");
+    }
+
+    #[test]
+    fn test_char_diff_respects_grapheme_clusters() {
+        // Family emoji (ZWJ sequence) and a decomposed é stay whole
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let old = format!("cafe\u{301} {}", family);
+        let new = format!("cafe {}!", family);
+
+        let hunks = char_diff(&old, &new);
+        let deleted: Vec<&str> =
+            hunks.iter().filter(|h| h.op == DiffOp::Delete).flat_map(|h| h.lines.iter().map(String::as_str)).collect();
+        let inserted: Vec<&str> =
+            hunks.iter().filter(|h| h.op == DiffOp::Insert).flat_map(|h| h.lines.iter().map(String::as_str)).collect();
+        assert_eq!(deleted, vec!["e\u{301}"]);
+        assert_eq!(inserted, vec!["e", "!"]);
+        assert!(hunks.iter().any(|h| h.op == DiffOp::Equal && h.lines.contains(&family.to_string())));
+        assert_eq!(apply(&old, &hunks, Granularity::Char), new);
+    }
+
+    #[test]
+    fn test_granularity_option_names() {
+        let options: DiffOptions = serde_json::from_str(r#"{ "granularity": "word" }"#).unwrap();
+        assert_eq!(options.granularity, Granularity::Word);
+        assert!(options.normalize_line_endings);
+        let options: DiffOptions = serde_json::from_str(r#"{ "granularity": "char" }"#).unwrap();
+        assert_eq!(options.granularity, Granularity::Char);
+        assert_eq!(DiffOptions::default().granularity, Granularity::Line);
+    }
+}
@@ -0,0 +1,188 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Edit distance - Levenshtein distance over grapheme clusters
+//
+// Strings are compared as sequences of extended grapheme clusters, so an
+// accented letter written with a combining mark, a flag or a ZWJ emoji
+// sequence counts as one character whatever its length in bytes or code
+// points. Insertions, deletions and substitutions each cost 1. Clusters
+// are compared as written, so precomposed é and e + U+0301 differ; run
+// both strings through NFC first when that matters.
+//
+// The thresholded form only answers "is the distance at most `max`?". It
+// gives up as soon as the grapheme counts differ by more than `max`, which
+// it can tell after reading at most `max + 1` graphemes past the end of the
+// shorter string, and otherwise fills only the diagonal band of width
+// `2 * max + 1` of the DP matrix, stopping early once every cell in a row
+// exceeds `max`. Time is O(max × length) and memory O(length), never the
+// full matrix.
+
+use serde::Serialize;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Best candidate found by `closest_match`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Match {
+    pub index: usize,
+    pub distance: usize,
+}
+
+/// Levenshtein distance between `a` and `b` in grapheme clusters
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<&str> = a.graphemes(true).collect();
+    let b: Vec<&str> = b.graphemes(true).collect();
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+
+    let mut prev: Vec<usize> = (0..=short.len()).collect();
+    let mut cur = vec![0; short.len() + 1];
+    for (i, long_char) in long.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, short_char) in short.iter().enumerate() {
+            let cost = usize::from(long_char != short_char);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[short.len()]
+}
+
+/// The edit distance if it is at most `max`, otherwise `None`
+pub fn edit_distance_within(a: &str, b: &str, max: usize) -> Option<usize> {
+    // Fewer bytes means no more graphemes to segment in full
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    let short: Vec<&str> = short.graphemes(true).collect();
+    within(&short, long, max)
+}
+
+/// The candidate closest to `needle` within `max`, ties going to the lowest
+/// index; `None` if no candidate is that close
+///
+/// Each match found tightens the threshold for the candidates after it.
+pub fn closest_match<'a>(needle: &str, candidates: impl IntoIterator<Item = &'a str>, max: usize) -> Option<Match> {
+    let needle: Vec<&str> = needle.graphemes(true).collect();
+    let mut best: Option<Match> = None;
+    for (index, candidate) in candidates.into_iter().enumerate() {
+        let limit = best.map_or(max, |found| found.distance - 1);
+        if let Some(distance) = within(&needle, candidate, limit) {
+            best = Some(Match { index, distance });
+            if distance == 0 {
+                break;
+            }
+        }
+    }
+    best
+}
+
+/// Thresholded distance between pre-segmented `a` and `b`, reading no more
+/// of `b` than can still be within `max`
+fn within(a: &[&str], b: &str, max: usize) -> Option<usize> {
+    let b: Vec<&str> = b.graphemes(true).take(a.len() + max + 1).collect();
+    if b.len() > a.len() + max || a.len() > b.len() + max {
+        return None;
+    }
+    banded(a, &b, max)
+}
+
+/// Levenshtein distance restricted to cells within `max` of the diagonal;
+/// the lengths must differ by at most `max`
+fn banded(a: &[&str], b: &[&str], max: usize) -> Option<usize> {
+    // Anything over `max` is as good as infinite
+    let over = max + 1;
+    let mut prev: Vec<usize> = (0..=b.len()).map(|j| j.min(over)).collect();
+    let mut cur = vec![over; b.len() + 1];
+
+    for i in 1..=a.len() {
+        let lo = i.saturating_sub(max).max(1);
+        let hi = (i + max).min(b.len());
+        cur[lo - 1] = if lo == 1 { i.min(over) } else { over };
+        let mut row_min = cur[lo - 1];
+        for j in lo..=hi {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let cell = (prev[j - 1] + cost).min(prev[j] + 1).min(cur[j - 1] + 1).min(over);
+            cur[j] = cell;
+            row_min = row_min.min(cell);
+        }
+        // The next row reads one cell past this row's band
+        if hi < b.len() {
+            cur[hi + 1] = over;
+        }
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    let distance = prev[b.len()];
+    (distance <= max).then_some(distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_ascii() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("flaw", "lawn"), 2);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+
+    #[test]
+    fn test_graphemes_not_bytes() {
+        // é as e + U+0301 is one grapheme of three bytes
+        assert_eq!(edit_distance("cafe\u{301}", "cafe"), 1);
+        assert_eq!(edit_distance("cafe\u{301}", "cafe\u{301}s"), 1);
+        assert_eq!(edit_distance("cafe\u{301}", "caf\u{e9}"), 1);
+        // Flags are two regional indicators, 8 bytes each
+        assert_eq!(edit_distance("🇩🇪 team", "🇫🇷 team"), 1);
+        // ZWJ family sequences differing in their last member
+        assert_eq!(edit_distance("👨‍👩‍👧", "👨‍👩‍👦"), 1);
+        assert_eq!(edit_distance("a👍🏽b", "ab"), 1);
+        assert_eq!(edit_distance_within("n\u{303}andu\u{301}", "nandu", 2), Some(2));
+    }
+
+    #[test]
+    fn test_within_agrees_with_full_distance() {
+        let words = ["", "a", "ab", "abc", "acb", "kitten", "sitting", "bitten", "mitten", "smitten", "é", "ée"];
+        for a in words {
+            for b in words {
+                let full = edit_distance(a, b);
+                for max in 0..5 {
+                    let expected = (full <= max).then_some(full);
+                    assert_eq!(edit_distance_within(a, b, max), expected, "{:?} {:?} max {}", a, b, max);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_threshold_does_not_fill_the_matrix() {
+        let title = "Reconciliation notes";
+        let body = "lorem ipsum dolor ".repeat(1200);
+        let mut edited = body.clone();
+        edited.replace_range(100..101, "X");
+        edited.insert(15_000, 'Y');
+
+        // A full 21600 × 21600 matrix would take seconds; the band is tiny
+        let start = Instant::now();
+        assert_eq!(edit_distance_within(title, &body, 3), None);
+        assert_eq!(edit_distance_within(&body, title, 3), None);
+        assert_eq!(edit_distance_within(&body, &edited, 3), Some(2));
+        assert_eq!(edit_distance_within(&body, &edited, 1), None);
+        assert!(start.elapsed() < Duration::from_secs(1), "took {:?}", start.elapsed());
+    }
+
+    #[test]
+    fn test_closest_match() {
+        let candidates = ["Recon Report", "Reconciliation Report", "Reconcilation Report", "Reconciliation Reports"];
+        assert_eq!(closest_match("Reconciliation Reprt", candidates, 3), Some(Match { index: 1, distance: 1 }));
+        assert_eq!(closest_match("Reconciliation Report", candidates, 3), Some(Match { index: 1, distance: 0 }));
+        assert_eq!(closest_match("Something else", candidates, 3), None);
+        assert_eq!(closest_match("x", Vec::<&str>::new(), 3), None);
+        // Ties go to the first candidate
+        assert_eq!(closest_match("ab", ["ax", "xb"], 1), Some(Match { index: 0, distance: 1 }));
+    }
+}
@@ -0,0 +1,349 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Encoding detection - what a byte string is before it is treated as text
+//
+// The guess is made in this order:
+//
+// 1. A byte order mark decides: EF BB BF is UTF-8, FF FE UTF-16LE and
+//    FE FF UTF-16BE.
+// 2. UTF-16 without a BOM is recognised by its alternating NULs: mostly
+//    ASCII text encoded as UTF-16LE has a zero in nearly every odd byte and
+//    almost none in the even bytes (the reverse for big-endian). Within the
+//    first 4 KiB, at least 40% of the code units must have a zero high byte
+//    and at most 5% a zero low byte. Non-Latin UTF-16 text without a BOM is
+//    not detected.
+// 3. Any other NUL, or more than 10% control characters, means binary.
+// 4. Valid UTF-8 (which includes plain ASCII) is UTF-8.
+// 5. Anything else is taken to be windows-1252, the superset of Latin-1
+//    that browsers use for the "latin1" label.
+//
+// Line endings are counted in code units of the guessed encoding, so the
+// "\r\0\n\0" of UTF-16LE is one CRLF.
+//
+// `to_utf8` is the other half: once the encoding is known, decode with
+// encoding_rs. Labels follow the WHATWG Encoding Standard, so "iso-8859-1"
+// and "latin1" decode as windows-1252, exactly as a browser would.
+
+use crate::error::ReconError;
+use crate::stats::{is_binary_control, BINARY_RATIO};
+use encoding_rs::{DecoderResult, Encoding};
+use serde::Serialize;
+
+/// Bytes inspected by the UTF-16 heuristic
+const UTF16_SAMPLE_BYTES: usize = 4096;
+
+/// Guessed character encoding, by WHATWG label
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum EncodingGuess {
+    #[serde(rename = "utf-8")]
+    Utf8,
+    #[serde(rename = "utf-16le")]
+    Utf16Le,
+    #[serde(rename = "utf-16be")]
+    Utf16Be,
+    #[serde(rename = "windows-1252")]
+    Windows1252,
+    /// Not text in any supported encoding
+    #[serde(rename = "binary")]
+    Binary,
+}
+
+/// Line terminator style
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEndingStyle {
+    Lf,
+    Crlf,
+    Cr,
+    /// More than one of the above
+    Mixed,
+    /// No line terminators at all
+    None,
+}
+
+/// What `detect` found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EncodingReport {
+    pub encoding_guess: EncodingGuess,
+    pub has_bom: bool,
+    pub line_ending: LineEndingStyle,
+    pub valid_utf8: bool,
+    /// Byte offset of the first invalid UTF-8 sequence
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample_invalid_offset: Option<usize>,
+}
+
+/// Inspect `data`
+pub fn detect(data: &[u8]) -> EncodingReport {
+    let (valid_utf8, sample_invalid_offset) = match std::str::from_utf8(data) {
+        Ok(_) => (true, None),
+        Err(e) => (false, Some(e.valid_up_to())),
+    };
+
+    let (encoding_guess, bom_len) = guess(data, valid_utf8);
+    let body = &data[bom_len..];
+    let line_ending = match encoding_guess {
+        EncodingGuess::Utf16Le => line_ending(body.chunks_exact(2).map(|unit| u16::from_le_bytes([unit[0], unit[1]]))),
+        EncodingGuess::Utf16Be => line_ending(body.chunks_exact(2).map(|unit| u16::from_be_bytes([unit[0], unit[1]]))),
+        _ => line_ending(body.iter().map(|&b| u16::from(b))),
+    };
+
+    EncodingReport {
+        encoding_guess,
+        has_bom: bom_len > 0,
+        line_ending,
+        valid_utf8,
+        sample_invalid_offset,
+    }
+}
+
+/// The guessed encoding and the length of its BOM, if any
+fn guess(data: &[u8], valid_utf8: bool) -> (EncodingGuess, usize) {
+    if data.starts_with(&[0xef, 0xbb, 0xbf]) {
+        return (EncodingGuess::Utf8, 3);
+    }
+    if data.starts_with(&[0xff, 0xfe]) {
+        return (EncodingGuess::Utf16Le, 2);
+    }
+    if data.starts_with(&[0xfe, 0xff]) {
+        return (EncodingGuess::Utf16Be, 2);
+    }
+    if let Some(utf16) = guess_utf16(data) {
+        return (utf16, 0);
+    }
+
+    let controls = data.iter().filter(|&&b| b == 0 || is_binary_control(b)).count();
+    if data.contains(&0) || controls as f64 > data.len() as f64 * BINARY_RATIO {
+        (EncodingGuess::Binary, 0)
+    } else if valid_utf8 {
+        (EncodingGuess::Utf8, 0)
+    } else {
+        (EncodingGuess::Windows1252, 0)
+    }
+}
+
+/// The alternating-NUL heuristic for BOM-less UTF-16
+fn guess_utf16(data: &[u8]) -> Option<EncodingGuess> {
+    let sample = &data[..data.len().min(UTF16_SAMPLE_BYTES)];
+    let units = sample.len() / 2;
+    if units == 0 {
+        return None;
+    }
+
+    let zero_even = sample.iter().step_by(2).take(units).filter(|&&b| b == 0).count();
+    let zero_odd = sample.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+    let mostly = |count: usize| count * 10 >= units * 4;
+    let rarely = |count: usize| count * 20 <= units;
+
+    if mostly(zero_odd) && rarely(zero_even) {
+        Some(EncodingGuess::Utf16Le)
+    } else if mostly(zero_even) && rarely(zero_odd) {
+        Some(EncodingGuess::Utf16Be)
+    } else {
+        None
+    }
+}
+
+/// Decode `data` from the encoding named by `label` into UTF-8
+///
+/// A BOM for the source encoding is dropped. Unknown labels, and labels
+/// such as "iso-2022-kr" that WHATWG maps to the replacement encoding, fail
+/// with `invalid_input`; a byte sequence that is not valid in the source
+/// encoding fails with `decode_error` naming its byte offset. Nothing is
+/// replaced with U+FFFD.
+pub fn to_utf8(data: &[u8], label: &str) -> Result<String, ReconError> {
+    let encoding = Encoding::for_label(label.as_bytes())
+        .filter(|&encoding| encoding != encoding_rs::REPLACEMENT)
+        .ok_or_else(|| ReconError::invalid_input(format!("Unsupported source encoding: {}", label)))?;
+
+    let mut decoder = encoding.new_decoder_with_bom_removal();
+    let capacity = decoder
+        .max_utf8_buffer_length_without_replacement(data.len())
+        .ok_or_else(|| ReconError::invalid_input("Input is too large to transcode"))?;
+    let mut text = String::with_capacity(capacity);
+    let (result, read) = decoder.decode_to_string_without_replacement(data, &mut text, true);
+    match result {
+        DecoderResult::InputEmpty => Ok(text),
+        DecoderResult::Malformed(bad, after) => {
+            let offset = read - bad as usize - after as usize;
+            Err(ReconError::decode(format!("Invalid {} sequence at byte {}", encoding.name(), offset)))
+        }
+        DecoderResult::OutputFull => unreachable!("output buffer is sized for the worst case"),
+    }
+}
+
+/// Classify the terminators among `units`
+fn line_ending(units: impl Iterator<Item = u16>) -> LineEndingStyle {
+    const CR: u16 = b'\r' as u16;
+    const LF: u16 = b'\n' as u16;
+
+    let (mut lf, mut crlf, mut cr) = (false, false, false);
+    let mut units = units.peekable();
+    while let Some(unit) = units.next() {
+        match unit {
+            CR if units.peek() == Some(&LF) => {
+                units.next();
+                crlf = true;
+            }
+            CR => cr = true,
+            LF => lf = true,
+            _ => {}
+        }
+    }
+
+    match (lf, crlf, cr) {
+        (false, false, false) => LineEndingStyle::None,
+        (true, false, false) => LineEndingStyle::Lf,
+        (false, true, false) => LineEndingStyle::Crlf,
+        (false, false, true) => LineEndingStyle::Cr,
+        _ => LineEndingStyle::Mixed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn test_utf8_with_bom() {
+        let report = detect(b"\xef\xbb\xbfline one\r\nline two\r\n");
+        assert_eq!(
+            report,
+            EncodingReport {
+                encoding_guess: EncodingGuess::Utf8,
+                has_bom: true,
+                line_ending: LineEndingStyle::Crlf,
+                valid_utf8: true,
+                sample_invalid_offset: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_utf16le_with_and_without_bom() {
+        let mut with_bom = vec![0xff, 0xfe];
+        with_bom.extend(utf16le("first\r\nsecond\r\n"));
+        let report = detect(&with_bom);
+        assert_eq!(report.encoding_guess, EncodingGuess::Utf16Le);
+        assert!(report.has_bom);
+        assert_eq!(report.line_ending, LineEndingStyle::Crlf);
+        assert!(!report.valid_utf8);
+
+        let report = detect(&utf16le("plain ascii text\nover two lines\n"));
+        assert_eq!(report.encoding_guess, EncodingGuess::Utf16Le);
+        assert!(!report.has_bom);
+        assert_eq!(report.line_ending, LineEndingStyle::Lf);
+        // NULs are valid UTF-8, so only the guess gives it away
+        assert!(report.valid_utf8);
+    }
+
+    #[test]
+    fn test_utf16be_bom() {
+        let mut data = vec![0xfe, 0xff];
+        data.extend("a\rb".encode_utf16().flat_map(u16::to_be_bytes));
+        let report = detect(&data);
+        assert_eq!(report.encoding_guess, EncodingGuess::Utf16Be);
+        assert_eq!(report.line_ending, LineEndingStyle::Cr);
+
+        let bare: Vec<u8> = "big endian text".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(detect(&bare).encoding_guess, EncodingGuess::Utf16Be);
+    }
+
+    #[test]
+    fn test_line_ending_styles() {
+        assert_eq!(detect(b"a\nb\r\nc").line_ending, LineEndingStyle::Mixed);
+        assert_eq!(detect(b"a\rb\nc").line_ending, LineEndingStyle::Mixed);
+        assert_eq!(detect(b"a\r\n").line_ending, LineEndingStyle::Crlf);
+        assert_eq!(detect(b"a\r\r\n").line_ending, LineEndingStyle::Mixed);
+        assert_eq!(detect(b"one line").line_ending, LineEndingStyle::None);
+        assert_eq!(detect(b"").line_ending, LineEndingStyle::None);
+    }
+
+    #[test]
+    fn test_latin1_is_windows_1252() {
+        let report = detect(b"caf\xe9 cr\xe8me\n");
+        assert_eq!(report.encoding_guess, EncodingGuess::Windows1252);
+        assert!(!report.valid_utf8);
+        assert_eq!(report.sample_invalid_offset, Some(3));
+        assert_eq!(report.line_ending, LineEndingStyle::Lf);
+    }
+
+    #[test]
+    fn test_binary() {
+        let png = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0, 0, 0, 0x0d, b'I', b'H', b'D', b'R', 0x00, 0x00, 0x01];
+        assert_eq!(detect(&png).encoding_guess, EncodingGuess::Binary);
+
+        let noise: Vec<u8> = (0..1024u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+        let report = detect(&noise);
+        assert_eq!(report.encoding_guess, EncodingGuess::Binary);
+        assert!(!report.valid_utf8);
+    }
+
+    #[test]
+    fn test_plain_ascii_and_empty() {
+        let report = detect(b"hello\n");
+        assert_eq!(report.encoding_guess, EncodingGuess::Utf8);
+        assert!(!report.has_bom);
+        assert_eq!(detect(b"").encoding_guess, EncodingGuess::Utf8);
+    }
+
+    #[test]
+    fn test_to_utf8_windows_1252() {
+        assert_eq!(to_utf8(b"\x80 5, \x93quoted\x94", "windows-1252").unwrap(), "€ 5, \u{201c}quoted\u{201d}");
+        assert_eq!(to_utf8(b"caf\xe9", "cp1252").unwrap(), "café");
+        // WHATWG treats Latin-1 as windows-1252, so 0x80 is still the euro sign
+        assert_eq!(to_utf8(b"caf\xe9 \x80", "iso-8859-1").unwrap(), "café €");
+        assert_eq!(to_utf8(b"cr\xe8me", " Latin1 ").unwrap(), "crème");
+    }
+
+    #[test]
+    fn test_to_utf8_utf16() {
+        assert_eq!(to_utf8(b"h\x00\xe9\x00\xac\x20", "utf-16le").unwrap(), "hé€");
+        assert_eq!(to_utf8(b"\xff\xfeh\x00i\x00", "utf-16le").unwrap(), "hi");
+        assert_eq!(to_utf8(b"\x00h\x00\xe9\x20\xac", "utf-16be").unwrap(), "hé€");
+        assert_eq!(to_utf8(b"\xfe\xff\xd8\x3d\xde\x00", "UTF-16BE").unwrap(), "\u{1f600}");
+    }
+
+    #[test]
+    fn test_to_utf8_reports_offsets() {
+        // Unpaired high surrogate in the second code unit
+        let err = to_utf8(b"a\x00\x3d\xd8b\x00", "utf-16le").unwrap_err();
+        assert_eq!(err.code, crate::error::ErrorCode::DecodeError);
+        assert_eq!(err.message, "Invalid UTF-16LE sequence at byte 2");
+
+        // Odd trailing byte
+        let err = to_utf8(b"\x00a\x00", "utf-16be").unwrap_err();
+        assert_eq!(err.message, "Invalid UTF-16BE sequence at byte 2");
+
+        let err = to_utf8(b"ok \xff", "utf-8").unwrap_err();
+        assert_eq!(err.message, "Invalid UTF-8 sequence at byte 3");
+    }
+
+    #[test]
+    fn test_to_utf8_unsupported_labels() {
+        for label in ["klingon", "", "iso-2022-kr"] {
+            let err = to_utf8(b"text", label).unwrap_err();
+            assert_eq!(err.code, crate::error::ErrorCode::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn test_serialized_shape() {
+        let json = serde_json::to_value(detect(b"caf\xe9\r\n")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "encoding_guess": "windows-1252",
+                "has_bom": false,
+                "line_ending": "crlf",
+                "valid_utf8": false,
+                "sample_invalid_offset": 3,
+            })
+        );
+        assert!(serde_json::to_value(detect(b"ok")).unwrap().get("sample_invalid_offset").is_none());
+    }
+}
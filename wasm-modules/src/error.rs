@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Errors surfaced to JS
+//
+// Every exported function fails with a JS `Error` whose `message` is the
+// human-readable description and which carries two extra properties:
+// `code`, one of the stable strings below, and `index`, the position of the
+// offending element for batch operations. Callers should branch on `code`;
+// messages may be reworded between releases.
+//
+// Building with the `string-errors` feature throws the bare message string
+// instead, as earlier releases did. Native callers get the `ReconError`
+// itself.
+
+use serde::Serialize;
+use thiserror::Error;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::JsValue;
+
+/// Stable machine-readable error category
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// An argument has the wrong shape or type, or an invalid value
+    InvalidInput,
+    /// The options object could not be parsed
+    InvalidOptions,
+    /// Encoded input (hex, base58, multihash, JSON, patch text) is malformed
+    DecodeError,
+    /// Input exceeds a size or depth limit
+    SizeLimit,
+    /// The object is in a state that does not allow the call
+    InvalidState,
+    /// A well-formed patch does not match the document it is applied to
+    PatchRejected,
+    /// A ReconForth program failed
+    EvalError,
+    /// A manifest or binary patch does not match its integrity digest
+    IntegrityMismatch,
+    /// A manifest or recipe id is from a format version this build cannot read
+    UnsupportedVersion,
+    /// A result could not be converted to a JS value
+    SerializationError,
+}
+
+impl ErrorCode {
+    /// The `code` string seen by JS
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::InvalidInput => "invalid_input",
+            ErrorCode::InvalidOptions => "invalid_options",
+            ErrorCode::DecodeError => "decode_error",
+            ErrorCode::SizeLimit => "size_limit",
+            ErrorCode::InvalidState => "invalid_state",
+            ErrorCode::PatchRejected => "patch_rejected",
+            ErrorCode::EvalError => "eval_error",
+            ErrorCode::IntegrityMismatch => "integrity_mismatch",
+            ErrorCode::UnsupportedVersion => "unsupported_version",
+            ErrorCode::SerializationError => "serialization_error",
+        }
+    }
+}
+
+/// An error with its category and, for batch operations, element index
+#[derive(Error, Debug, Clone, PartialEq, Eq, Serialize)]
+#[error("{message}")]
+pub struct ReconError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+}
+
+impl ReconError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        ReconError { code, message: message.into(), index: None }
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        ReconError::new(ErrorCode::InvalidInput, message)
+    }
+
+    pub fn invalid_options(message: impl Into<String>) -> Self {
+        ReconError::new(ErrorCode::InvalidOptions, message)
+    }
+
+    pub fn decode(message: impl Into<String>) -> Self {
+        ReconError::new(ErrorCode::DecodeError, message)
+    }
+
+    pub fn serialization(message: impl Into<String>) -> Self {
+        ReconError::new(ErrorCode::SerializationError, message)
+    }
+
+    /// Attach the index of the batch element that failed
+    pub fn at(mut self, index: usize) -> Self {
+        self.index = Some(index);
+        self
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl From<ReconError> for JsValue {
+    #[cfg(not(feature = "string-errors"))]
+    fn from(error: ReconError) -> JsValue {
+        let js = js_sys::Error::new(&error.message);
+        // Setting a property on a fresh Error object cannot fail
+        let _ = js_sys::Reflect::set(&js, &"code".into(), &error.code.as_str().into());
+        if let Some(index) = error.index {
+            let _ = js_sys::Reflect::set(&js, &"index".into(), &JsValue::from_f64(index as f64));
+        }
+        js.into()
+    }
+
+    #[cfg(feature = "string-errors")]
+    fn from(error: ReconError) -> JsValue {
+        JsValue::from_str(&error.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_serialize_as_their_js_strings() {
+        let codes = [
+            ErrorCode::InvalidInput,
+            ErrorCode::InvalidOptions,
+            ErrorCode::DecodeError,
+            ErrorCode::SizeLimit,
+            ErrorCode::InvalidState,
+            ErrorCode::PatchRejected,
+            ErrorCode::EvalError,
+            ErrorCode::IntegrityMismatch,
+            ErrorCode::UnsupportedVersion,
+            ErrorCode::SerializationError,
+        ];
+        for code in codes {
+            assert_eq!(serde_json::to_value(code).unwrap(), code.as_str());
+        }
+    }
+
+    #[test]
+    fn test_error_shape() {
+        let error = ReconError::invalid_input("bad entry").at(3);
+        assert_eq!(error.to_string(), "bad entry");
+        assert_eq!(
+            serde_json::to_string(&error).unwrap(),
+            r#"{"code":"invalid_input","message":"bad entry","index":3}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&ReconError::decode("bad hex")).unwrap(),
+            r#"{"code":"decode_error","message":"bad hex"}"#
+        );
+    }
+}
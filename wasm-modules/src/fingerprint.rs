@@ -0,0 +1,146 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Near-duplicate fingerprints - 64-bit SimHash over word shingles
+//
+// Every 3-word shingle of the normalized content is hashed and mixed to a
+// well-distributed u64. Each of the 64 bit positions keeps a vote: +1 if
+// the shingle hash has the bit set, -1 otherwise. The fingerprint bit is
+// set when the vote is positive. Similar documents share most shingles and
+// therefore end up a small Hamming distance apart.
+
+use crate::normalize::{self, NormalizeOptions};
+use crate::similarity::{self, DEFAULT_SHINGLE_SIZE};
+
+/// 64-bit SimHash of a document
+///
+/// Empty documents fingerprint to 0.
+pub fn simhash(content: &str) -> u64 {
+    let normalized = normalize::normalize(content, &NormalizeOptions::default());
+    let words = similarity::words(&normalized);
+    let shingles = similarity::shingles(&words, DEFAULT_SHINGLE_SIZE.min(words.len()));
+
+    let mut votes = [0i64; 64];
+    for shingle in shingles {
+        let hash = mix64(shingle);
+        for (bit, vote) in votes.iter_mut().enumerate() {
+            if hash >> bit & 1 == 1 {
+                *vote += 1;
+            } else {
+                *vote -= 1;
+            }
+        }
+    }
+
+    votes
+        .iter()
+        .enumerate()
+        .filter(|(_, vote)| **vote > 0)
+        .fold(0u64, |fingerprint, (bit, _)| fingerprint | 1 << bit)
+}
+
+/// Fingerprint as 16 lowercase hex characters
+pub fn to_hex(fingerprint: u64) -> String {
+    format!("{:016x}", fingerprint)
+}
+
+/// Parse a fingerprint produced by `to_hex` (case-insensitive)
+pub fn from_hex(hex: &str) -> Option<u64> {
+    if hex.len() != 16 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u64::from_str_radix(hex, 16).ok()
+}
+
+/// Number of differing bits between two fingerprints
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// SplitMix64 finalizer, spreads shingle hash entropy over all 64 bits
+fn mix64(mut x: u64) -> u64 {
+    x ^= x >> 30;
+    x = x.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x ^= x >> 27;
+    x = x.wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: &[&str] = &[
+        "document", "repository", "license", "security", "policy", "release", "branch", "commit",
+        "canonical", "source", "version", "review", "merge", "conflict", "bundle", "pack",
+        "validate", "rule", "content", "hash", "graph", "store", "sync", "mirror",
+    ];
+
+    /// Deterministic pseudo-random prose, one sentence per line
+    fn prose(seed: u64, sentences: usize) -> Vec<String> {
+        let mut state = seed;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        (0..sentences)
+            .map(|_| {
+                let len = 8 + next() % 8;
+                let words: Vec<&str> = (0..len).map(|_| WORDS[(next() % WORDS.len() as u64) as usize]).collect();
+                format!("{}.", words.join(" "))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_identical_and_whitespace_noise() {
+        let doc = prose(1, 30).join("\n");
+        let noisy = format!("  {}  \r\n\r\n\r\n", doc.replace('\n', "   \r\n"));
+        assert_eq!(simhash(&doc), simhash(&doc));
+        assert_eq!(simhash(&doc), simhash(&noisy));
+    }
+
+    #[test]
+    fn test_single_changed_sentence_is_close() {
+        let original = prose(7, 40);
+        let mut edited = original.clone();
+        edited[20] = "this sentence was rewritten entirely by a reviewer.".to_string();
+
+        let distance = hamming_distance(simhash(&original.join("\n")), simhash(&edited.join("\n")));
+        assert!(distance <= 8, "distance {}", distance);
+    }
+
+    #[test]
+    fn test_unrelated_documents_are_far() {
+        let a = prose(11, 40).join("\n");
+        let b = prose(12345, 40).join("\n");
+        let distance = hamming_distance(simhash(&a), simhash(&b));
+        assert!(distance >= 16, "distance {}", distance);
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let fingerprint = simhash("the quick brown fox jumps over the lazy dog");
+        let hex = to_hex(fingerprint);
+        assert_eq!(hex.len(), 16);
+        assert_eq!(from_hex(&hex), Some(fingerprint));
+        assert_eq!(from_hex(&hex.to_uppercase()), Some(fingerprint));
+        assert_eq!(from_hex("abc"), None);
+        assert_eq!(from_hex("zzzzzzzzzzzzzzzz"), None);
+        assert_eq!(from_hex("+123456789abcdef"), None);
+    }
+
+    #[test]
+    fn test_empty_document() {
+        assert_eq!(simhash(""), 0);
+        assert_eq!(simhash("   \n  "), 0);
+    }
+
+    #[test]
+    fn test_hamming_distance() {
+        assert_eq!(hamming_distance(0, 0), 0);
+        assert_eq!(hamming_distance(0, u64::MAX), 64);
+        assert_eq!(hamming_distance(0b1011, 0b0001), 2);
+    }
+}
@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Folding - lossy normalization for fuzzy match keys
+//
+// Steps run in this order, each one optional:
+//
+// 1. Compatibility (NFKC): ligatures expand (ﬁ → fi), full-width forms
+//    become their ASCII counterparts (Ａ → A), and so on.
+// 2. Case folding with the full Unicode mapping, which unlike lowercasing
+//    maps ß to "ss", final sigma to σ and İ to "i" plus a combining dot.
+//    Turkish dotless ı has no folding and stays distinct from i.
+// 3. Accent stripping: decompose (NFD) and drop every combining mark. This
+//    also drops the vowel signs of scripts such as Devanagari, so leave it
+//    off for such content. Letters without a decomposition, like ł or ø,
+//    are kept.
+// 4. Punctuation stripping: drop every character that is not a letter,
+//    digit, mark or whitespace, so symbols and emoji go too.
+// 5. Whitespace collapsing: runs of Unicode whitespace become one space and
+//    the ends are trimmed.
+//
+// The result is in NFC.
+
+use serde::Deserialize;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Which folding steps to apply; all on by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct FoldOptions {
+    pub compatibility: bool,
+    pub case_fold: bool,
+    pub strip_accents: bool,
+    pub strip_punctuation: bool,
+    pub collapse_whitespace: bool,
+}
+
+impl Default for FoldOptions {
+    fn default() -> Self {
+        FoldOptions {
+            compatibility: true,
+            case_fold: true,
+            strip_accents: true,
+            strip_punctuation: true,
+            collapse_whitespace: true,
+        }
+    }
+}
+
+/// Fold `content` according to `options`
+pub fn fold(content: &str, options: &FoldOptions) -> String {
+    let mut text: String = if options.compatibility { content.nfkc().collect() } else { content.to_string() };
+    if options.case_fold {
+        text = caseless::default_case_fold_str(&text);
+    }
+    text = if options.strip_accents {
+        text.nfd().filter(|&c| !is_combining_mark(c)).nfc().collect()
+    } else {
+        text.nfc().collect()
+    };
+    if options.strip_punctuation {
+        text.retain(|c| c.is_alphanumeric() || c.is_whitespace() || is_combining_mark(c));
+    }
+    if options.collapse_whitespace {
+        text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(content: &str) -> String {
+        fold(content, &FoldOptions::default())
+    }
+
+    #[test]
+    fn test_multilingual_fixtures() {
+        let fixtures = [
+            ("Straße", "strasse"),
+            ("STRASSE", "strasse"),
+            ("ﬁnancial ﬂow", "financial flow"),
+            ("ＡＢＣ　１２３", "abc 123"),
+            ("Crème Brûlée", "creme brulee"),
+            ("İSTANBUL", "istanbul"),
+            ("ısırgan", "ısırgan"),
+            ("ΣΊΣΥΦΟΣ", "σισυφοσ"),
+            ("σίσυφος", "σισυφοσ"),
+            ("Ǆemal", "dzemal"),
+            ("Łódź", "łodz"),
+            ("Ærøskøbing", "ærøskøbing"),
+            ("Ça va? «Oui» — très bien…", "ca va oui tres bien"),
+            ("  The   Title:\tA\u{2003}Subtitle  ", "the title a subtitle"),
+            ("e\u{301}", "e"),
+        ];
+        for (input, expected) in fixtures {
+            assert_eq!(key(input), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_dotless_i_stays_distinct() {
+        assert_ne!(key("ısı"), key("isi"));
+        assert_eq!(key("İsi"), key("isi"));
+    }
+
+    #[test]
+    fn test_steps_can_be_disabled() {
+        let only = |options: FoldOptions| fold("Ｃafé,  Straße!", &options);
+        let none = FoldOptions {
+            compatibility: false,
+            case_fold: false,
+            strip_accents: false,
+            strip_punctuation: false,
+            collapse_whitespace: false,
+        };
+        assert_eq!(only(none), "Ｃafé,  Straße!");
+        assert_eq!(only(FoldOptions { case_fold: true, ..none }), "ｃafé,  strasse!");
+        assert_eq!(only(FoldOptions { compatibility: true, ..none }), "Café,  Straße!");
+        assert_eq!(only(FoldOptions { strip_accents: true, ..none }), "Ｃafe,  Straße!");
+        assert_eq!(only(FoldOptions { strip_punctuation: true, ..none }), "Ｃafé  Straße");
+        assert_eq!(only(FoldOptions { collapse_whitespace: true, ..none }), "Ｃafé, Straße!");
+
+        // Composed output even when only case folding ran
+        assert_eq!(fold("E\u{301}", &FoldOptions { case_fold: true, ..none }), "é");
+    }
+
+    #[test]
+    fn test_options_deserialize() {
+        let options: FoldOptions = serde_json::from_str(r#"{ "strip_punctuation": false }"#).unwrap();
+        assert_eq!(options, FoldOptions { strip_punctuation: false, ..FoldOptions::default() });
+    }
+}
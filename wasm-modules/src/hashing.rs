@@ -0,0 +1,245 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Hashing primitives - digest algorithms and output encodings
+
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+/// Hash algorithm selectable by batch operations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// Algorithm name as accepted in options
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    /// Parse algorithm from its name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sha256" => Some(HashAlgorithm::Sha256),
+            "blake3" => Some(HashAlgorithm::Blake3),
+            _ => None,
+        }
+    }
+
+    /// Raw 32-byte digest of `data`
+    pub fn digest(self, data: &[u8]) -> [u8; 32] {
+        match self {
+            HashAlgorithm::Sha256 => sha256(data),
+            HashAlgorithm::Blake3 => *blake3::hash(data).as_bytes(),
+        }
+    }
+
+    /// Digest of `data` as lowercase hex
+    pub fn hash(self, data: &[u8]) -> String {
+        to_hex(&self.digest(data))
+    }
+}
+
+/// Text encoding for digests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestEncoding {
+    /// Lowercase hex
+    #[default]
+    Hex,
+    /// RFC 4648 §4 base64 with padding
+    Base64,
+    /// RFC 4648 §5 URL-safe base64 without padding, as used in JWTs
+    Base64Url,
+}
+
+impl DigestEncoding {
+    /// Parse encoding from its name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "hex" => Some(DigestEncoding::Hex),
+            "base64" => Some(DigestEncoding::Base64),
+            "base64url" => Some(DigestEncoding::Base64Url),
+            _ => None,
+        }
+    }
+
+    /// Encode digest bytes
+    pub fn encode(self, digest: &[u8]) -> String {
+        match self {
+            DigestEncoding::Hex => to_hex(digest),
+            DigestEncoding::Base64 => STANDARD.encode(digest),
+            DigestEncoding::Base64Url => URL_SAFE_NO_PAD.encode(digest),
+        }
+    }
+
+    /// Append the encoded digest to `out` without allocating a new string
+    pub fn encode_into(self, digest: &[u8], out: &mut String) {
+        match self {
+            DigestEncoding::Hex => {
+                for byte in digest {
+                    out.push(HEX_DIGITS[usize::from(byte >> 4)] as char);
+                    out.push(HEX_DIGITS[usize::from(byte & 0xf)] as char);
+                }
+            }
+            DigestEncoding::Base64 => STANDARD.encode_string(digest, out),
+            DigestEncoding::Base64Url => URL_SAFE_NO_PAD.encode_string(digest, out),
+        }
+    }
+}
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// SHA-256 digest of `data`
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// Convert bytes to a lowercase hex string
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Check a prefix length: between 1 and 64, the length of a full 32-byte
+/// digest in hex
+pub fn validate_prefix_len(hex_chars: usize) -> Result<(), String> {
+    if !(1..=64).contains(&hex_chars) {
+        return Err(format!("Prefix length must be between 1 and 64 hex characters, got {}", hex_chars));
+    }
+    Ok(())
+}
+
+/// The first `hex_chars` characters of a hex digest
+///
+/// Callers check `hex_chars` with `validate_prefix_len` first; longer
+/// prefixes give the whole digest.
+pub fn hex_prefix(hex: &str, hex_chars: usize) -> &str {
+    &hex[..hex_chars.min(hex.len())]
+}
+
+/// Parse a 64-character hex digest (either case)
+pub fn digest_from_hex(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut digest = [0u8; 32];
+    for (byte, pair) in digest.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        let pair = std::str::from_utf8(pair).ok()?;
+        if !pair.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        *byte = u8::from_str_radix(pair, 16).ok()?;
+    }
+    Some(digest)
+}
+
+/// Constant-time comparison of two hex digests (either case)
+///
+/// Malformed or wrong-length input compares unequal.
+pub fn hex_digests_equal(a: &str, b: &str) -> bool {
+    match (digest_from_hex(a), digest_from_hex(b)) {
+        (Some(a), Some(b)) => digests_equal(&a, &b),
+        _ => false,
+    }
+}
+
+/// Constant-time comparison of two digests
+pub fn digests_equal(a: &[u8; 32], b: &[u8; 32]) -> bool {
+    a.ct_eq(b).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC_HEX: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn test_hex_prefix() {
+        let hex = HashAlgorithm::Sha256.hash(b"abc");
+        assert_eq!(hex_prefix(&hex, 8), "ba7816bf");
+        assert_eq!(hex_prefix(&hex, 64), hex);
+        assert_eq!(hex_prefix(&hex, 1), "b");
+        assert!(validate_prefix_len(1).is_ok());
+        assert!(validate_prefix_len(64).is_ok());
+        assert!(validate_prefix_len(0).is_err());
+        assert!(validate_prefix_len(65).is_err());
+    }
+
+    #[test]
+    fn test_encodings_of_known_vector() {
+        let digest = sha256(b"abc");
+        assert_eq!(DigestEncoding::Hex.encode(&digest), ABC_HEX);
+        assert_eq!(DigestEncoding::Base64.encode(&digest), "ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=");
+        assert_eq!(DigestEncoding::Base64Url.encode(&digest), "ungWv48Bz-pBQUDeXa4iI7ADYaOWF3qctBD_YfIAFa0");
+
+        for encoding in [DigestEncoding::Hex, DigestEncoding::Base64, DigestEncoding::Base64Url] {
+            let mut out = String::from("prefix:");
+            encoding.encode_into(&digest, &mut out);
+            assert_eq!(out, format!("prefix:{}", encoding.encode(&digest)));
+        }
+    }
+
+    #[test]
+    fn test_encodings_agree_with_each_other() {
+        let digest = sha256(b"");
+        let base64 = DigestEncoding::Base64.encode(&digest);
+        let base64url = DigestEncoding::Base64Url.encode(&digest);
+
+        assert_eq!(base64, "47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=");
+        assert_eq!(base64url, base64.trim_end_matches('=').replace('+', "-").replace('/', "_"));
+        assert_eq!(STANDARD.decode(&base64).unwrap(), digest);
+        assert_eq!(URL_SAFE_NO_PAD.decode(&base64url).unwrap(), digest);
+        assert_eq!(digest_from_hex(&DigestEncoding::Hex.encode(&digest)).unwrap(), digest);
+        assert!(!base64url.contains('='));
+    }
+
+    #[test]
+    fn test_encoding_names() {
+        assert_eq!(DigestEncoding::from_name("hex"), Some(DigestEncoding::Hex));
+        assert_eq!(DigestEncoding::from_name("base64"), Some(DigestEncoding::Base64));
+        assert_eq!(DigestEncoding::from_name("base64url"), Some(DigestEncoding::Base64Url));
+        assert_eq!(DigestEncoding::from_name("base32"), None);
+
+        for algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Blake3] {
+            assert_eq!(HashAlgorithm::from_name(algorithm.as_str()), Some(algorithm));
+            assert_eq!(serde_json::from_value::<HashAlgorithm>(algorithm.as_str().into()).unwrap(), algorithm);
+        }
+        assert_eq!(HashAlgorithm::from_name("md5"), None);
+    }
+
+    #[test]
+    fn test_digest_from_hex() {
+        assert_eq!(to_hex(&digest_from_hex(ABC_HEX).unwrap()), ABC_HEX);
+        assert_eq!(to_hex(&digest_from_hex(&ABC_HEX.to_uppercase()).unwrap()), ABC_HEX);
+        assert!(digest_from_hex(&ABC_HEX[..62]).is_none());
+        assert!(digest_from_hex(&format!("{}zz", &ABC_HEX[..62])).is_none());
+        assert!(digest_from_hex(&format!("+{}", &ABC_HEX[..63])).is_none());
+        assert!(digest_from_hex(&"é".repeat(32)).is_none());
+    }
+
+    #[test]
+    fn test_hex_digests_equal() {
+        let upper = ABC_HEX.to_uppercase();
+        assert!(hex_digests_equal(ABC_HEX, ABC_HEX));
+        assert!(hex_digests_equal(ABC_HEX, &upper));
+        assert!(hex_digests_equal(&upper, ABC_HEX));
+
+        let other = to_hex(&sha256(b"abd"));
+        assert!(!hex_digests_equal(ABC_HEX, &other));
+        assert!(!hex_digests_equal(ABC_HEX, &format!(" {}", ABC_HEX)));
+        assert!(!hex_digests_equal(ABC_HEX, &format!("{}\n", ABC_HEX)));
+        assert!(!hex_digests_equal(ABC_HEX, &ABC_HEX[..63]));
+        assert!(!hex_digests_equal(&ABC_HEX[..32], &ABC_HEX[..32]));
+        assert!(!hex_digests_equal("", ""));
+    }
+}
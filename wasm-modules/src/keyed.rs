@@ -0,0 +1,204 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Keyed content hashes - HMAC-SHA256 and keyed BLAKE3
+//
+// Keyed digests can be published to third parties without letting them
+// precompute hashes of guessed (short, enumerable) content.
+//
+// Per-tenant namespacing uses BLAKE3 in its two keyed modes:
+//
+//   tenant_key = BLAKE3-derive_key(TENANT_KEY_CONTEXT, master || tenant_id)
+//   digest     = BLAKE3-keyed_hash(tenant_key, content)
+//
+// `master` is exactly 32 bytes, so the key material splits unambiguously
+// and `tenant_id` is its UTF-8 bytes as given. The same content hashed
+// under two tenant keys gives unrelated digests, and a digest reveals
+// nothing about the master key or other tenants' keys.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Length of BLAKE3 keys, master and derived alike
+pub const KEY_LEN: usize = blake3::KEY_LEN;
+
+/// Domain-separation context for `derive_tenant_key`; changing it changes
+/// every tenant key
+pub const TENANT_KEY_CONTEXT: &str = "recon-wasm 2025-06-01 tenant content key v1";
+
+/// HMAC-SHA256 of `data` under `key`, as lowercase hex
+pub fn hmac_bytes(key: &[u8], data: &[u8]) -> String {
+    crate::hashing::to_hex(&hmac_mac(key, data).finalize().into_bytes())
+}
+
+/// Check `expected_hex` (either case) against the HMAC of `data`
+///
+/// The digest comparison is constant-time. Malformed or wrong-length hex
+/// returns false.
+pub fn verify_hmac_bytes(key: &[u8], data: &[u8], expected_hex: &str) -> bool {
+    match crate::hashing::digest_from_hex(expected_hex) {
+        Some(expected) => hmac_mac(key, data).verify_slice(&expected).is_ok(),
+        None => false,
+    }
+}
+
+fn hmac_mac(key: &[u8], data: &[u8]) -> HmacSha256 {
+    // HMAC accepts keys of any length, so this cannot fail
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac
+}
+
+/// Keyed BLAKE3 of `data` under a 32-byte `key`, as lowercase hex
+pub fn blake3_keyed(key: &[u8], data: &[u8]) -> Result<String, String> {
+    let key = key_array(key, "key")?;
+    Ok(blake3::keyed_hash(&key, data).to_hex().to_string())
+}
+
+/// Per-tenant key derived from a 32-byte master key
+pub fn derive_tenant_key(master: &[u8], tenant_id: &str) -> Result<[u8; KEY_LEN], String> {
+    let master = key_array(master, "master key")?;
+    let mut hasher = blake3::Hasher::new_derive_key(TENANT_KEY_CONTEXT);
+    hasher.update(&master);
+    hasher.update(tenant_id.as_bytes());
+    Ok(*hasher.finalize().as_bytes())
+}
+
+fn key_array(key: &[u8], what: &str) -> Result<[u8; KEY_LEN], String> {
+    key.try_into()
+        .map_err(|_| format!("BLAKE3 {} must be {} bytes, got {}", what, KEY_LEN, key.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::blake3_vector_input;
+
+    /// RFC 4231 §4 test cases for HMAC-SHA-256
+    fn rfc4231_vectors() -> Vec<(Vec<u8>, Vec<u8>, &'static str)> {
+        vec![
+            (
+                vec![0x0b; 20],
+                b"Hi There".to_vec(),
+                "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+            ),
+            (
+                b"Jefe".to_vec(),
+                b"what do ya want for nothing?".to_vec(),
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            ),
+            (
+                vec![0xaa; 20],
+                vec![0xdd; 50],
+                "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+            ),
+            (
+                (0x01..=0x19).collect(),
+                vec![0xcd; 50],
+                "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b",
+            ),
+            (
+                vec![0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First".to_vec(),
+                "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            ),
+            (
+                vec![0xaa; 131],
+                b"This is a test using a larger than block-size key and a larger than block-size data. \
+                  The key needs to be hashed before being used by the HMAC algorithm."
+                    .to_vec(),
+                "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2",
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_rfc4231_vectors() {
+        for (key, data, expected) in rfc4231_vectors() {
+            assert_eq!(hmac_bytes(&key, &data), expected);
+            assert!(verify_hmac_bytes(&key, &data, expected));
+        }
+    }
+
+    #[test]
+    fn test_rfc4231_truncated_vector() {
+        // Test case 5 specifies only the first 128 bits of the output
+        let mac = hmac_bytes(&[0x0c; 20], b"Test With Truncation");
+        assert_eq!(&mac[..32], "a3b6167473100ee06e0c796c2955552b");
+    }
+
+    #[test]
+    fn test_verify_accepts_uppercase() {
+        let mac = hmac_bytes(b"Jefe", b"what do ya want for nothing?");
+        assert!(verify_hmac_bytes(b"Jefe", b"what do ya want for nothing?", &mac.to_uppercase()));
+    }
+
+    #[test]
+    fn test_verify_rejects_mismatch_and_malformed() {
+        let mac = hmac_bytes(b"key", b"content");
+        assert!(!verify_hmac_bytes(b"other key", b"content", &mac));
+        assert!(!verify_hmac_bytes(b"key", b"contents", &mac));
+        assert!(!verify_hmac_bytes(b"key", b"content", &mac[..62]));
+        assert!(!verify_hmac_bytes(b"key", b"content", &format!("{}zz", &mac[..62])));
+        assert!(!verify_hmac_bytes(b"key", b"content", ""));
+        assert!(!verify_hmac_bytes(b"key", b"content", "not hex at all"));
+    }
+
+    #[test]
+    fn test_empty_key_and_content() {
+        // Python: hmac.new(b"", b"", hashlib.sha256).hexdigest()
+        assert_eq!(
+            hmac_bytes(b"", b""),
+            "b613679a0814d9ec772f95d778c35fc5ff1697c493715653c6c712144292c5ad"
+        );
+    }
+
+    /// Official BLAKE3 test vector input: bytes 0, 1, .., 250, 0, 1, ..
+    #[test]
+    fn test_blake3_keyed_official_vectors() {
+        let key = b"whats the Elvish word for friend";
+        let vectors = [
+            (0, "92b2b75604ed3c761f9d6f62392c8a9227ad0ea3f09573e783f1498a4ed60d26"),
+            (1, "6d7878dfff2f485635d39013278ae14f1454b8c0a3a2d34bc1ab38228a80c95b"),
+            (1023, "c951ecdf03288d0fcc96ee3413563d8a6d3589547f2c2fb36d9786470f1b9d6e"),
+            (1024, "75c46f6f3d9eb4f55ecaaee480db732e6c2105546f1e675003687c31719c7ba4"),
+            (1025, "357dc55de0c7e382c900fd6e320acc04146be01db6a8ce7210b7189bd664ea69"),
+            (2048, "879cf1fa2ea0e79126cb1063617a05b6ad9d0b696d0d757cf053439f60a99dd1"),
+        ];
+        for (len, expected) in vectors {
+            assert_eq!(blake3_keyed(key, &blake3_vector_input(len)).unwrap(), expected, "input length {}", len);
+        }
+    }
+
+    #[test]
+    fn test_tenant_key_scheme_is_pinned() {
+        // Changing these values breaks every stored tenant digest
+        let master = [0x42; KEY_LEN];
+        let acme = derive_tenant_key(&master, "acme").unwrap();
+        let globex = derive_tenant_key(&master, "globex").unwrap();
+        assert_eq!(crate::hashing::to_hex(&acme), "d442018de716dbd1e5082578fcd7efaddc05a85e9a585cf5663e19b21aff6a24");
+        assert_eq!(crate::hashing::to_hex(&globex), "125720d91bd2c3e01e9b07d88f05555d77b004c6ae9edf081b8438edadf73cba");
+        assert_eq!(blake3_keyed(&acme, b"shared content").unwrap(), "c77b7bea4de402f99fedf4d092b4250b691bcf79a7627c0d0d43027bc009ef07");
+        assert_eq!(blake3_keyed(&globex, b"shared content").unwrap(), "05ab939f99a814f2a859351da0cc7e2c820923e2f2a5d0212e9c7a6f0f73cc18");
+    }
+
+    #[test]
+    fn test_tenant_key_matches_documented_construction() {
+        let master = blake3_vector_input(KEY_LEN);
+        let mut material = master.clone();
+        material.extend_from_slice("tenant-7".as_bytes());
+        assert_eq!(
+            derive_tenant_key(&master, "tenant-7").unwrap(),
+            blake3::derive_key(TENANT_KEY_CONTEXT, &material)
+        );
+        assert_ne!(derive_tenant_key(&master, "tenant-7").unwrap(), derive_tenant_key(&master, "tenant-8").unwrap());
+    }
+
+    #[test]
+    fn test_wrong_key_length_is_an_error() {
+        assert_eq!(blake3_keyed(&[0; 16], b"x").unwrap_err(), "BLAKE3 key must be 32 bytes, got 16");
+        assert_eq!(derive_tenant_key(&[0; 33], "t").unwrap_err(), "BLAKE3 master key must be 32 bytes, got 33");
+        assert!(derive_tenant_key(&[], "t").is_err());
+    }
+}
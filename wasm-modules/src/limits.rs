@@ -0,0 +1,143 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Size limits - reject oversize input with `size_limit` instead of trapping
+//
+// WASM has no way to recover from a failed allocation: the module aborts
+// with an `unreachable` trap and takes the worker with it. Inputs are
+// checked against these limits before the expensive (and copying) work
+// starts. A limit of 0 means unlimited.
+//
+// Limits are per thread, which in the browser means per module instance.
+
+use crate::error::{ErrorCode, ReconError};
+use serde::Serialize;
+use std::cell::Cell;
+
+/// Limits in force for the current thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Limits {
+    /// Largest single input, and largest total across one batch, in bytes
+    pub max_input_bytes: usize,
+    /// Most elements accepted in one batch call
+    pub max_batch_items: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_input_bytes: 256 * 1024 * 1024,
+            max_batch_items: 100_000,
+        }
+    }
+}
+
+thread_local! {
+    static LIMITS: Cell<Limits> = Cell::new(Limits::default());
+}
+
+/// Replace the limits for the current thread
+pub fn set(limits: Limits) {
+    LIMITS.with(|cell| cell.set(limits));
+}
+
+/// The limits for the current thread
+pub fn get() -> Limits {
+    LIMITS.with(Cell::get)
+}
+
+/// Check the size of a single input
+pub fn check_input(bytes: usize) -> Result<(), ReconError> {
+    let max = get().max_input_bytes;
+    if max != 0 && bytes > max {
+        return Err(ReconError::new(
+            ErrorCode::SizeLimit,
+            format!("Input is {} bytes, over the {}-byte limit", bytes, max),
+        ));
+    }
+    Ok(())
+}
+
+/// Running total for one batch: the item count is checked up front,
+/// element sizes as they are added
+pub struct BatchBudget {
+    max_bytes: usize,
+    used: usize,
+}
+
+impl BatchBudget {
+    pub fn new(items: usize) -> Result<Self, ReconError> {
+        let limits = get();
+        if limits.max_batch_items != 0 && items > limits.max_batch_items {
+            return Err(ReconError::new(
+                ErrorCode::SizeLimit,
+                format!("Batch has {} items, over the {}-item limit", items, limits.max_batch_items),
+            ));
+        }
+        Ok(BatchBudget { max_bytes: limits.max_input_bytes, used: 0 })
+    }
+
+    /// Count `bytes` for the element at `index`
+    pub fn add(&mut self, index: usize, bytes: usize) -> Result<(), ReconError> {
+        self.used = self.used.saturating_add(bytes);
+        if self.max_bytes != 0 && self.used > self.max_bytes {
+            return Err(ReconError::new(
+                ErrorCode::SizeLimit,
+                format!("Batch exceeds the {}-byte limit at item {}", self.max_bytes, index),
+            )
+            .at(index));
+        }
+        Ok(())
+    }
+}
+
+/// Check a whole batch given the size of each element
+pub fn check_batch(sizes: impl ExactSizeIterator<Item = usize>) -> Result<(), ReconError> {
+    let mut budget = BatchBudget::new(sizes.len())?;
+    for (index, bytes) in sizes.enumerate() {
+        budget.add(index, bytes)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each test runs on its own thread, so limits set here do not leak
+
+    #[test]
+    fn test_defaults() {
+        assert_eq!(get(), Limits { max_input_bytes: 256 * 1024 * 1024, max_batch_items: 100_000 });
+        assert!(check_input(1024 * 1024).is_ok());
+    }
+
+    #[test]
+    fn test_single_input_limit() {
+        set(Limits { max_input_bytes: 10, max_batch_items: 0 });
+        assert!(check_input(10).is_ok());
+        let err = check_input(11).unwrap_err();
+        assert_eq!(err.code, ErrorCode::SizeLimit);
+        assert_eq!(err.message, "Input is 11 bytes, over the 10-byte limit");
+        assert_eq!(err.index, None);
+    }
+
+    #[test]
+    fn test_batch_limits() {
+        set(Limits { max_input_bytes: 10, max_batch_items: 3 });
+        assert!(check_batch([4, 3, 3].into_iter()).is_ok());
+
+        let err = check_batch([1, 1, 1, 1].into_iter()).unwrap_err();
+        assert_eq!(err.code, ErrorCode::SizeLimit);
+        assert_eq!(err.index, None);
+
+        let err = check_batch([4, 4, 4].into_iter()).unwrap_err();
+        assert_eq!((err.code, err.index), (ErrorCode::SizeLimit, Some(2)));
+    }
+
+    #[test]
+    fn test_zero_means_unlimited() {
+        set(Limits { max_input_bytes: 0, max_batch_items: 0 });
+        assert!(check_input(usize::MAX).is_ok());
+        assert!(check_batch(std::iter::repeat_n(usize::MAX, 1000)).is_ok());
+    }
+}
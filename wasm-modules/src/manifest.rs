@@ -0,0 +1,267 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Snapshot manifests - the id to hash list two peers compare before syncing
+//
+// A manifest is a JSON object in RFC 8785 canonical form:
+//
+//     {"entries":{"doc-1":"<hash>",...},"integrity":"<sha256>","version":1}
+//
+// `entries` maps each document id to its hash; canonical form sorts the ids.
+// `integrity` is the SHA-256 hex digest of the canonical form of every
+// other member of the object. Readers verify it over whatever members are
+// present, so a later writer can add fields without breaking this reader
+// as long as `version` stays 1; a different version is rejected outright
+// because its integrity rule may differ.
+
+use crate::canonical;
+use crate::error::{ErrorCode, ReconError};
+use crate::hashing::HashAlgorithm;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Manifest format written by `Manifest::encode`
+pub const MANIFEST_VERSION: u64 = 1;
+
+/// Entries as supplied by callers
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum ManifestEntries {
+    List(Vec<ManifestEntry>),
+    Map(BTreeMap<String, String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ManifestEntry {
+    pub id: String,
+    pub hash: String,
+}
+
+/// Document hashes by id
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub entries: BTreeMap<String, String>,
+}
+
+/// Differences from a local manifest to a remote one, ids ascending
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ManifestDiff {
+    /// Only in the remote manifest
+    pub added: Vec<String>,
+    /// Only in the local manifest
+    pub removed: Vec<String>,
+    /// In both, with different hashes
+    pub changed: Vec<String>,
+    pub unchanged_count: usize,
+}
+
+impl Manifest {
+    /// Collect entries, rejecting an id given twice
+    pub fn from_entries(entries: ManifestEntries) -> Result<Self, ReconError> {
+        let list = match entries {
+            ManifestEntries::Map(entries) => return Ok(Manifest { entries }),
+            ManifestEntries::List(list) => list,
+        };
+
+        let mut entries = BTreeMap::new();
+        for (index, entry) in list.into_iter().enumerate() {
+            if entries.insert(entry.id.clone(), entry.hash).is_some() {
+                return Err(ReconError::invalid_input(format!("Duplicate document id: {}", entry.id)).at(index));
+            }
+        }
+        Ok(Manifest { entries })
+    }
+
+    /// The canonical manifest text
+    pub fn encode(&self) -> String {
+        let mut object = serde_json::Map::new();
+        object.insert("entries".to_string(), serde_json::json!(self.entries));
+        object.insert("version".to_string(), MANIFEST_VERSION.into());
+        let integrity = integrity(&object).expect("string maps always canonicalize");
+        object.insert("integrity".to_string(), integrity.into());
+        canonicalize_value(&object).expect("string maps always canonicalize")
+    }
+
+    /// Parse and verify manifest text
+    ///
+    /// Malformed text fails with `decode_error`, a version other than 1 with
+    /// `unsupported_version` and a digest that does not match the contents
+    /// with `integrity_mismatch`.
+    pub fn parse(text: &str) -> Result<Self, ReconError> {
+        // Canonicalizing first rejects duplicate members, which serde_json
+        // would otherwise resolve silently
+        canonical::canonicalize(text).map_err(|e| ReconError::decode(format!("Invalid manifest: {}", e)))?;
+        let mut object: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(text).map_err(|e| ReconError::decode(format!("Invalid manifest: {}", e)))?;
+
+        match object.get("version").and_then(serde_json::Value::as_u64) {
+            Some(MANIFEST_VERSION) => {}
+            Some(version) => {
+                return Err(ReconError::new(
+                    ErrorCode::UnsupportedVersion,
+                    format!("Unsupported manifest version {}", version),
+                ))
+            }
+            None => return Err(ReconError::decode("Invalid manifest: missing integer version")),
+        }
+
+        let claimed = match object.remove("integrity") {
+            Some(serde_json::Value::String(claimed)) => claimed,
+            _ => return Err(ReconError::decode("Invalid manifest: missing integrity digest")),
+        };
+        if integrity(&object)? != claimed.to_ascii_lowercase() {
+            return Err(ReconError::new(
+                ErrorCode::IntegrityMismatch,
+                "Manifest integrity digest does not match its contents",
+            ));
+        }
+
+        let entries = object.remove("entries").unwrap_or_default();
+        let entries = serde_json::from_value(entries)
+            .map_err(|e| ReconError::decode(format!("Invalid manifest entries: {}", e)))?;
+        Ok(Manifest { entries })
+    }
+
+    /// What changed going from `self` to `remote`
+    pub fn diff(&self, remote: &Manifest) -> ManifestDiff {
+        let mut diff = ManifestDiff::default();
+        for (id, hash) in &self.entries {
+            match remote.entries.get(id) {
+                None => diff.removed.push(id.clone()),
+                Some(remote_hash) if remote_hash != hash => diff.changed.push(id.clone()),
+                Some(_) => diff.unchanged_count += 1,
+            }
+        }
+        diff.added = remote.entries.keys().filter(|id| !self.entries.contains_key(*id)).cloned().collect();
+        diff
+    }
+}
+
+/// SHA-256 hex digest of the canonical form of `object`
+fn integrity(object: &serde_json::Map<String, serde_json::Value>) -> Result<String, ReconError> {
+    let canonical = canonicalize_value(object)?;
+    Ok(HashAlgorithm::Sha256.hash(canonical.as_bytes()))
+}
+
+fn canonicalize_value(object: &serde_json::Map<String, serde_json::Value>) -> Result<String, ReconError> {
+    let json = serde_json::to_string(object).map_err(|e| ReconError::serialization(e.to_string()))?;
+    canonical::canonicalize(&json).map_err(ReconError::decode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(pairs: &[(&str, &str)]) -> Manifest {
+        Manifest { entries: pairs.iter().map(|(id, hash)| (id.to_string(), hash.to_string())).collect() }
+    }
+
+    #[test]
+    fn test_encoding_is_canonical_and_round_trips() {
+        let entries: ManifestEntries =
+            serde_json::from_str(r#"[{ "id": "b", "hash": "22" }, { "id": "a", "hash": "11" }]"#).unwrap();
+        let built = Manifest::from_entries(entries).unwrap();
+        let text = built.encode();
+
+        let body = r#"{"entries":{"a":"11","b":"22"},"version":1}"#;
+        let digest = HashAlgorithm::Sha256.hash(body.as_bytes());
+        assert_eq!(text, format!(r#"{{"entries":{{"a":"11","b":"22"}},"integrity":"{}","version":1}}"#, digest));
+
+        // The object form gives the same manifest
+        let map: ManifestEntries = serde_json::from_str(r#"{ "a": "11", "b": "22" }"#).unwrap();
+        assert_eq!(Manifest::from_entries(map).unwrap().encode(), text);
+        assert_eq!(Manifest::parse(&text).unwrap(), built);
+    }
+
+    #[test]
+    fn test_duplicate_ids_rejected() {
+        let entries: ManifestEntries =
+            serde_json::from_str(r#"[{ "id": "a", "hash": "1" }, { "id": "b", "hash": "2" }, { "id": "a", "hash": "3" }]"#)
+                .unwrap();
+        let err = Manifest::from_entries(entries).unwrap_err();
+        assert_eq!((err.code, err.index), (ErrorCode::InvalidInput, Some(2)));
+    }
+
+    #[test]
+    fn test_diff() {
+        let local = manifest(&[("a", "1"), ("b", "2"), ("c", "3"), ("d", "4")]);
+        let remote = manifest(&[("b", "2"), ("c", "30"), ("d", "4"), ("e", "5")]);
+        assert_eq!(
+            local.diff(&remote),
+            ManifestDiff {
+                added: vec!["e".to_string()],
+                removed: vec!["a".to_string()],
+                changed: vec!["c".to_string()],
+                unchanged_count: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_empty_manifests() {
+        let empty = Manifest::default();
+        let text = empty.encode();
+        assert!(text.starts_with(r#"{"entries":{},"integrity":""#));
+        assert_eq!(Manifest::parse(&text).unwrap(), empty);
+        assert_eq!(empty.diff(&empty), ManifestDiff::default());
+
+        let one = manifest(&[("x", "1")]);
+        assert_eq!(empty.diff(&one).added, ["x"]);
+        assert_eq!(one.diff(&empty).removed, ["x"]);
+    }
+
+    #[test]
+    fn test_large_manifests() {
+        let local = Manifest {
+            entries: (0..10_000u32).map(|i| (format!("doc-{:05}", i), HashAlgorithm::Sha256.hash(&i.to_le_bytes()))).collect(),
+        };
+        let mut remote = local.clone();
+        for i in (0..10_000).step_by(100) {
+            remote.entries.insert(format!("doc-{:05}", i), "changed".to_string());
+        }
+        remote.entries.remove("doc-00001");
+        remote.entries.insert("doc-10000".to_string(), "new".to_string());
+
+        let parsed_local = Manifest::parse(&local.encode()).unwrap();
+        let parsed_remote = Manifest::parse(&remote.encode()).unwrap();
+        assert_eq!(parsed_local, local);
+
+        let diff = parsed_local.diff(&parsed_remote);
+        assert_eq!(diff.changed.len(), 100);
+        assert_eq!(diff.changed[1], "doc-00100");
+        assert_eq!(diff.removed, ["doc-00001"]);
+        assert_eq!(diff.added, ["doc-10000"]);
+        assert_eq!(diff.unchanged_count, 10_000 - 101);
+    }
+
+    #[test]
+    fn test_corruption_and_versions_rejected() {
+        let text = manifest(&[("a", "1")]).encode();
+
+        let tampered = text.replace(r#""a":"1""#, r#""a":"2""#);
+        assert_eq!(Manifest::parse(&tampered).unwrap_err().code, ErrorCode::IntegrityMismatch);
+
+        let digest_start = text.find("integrity").unwrap() + 12;
+        let mut bad_digest = text.clone();
+        bad_digest.replace_range(digest_start..digest_start + 4, "0000");
+        assert_eq!(Manifest::parse(&bad_digest).unwrap_err().code, ErrorCode::IntegrityMismatch);
+
+        let future = text.replace(r#""version":1"#, r#""version":2"#);
+        assert_eq!(Manifest::parse(&future).unwrap_err().code, ErrorCode::UnsupportedVersion);
+
+        for malformed in ["", "[]", "{", r#"{"entries":{},"version":1}"#, r#"{"version":1,"version":1}"#] {
+            assert_eq!(Manifest::parse(malformed).unwrap_err().code, ErrorCode::DecodeError, "{}", malformed);
+        }
+    }
+
+    #[test]
+    fn test_unknown_fields_tolerated() {
+        // A newer writer adds a member; it is covered by the digest like the rest
+        let body = r#"{"created_by":"peer-7","entries":{"a":"1"},"version":1}"#;
+        let digest = HashAlgorithm::Sha256.hash(body.as_bytes());
+        let text = format!(r#"{{ "version": 1, "created_by": "peer-7", "integrity": "{}", "entries": {{ "a": "1" }} }}"#, digest);
+        assert_eq!(Manifest::parse(&text).unwrap(), manifest(&[("a", "1")]));
+
+        let tampered = text.replace("peer-7", "peer-8");
+        assert_eq!(Manifest::parse(&tampered).unwrap_err().code, ErrorCode::IntegrityMismatch);
+    }
+}
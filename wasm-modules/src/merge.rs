@@ -0,0 +1,272 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Three-way merge - line-based diff3
+//
+// Both edits are diffed against the base. Base lines that are unchanged on
+// both sides are stable and act as anchors; every region between anchors
+// is taken from whichever side changed it. If both sides changed the same
+// region differently (including adjacent edits with no stable line
+// between them) the region is a conflict. A side that made exactly the
+// same change as the other merges cleanly.
+
+use crate::diff::{self, DiffOp};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Options for three-way merges
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct MergeOptions {
+    /// Write git-style conflict markers into `merged`; otherwise conflicting
+    /// regions keep the base text
+    pub mark_conflicts: bool,
+}
+
+/// A region both sides changed differently
+///
+/// Ranges are 0-based, end-exclusive line indices into each document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Conflict {
+    pub base_range: Range<usize>,
+    pub ours_range: Range<usize>,
+    pub theirs_range: Range<usize>,
+    pub base_lines: Vec<String>,
+    pub ours_lines: Vec<String>,
+    pub theirs_lines: Vec<String>,
+}
+
+/// Result of a three-way merge
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MergeResult {
+    pub merged: String,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Merge `ours` and `theirs`, both derived from `base`
+pub fn merge_three_way(base: &str, ours: &str, theirs: &str, options: &MergeOptions) -> MergeResult {
+    let base: Vec<&str> = base.split_inclusive('\n').collect();
+    let ours: Vec<&str> = ours.split_inclusive('\n').collect();
+    let theirs: Vec<&str> = theirs.split_inclusive('\n').collect();
+
+    let mut ids = HashMap::new();
+    let base_ids = diff::intern(&base, &mut ids);
+    let ours_ids = diff::intern(&ours, &mut ids);
+    let theirs_ids = diff::intern(&theirs, &mut ids);
+
+    let to_ours = matches(&base_ids, &ours_ids);
+    let to_theirs = matches(&base_ids, &theirs_ids);
+
+    let mut merged = String::new();
+    let mut conflicts = Vec::new();
+    let (mut b, mut o, mut t) = (0, 0, 0);
+
+    loop {
+        // Next base line kept by both sides, or the end of all three
+        let anchor = (b..base.len()).find_map(|i| Some((i, to_ours[i]?, to_theirs[i]?)));
+
+        let (bi, oi, ti) = anchor.unwrap_or((base.len(), ours.len(), theirs.len()));
+        if (bi, oi, ti) != (b, o, t) {
+            let region = Region {
+                base: &base[b..bi],
+                ours: &ours[o..oi],
+                theirs: &theirs[t..ti],
+            };
+            if let Some(resolved) = region.resolve() {
+                merged.extend(resolved.iter().copied());
+            } else {
+                if options.mark_conflicts {
+                    write_markers(&region, &mut merged);
+                } else {
+                    merged.extend(region.base.iter().copied());
+                }
+                conflicts.push(Conflict {
+                    base_range: b..bi,
+                    ours_range: o..oi,
+                    theirs_range: t..ti,
+                    base_lines: to_strings(region.base),
+                    ours_lines: to_strings(region.ours),
+                    theirs_lines: to_strings(region.theirs),
+                });
+            }
+        }
+
+        match anchor {
+            Some((bi, oi, ti)) => {
+                merged.push_str(base[bi]);
+                (b, o, t) = (bi + 1, oi + 1, ti + 1);
+            }
+            None => break,
+        }
+    }
+
+    MergeResult { merged, conflicts }
+}
+
+/// Lines between two anchors
+struct Region<'a> {
+    base: &'a [&'a str],
+    ours: &'a [&'a str],
+    theirs: &'a [&'a str],
+}
+
+impl<'a> Region<'a> {
+    /// The merged lines, or `None` if both sides changed the region differently
+    fn resolve(&self) -> Option<&'a [&'a str]> {
+        if self.ours == self.base {
+            Some(self.theirs)
+        } else if self.theirs == self.base || self.ours == self.theirs {
+            Some(self.ours)
+        } else {
+            None
+        }
+    }
+}
+
+/// For each base line, the index of the matching line in `other`
+fn matches(base: &[usize], other: &[usize]) -> Vec<Option<usize>> {
+    let mut matched = vec![None; base.len()];
+    for span in diff::diff_slices(base, other) {
+        if span.op == DiffOp::Equal {
+            for (b, o) in span.old.zip(span.new) {
+                matched[b] = Some(o);
+            }
+        }
+    }
+    matched
+}
+
+fn write_markers(region: &Region, out: &mut String) {
+    out.push_str("<<<<<<< ours\n");
+    push_lines(region.ours, out);
+    out.push_str("=======\n");
+    push_lines(region.theirs, out);
+    out.push_str(">>>>>>> theirs\n");
+}
+
+/// Append lines, terminating the last one so a marker can follow
+fn push_lines(lines: &[&str], out: &mut String) {
+    out.extend(lines.iter().copied());
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+fn to_strings(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|line| line.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge(base: &str, ours: &str, theirs: &str) -> MergeResult {
+        merge_three_way(base, ours, theirs, &MergeOptions::default())
+    }
+
+    fn merge_marked(base: &str, ours: &str, theirs: &str) -> MergeResult {
+        merge_three_way(base, ours, theirs, &MergeOptions { mark_conflicts: true })
+    }
+
+    const BASE: &str = "one\ntwo\nthree\nfour\nfive\nsix\n";
+
+    #[test]
+    fn test_non_overlapping_changes_merge_cleanly() {
+        let ours = "ONE\ntwo\nthree\nfour\nfive\nsix\n";
+        let theirs = "one\ntwo\nthree\nfour\nfive\nsix\nseven\n";
+        let result = merge(BASE, ours, theirs);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.merged, "ONE\ntwo\nthree\nfour\nfive\nsix\nseven\n");
+
+        let ours = "one\ntwo\nthree\nfive\nsix\n";
+        let theirs = "zero\none\ntwo\nthree\nfour\nfive\nsix\n";
+        assert_eq!(merge(BASE, ours, theirs).merged, "zero\none\ntwo\nthree\nfive\nsix\n");
+    }
+
+    #[test]
+    fn test_one_side_identical_to_base() {
+        let edited = "one\n2\nthree\nfour\n5\nsix\n";
+        assert_eq!(merge(BASE, BASE, edited), MergeResult { merged: edited.to_string(), conflicts: vec![] });
+        assert_eq!(merge(BASE, edited, BASE), MergeResult { merged: edited.to_string(), conflicts: vec![] });
+        assert_eq!(merge(BASE, BASE, BASE).merged, BASE);
+    }
+
+    #[test]
+    fn test_identical_change_does_not_conflict() {
+        let edited = "one\ntwo\nTHREE\nfour\nfive\n";
+        let result = merge(BASE, edited, edited);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.merged, edited);
+    }
+
+    #[test]
+    fn test_conflicting_change() {
+        let ours = "one\ntwo\nthree (ours)\nfour\nfive\nsix\n";
+        let theirs = "one\ntwo\nthree (theirs)\nfour\nfive\nSIX\n";
+        let result = merge(BASE, ours, theirs);
+
+        assert_eq!(
+            result.conflicts,
+            vec![Conflict {
+                base_range: 2..3,
+                ours_range: 2..3,
+                theirs_range: 2..3,
+                base_lines: vec!["three\n".to_string()],
+                ours_lines: vec!["three (ours)\n".to_string()],
+                theirs_lines: vec!["three (theirs)\n".to_string()],
+            }]
+        );
+        // The conflicting region keeps the base text; the clean change still applies
+        assert_eq!(result.merged, "one\ntwo\nthree\nfour\nfive\nSIX\n");
+    }
+
+    #[test]
+    fn test_conflict_markers() {
+        let ours = "one\ntwo\nthree (ours)\nfour\nfive\nsix\n";
+        let theirs = "one\ntwo\nthree (theirs)\nfour\nfive\nsix\n";
+        let result = merge_marked(BASE, ours, theirs);
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(
+            result.merged,
+            "one\ntwo\n<<<<<<< ours\nthree (ours)\n=======\nthree (theirs)\n>>>>>>> theirs\nfour\nfive\nsix\n"
+        );
+    }
+
+    #[test]
+    fn test_delete_against_edit_conflicts() {
+        let ours = "one\ntwo\nfour\nfive\nsix\n";
+        let theirs = "one\ntwo\nTHREE\nfour\nfive\nsix\n";
+        let result = merge_marked(BASE, ours, theirs);
+        assert_eq!(result.conflicts.len(), 1);
+        assert!(result.conflicts[0].ours_lines.is_empty());
+        assert_eq!(result.merged, "one\ntwo\n<<<<<<< ours\n=======\nTHREE\n>>>>>>> theirs\nfour\nfive\nsix\n");
+    }
+
+    #[test]
+    fn test_competing_insertions_at_end_conflict() {
+        let result = merge(BASE, &format!("{}ours\n", BASE), &format!("{}theirs\n", BASE));
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].base_range, 6..6);
+        assert_eq!(result.merged, BASE);
+    }
+
+    #[test]
+    fn test_missing_trailing_newline() {
+        let base = "a\nb\nc";
+        let result = merge(base, "A\nb\nc", "a\nb\nc\nd");
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.merged, "A\nb\nc\nd");
+
+        // Both sides touch the unterminated last line
+        let result = merge_marked(base, "a\nb\nx", "a\nb\ny");
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.merged, "a\nb\n<<<<<<< ours\nx\n=======\ny\n>>>>>>> theirs\n");
+    }
+
+    #[test]
+    fn test_empty_documents() {
+        assert_eq!(merge("", "", ""), MergeResult { merged: String::new(), conflicts: vec![] });
+        assert_eq!(merge("", "new\n", "").merged, "new\n");
+        assert_eq!(merge("", "a\n", "b\n").conflicts.len(), 1);
+    }
+}
@@ -0,0 +1,289 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Merkle trees over document chunks
+//
+// Tree shape and proof verification follow RFC 9162 §2.1 (Certificate
+// Transparency v2), which splits n leaves at the largest power of two
+// smaller than n, so odd leaf counts are handled deterministically
+// without duplicating nodes. Hashes are SHA-256 with domain separation:
+//
+//   chunk_hash = SHA-256(chunk)
+//   leaf       = SHA-256(0x00 || chunk_hash)
+//   node       = SHA-256(0x01 || left || right)
+//
+// A leaf preimage is always 33 bytes and a node preimage 65 bytes, and the
+// prefixes differ, so a leaf can never be passed off as an interior node.
+// Content is split into `chunk_size`-byte chunks; empty content is one
+// empty chunk.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub type Hash = [u8; 32];
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Membership proof for a single chunk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    /// Number of leaves in the tree the proof was issued for
+    pub leaf_count: usize,
+    /// Sibling hashes from the leaf up to the root, as hex
+    pub path: Vec<String>,
+}
+
+/// Merkle tree built from chunk hashes
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    leaves: Vec<Hash>,
+}
+
+impl MerkleTree {
+    /// Split `data` into `chunk_size`-byte chunks and build the tree
+    pub fn from_bytes(data: &[u8], chunk_size: usize) -> Result<Self, String> {
+        if chunk_size == 0 {
+            return Err("chunk_size must be greater than zero".to_string());
+        }
+
+        let leaves = if data.is_empty() {
+            vec![leaf_hash(&chunk_hash(&[]))]
+        } else {
+            data.chunks(chunk_size).map(|chunk| leaf_hash(&chunk_hash(chunk))).collect()
+        };
+
+        Ok(MerkleTree { leaves })
+    }
+
+    /// Number of chunks
+    pub fn leaf_count(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Root hash
+    pub fn root(&self) -> Hash {
+        subtree_root(&self.leaves)
+    }
+
+    /// Audit path for the chunk at `index`
+    pub fn proof(&self, index: usize) -> Option<MerkleProof> {
+        if index >= self.leaves.len() {
+            return None;
+        }
+
+        let mut path = Vec::new();
+        audit_path(index, &self.leaves, &mut path);
+
+        Some(MerkleProof {
+            leaf_count: self.leaves.len(),
+            path: path.iter().map(|h| crate::hashing::to_hex(h)).collect(),
+        })
+    }
+}
+
+/// SHA-256 of a raw chunk
+pub fn chunk_hash(chunk: &[u8]) -> Hash {
+    Sha256::digest(chunk).into()
+}
+
+/// Leaf node hash for a chunk hash
+pub fn leaf_hash(chunk_hash: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(chunk_hash);
+    hasher.finalize().into()
+}
+
+/// Interior node hash
+pub fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Verify that `chunk_hash` sits at `index` in the tree with `root`
+///
+/// Implements the inclusion proof check of RFC 9162 §2.1.3.2.
+pub fn verify(root: &Hash, chunk_hash: &Hash, proof: &[Hash], index: usize, leaf_count: usize) -> bool {
+    if index >= leaf_count {
+        return false;
+    }
+
+    let mut f_n = index;
+    let mut s_n = leaf_count - 1;
+    let mut r = leaf_hash(chunk_hash);
+
+    for p in proof {
+        if s_n == 0 {
+            return false;
+        }
+        if f_n & 1 == 1 || f_n == s_n {
+            r = node_hash(p, &r);
+            while f_n & 1 == 0 && f_n != 0 {
+                f_n >>= 1;
+                s_n >>= 1;
+            }
+        } else {
+            r = node_hash(&r, p);
+        }
+        f_n >>= 1;
+        s_n >>= 1;
+    }
+
+    s_n == 0 && &r == root
+}
+
+/// Largest power of two strictly less than `n` (n > 1)
+fn split_point(n: usize) -> usize {
+    let mut k = 1;
+    while k << 1 < n {
+        k <<= 1;
+    }
+    k
+}
+
+fn subtree_root(leaves: &[Hash]) -> Hash {
+    match leaves.len() {
+        1 => leaves[0],
+        n => {
+            let k = split_point(n);
+            node_hash(&subtree_root(&leaves[..k]), &subtree_root(&leaves[k..]))
+        }
+    }
+}
+
+fn audit_path(index: usize, leaves: &[Hash], path: &mut Vec<Hash>) {
+    let n = leaves.len();
+    if n <= 1 {
+        return;
+    }
+    let k = split_point(n);
+    if index < k {
+        audit_path(index, &leaves[..k], path);
+        path.push(subtree_root(&leaves[k..]));
+    } else {
+        audit_path(index - k, &leaves[k..], path);
+        path.push(subtree_root(&leaves[..k]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_path(proof: &MerkleProof) -> Vec<Hash> {
+        proof.path.iter().map(|h| crate::hashing::digest_from_hex(h).unwrap()).collect()
+    }
+
+    fn check_all_proofs(data: &[u8], chunk_size: usize) {
+        let tree = MerkleTree::from_bytes(data, chunk_size).unwrap();
+        let root = tree.root();
+        let chunks: Vec<&[u8]> = if data.is_empty() { vec![&[]] } else { data.chunks(chunk_size).collect() };
+        assert_eq!(tree.leaf_count(), chunks.len());
+
+        for (i, chunk) in chunks.iter().enumerate() {
+            let proof = tree.proof(i).unwrap();
+            let path = decode_path(&proof);
+            assert!(verify(&root, &chunk_hash(chunk), &path, i, proof.leaf_count), "chunk {}", i);
+        }
+    }
+
+    #[test]
+    fn test_single_chunk_document() {
+        let tree = MerkleTree::from_bytes(b"short", 1024).unwrap();
+        assert_eq!(tree.leaf_count(), 1);
+        assert_eq!(tree.root(), leaf_hash(&chunk_hash(b"short")));
+
+        let proof = tree.proof(0).unwrap();
+        assert!(proof.path.is_empty());
+        assert!(verify(&tree.root(), &chunk_hash(b"short"), &[], 0, 1));
+    }
+
+    #[test]
+    fn test_empty_content_is_one_empty_chunk() {
+        let tree = MerkleTree::from_bytes(b"", 16).unwrap();
+        assert_eq!(tree.leaf_count(), 1);
+        assert_eq!(tree.root(), leaf_hash(&chunk_hash(b"")));
+    }
+
+    #[test]
+    fn test_chunk_boundaries() {
+        assert_eq!(MerkleTree::from_bytes(&[7; 16], 16).unwrap().leaf_count(), 1);
+        assert_eq!(MerkleTree::from_bytes(&[7; 17], 16).unwrap().leaf_count(), 2);
+        assert_eq!(MerkleTree::from_bytes(&[7; 15], 16).unwrap().leaf_count(), 1);
+        assert_eq!(MerkleTree::from_bytes(&[7; 32], 16).unwrap().leaf_count(), 2);
+        assert_eq!(MerkleTree::from_bytes(&[7; 33], 16).unwrap().leaf_count(), 3);
+
+        assert_ne!(
+            MerkleTree::from_bytes(&[7; 16], 16).unwrap().root(),
+            MerkleTree::from_bytes(&[7; 17], 16).unwrap().root()
+        );
+    }
+
+    #[test]
+    fn test_proofs_verify_for_all_tree_sizes() {
+        let data: Vec<u8> = (0..256u32).map(|i| (i * 31 % 256) as u8).collect();
+        for leaves in 1..=17 {
+            check_all_proofs(&data[..leaves * 8], 8);
+            check_all_proofs(&data[..leaves * 8 - 3], 8);
+        }
+    }
+
+    #[test]
+    fn test_odd_leaf_shape() {
+        // Three leaves: root = node(node(l0, l1), l2)
+        let tree = MerkleTree::from_bytes(b"aabbc", 2).unwrap();
+        let l: Vec<Hash> = [&b"aa"[..], b"bb", b"c"].iter().map(|c| leaf_hash(&chunk_hash(c))).collect();
+        assert_eq!(tree.root(), node_hash(&node_hash(&l[0], &l[1]), &l[2]));
+    }
+
+    #[test]
+    fn test_tampered_proof_rejected() {
+        let data = b"0123456789abcdefghijklmnopqrstuvwxyz";
+        let tree = MerkleTree::from_bytes(data, 4).unwrap();
+        let root = tree.root();
+        let proof = tree.proof(3).unwrap();
+        let path = decode_path(&proof);
+        let chunk = chunk_hash(&data[12..16]);
+        assert!(verify(&root, &chunk, &path, 3, proof.leaf_count));
+
+        // Flipped bit in a sibling
+        let mut tampered = path.clone();
+        tampered[1][0] ^= 1;
+        assert!(!verify(&root, &chunk, &tampered, 3, proof.leaf_count));
+
+        // Wrong chunk, wrong index, wrong size, truncated and extended paths
+        assert!(!verify(&root, &chunk_hash(b"xxxx"), &path, 3, proof.leaf_count));
+        assert!(!verify(&root, &chunk, &path, 2, proof.leaf_count));
+        assert!(!verify(&root, &chunk, &path, 3, 4));
+        assert!(!verify(&root, &chunk, &path[..path.len() - 1], 3, proof.leaf_count));
+        let mut extended = path.clone();
+        extended.push([0; 32]);
+        assert!(!verify(&root, &chunk, &extended, 3, proof.leaf_count));
+        assert!(!verify(&root, &chunk, &path, 99, proof.leaf_count));
+    }
+
+    #[test]
+    fn test_leaf_cannot_pose_as_node() {
+        let a = leaf_hash(&chunk_hash(b"a"));
+        let b = leaf_hash(&chunk_hash(b"b"));
+        let mut preimage = Vec::new();
+        preimage.extend_from_slice(&a);
+        preimage.extend_from_slice(&b);
+        // Hashing the concatenated children as a chunk gives a different value
+        assert_ne!(leaf_hash(&chunk_hash(&preimage)), node_hash(&a, &b));
+    }
+
+    #[test]
+    fn test_zero_chunk_size_rejected() {
+        assert!(MerkleTree::from_bytes(b"abc", 0).is_err());
+    }
+
+    #[test]
+    fn test_out_of_range_proof() {
+        let tree = MerkleTree::from_bytes(b"abcd", 2).unwrap();
+        assert!(tree.proof(2).is_none());
+    }
+}
@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// MinHash signatures and LSH banding - candidate near-duplicates at scale
+//
+// A signature is, for each of `num_hashes` hash functions, the smallest
+// hash of any of the document's 3-word shingles (the shingles of
+// `similarity`, over content normalized with the default policy). Two
+// documents agree at a position with probability equal to the Jaccard
+// index of their shingle sets, so the fraction of matching positions
+// estimates it.
+//
+// Hash function i is a 64-bit finalizer over the shingle hash XOR seed i,
+// keeping the high 32 bits. The seeds come from a fixed SplitMix64 stream,
+// so signatures are the same across calls, builds and platforms, and a
+// signature of n hashes is a prefix of one of more.
+//
+// LSH splits each signature into `bands` bands of `rows` values. Documents
+// whose values agree on every row of some band share a bucket; a pair with
+// Jaccard index s shares at least one with probability 1 - (1 - s^rows)^bands,
+// a steep S-curve around (1 / bands)^(1 / rows).
+
+use crate::normalize::{self, NormalizeOptions};
+use crate::similarity::{self, DEFAULT_SHINGLE_SIZE};
+use std::collections::{BTreeSet, HashMap};
+
+/// Most hash functions per signature
+pub const MAX_HASHES: usize = 4096;
+
+/// MinHash signature of `content`; all `u32::MAX` for content without words
+pub fn signature(content: &str, num_hashes: usize) -> Result<Vec<u32>, String> {
+    if !(1..=MAX_HASHES).contains(&num_hashes) {
+        return Err(format!("num_hashes must be between 1 and {}, got {}", MAX_HASHES, num_hashes));
+    }
+    let normalized = normalize::normalize(content, &NormalizeOptions::default());
+    let words = similarity::words(&normalized);
+    let shingles = similarity::shingles(&words, DEFAULT_SHINGLE_SIZE.min(words.len()));
+
+    let seeds = seeds(num_hashes);
+    let mut signature = vec![u32::MAX; num_hashes];
+    for shingle in shingles {
+        for (slot, seed) in signature.iter_mut().zip(&seeds) {
+            *slot = (*slot).min((mix(shingle ^ seed) >> 32) as u32);
+        }
+    }
+    Ok(signature)
+}
+
+/// Fraction of positions at which two signatures agree
+pub fn estimate_jaccard(a: &[u32], b: &[u32]) -> Result<f64, String> {
+    if a.len() != b.len() || a.is_empty() {
+        return Err(format!("Signatures must be non-empty and the same length, got {} and {}", a.len(), b.len()));
+    }
+    let agreeing = a.iter().zip(b).filter(|(x, y)| x == y).count();
+    Ok(agreeing as f64 / a.len() as f64)
+}
+
+/// Groups of signature indices that share an LSH bucket
+///
+/// `bands * rows` must equal the signature length. Each group lists its
+/// indices in ascending order and appears once however many bands it
+/// collides in; groups are ordered by their indices.
+pub fn buckets(signatures: &[Vec<u32>], bands: usize, rows: usize) -> Result<Vec<Vec<usize>>, String> {
+    let Some(first) = signatures.first() else {
+        return Ok(Vec::new());
+    };
+    if bands == 0 || rows == 0 || bands.checked_mul(rows) != Some(first.len()) {
+        return Err(format!(
+            "bands ({}) times rows ({}) must equal the signature length ({})",
+            bands,
+            rows,
+            first.len()
+        ));
+    }
+    if let Some(index) = signatures.iter().position(|signature| signature.len() != first.len()) {
+        return Err(format!("Signature {} has {} values, expected {}", index, signatures[index].len(), first.len()));
+    }
+
+    let mut groups = BTreeSet::new();
+    for band in 0..bands {
+        let mut buckets: HashMap<&[u32], Vec<usize>> = HashMap::new();
+        for (index, signature) in signatures.iter().enumerate() {
+            buckets.entry(&signature[band * rows..(band + 1) * rows]).or_default().push(index);
+        }
+        groups.extend(buckets.into_values().filter(|indices| indices.len() > 1));
+    }
+    Ok(groups.into_iter().collect())
+}
+
+/// The first `count` seeds of the fixed SplitMix64 stream
+fn seeds(count: usize) -> Vec<u64> {
+    let mut state: u64 = 0x5265_636f_6e4d_4831; // "ReconMH1"
+    (0..count)
+        .map(|_| {
+            state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            mix(state)
+        })
+        .collect()
+}
+
+/// SplitMix64 finalizer
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A document of `len` words drawn from a 2000-word vocabulary
+    fn document(len: usize, seed: u64) -> Vec<String> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                format!("w{}", state % 2000)
+            })
+            .collect()
+    }
+
+    fn exact_jaccard(a: &str, b: &str) -> f64 {
+        let shingles = |text: &str| similarity::shingles(&similarity::words(text), DEFAULT_SHINGLE_SIZE);
+        similarity::jaccard(&shingles(a), &shingles(b))
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let first = signature("the quick brown fox jumps over the lazy dog", 8).unwrap();
+        assert_eq!(first, signature("the quick brown fox jumps over the lazy dog", 8).unwrap());
+        assert_eq!(first, signature("  the quick brown\r\nfox jumps over the lazy dog ", 8).unwrap());
+        // Pinned: a change here breaks every stored signature
+        assert_eq!(first[..4], [107957458, 15897103, 128046974, 270783748]);
+        // Shorter signatures are prefixes of longer ones
+        assert_eq!(signature("the quick brown fox jumps over the lazy dog", 64).unwrap()[..8], first[..]);
+        assert_eq!(signature("", 3).unwrap(), vec![u32::MAX; 3]);
+    }
+
+    #[test]
+    fn test_estimates_track_exact_jaccard() {
+        let base = document(300, 1);
+        for replaced in [0, 10, 40, 100, 300] {
+            let mut other = base.clone();
+            let fresh = document(replaced, 99);
+            for (i, word) in fresh.into_iter().enumerate() {
+                other[i * 300 / replaced.max(1)] = word;
+            }
+            let (a, b) = (base.join(" "), other.join(" "));
+            let exact = exact_jaccard(&a, &b);
+            let estimate = estimate_jaccard(&signature(&a, 256).unwrap(), &signature(&b, 256).unwrap()).unwrap();
+            assert!((estimate - exact).abs() < 0.08, "replaced {}: exact {} estimate {}", replaced, exact, estimate);
+        }
+    }
+
+    #[test]
+    fn test_near_duplicates_share_a_bucket() {
+        let original = document(200, 7);
+        let mut edited = original.clone();
+        edited[50] = "changed".to_string();
+        edited.insert(120, "inserted".to_string());
+        let docs = [original.join(" "), document(200, 8).join(" "), edited.join(" "), document(200, 9).join(" ")];
+
+        let signatures: Vec<Vec<u32>> = docs.iter().map(|doc| signature(doc, 128).unwrap()).collect();
+        // 16 bands of 8 rows: the S-curve threshold is about 0.71
+        assert_eq!(buckets(&signatures, 16, 8).unwrap(), vec![vec![0, 2]]);
+        assert!(buckets(&signatures, 128, 1).unwrap().iter().all(|group| group.len() >= 2));
+    }
+
+    #[test]
+    fn test_parameters_validated() {
+        let signatures = vec![vec![1, 2, 3, 4], vec![1, 2, 3, 5]];
+        assert_eq!(buckets(&signatures, 2, 2).unwrap(), vec![vec![0, 1]]);
+        assert!(buckets(&signatures, 3, 2).is_err());
+        assert!(buckets(&signatures, 0, 4).is_err());
+        assert!(buckets(&signatures, 2, 1).is_err());
+        assert!(buckets(&[vec![1, 2], vec![1]], 2, 1).unwrap_err().starts_with("Signature 1 has 1 values"));
+        assert!(buckets(&[], 2, 2).unwrap().is_empty());
+
+        assert!(signature("a", 0).is_err());
+        assert!(signature("a", MAX_HASHES + 1).is_err());
+        assert!(estimate_jaccard(&[1, 2], &[1]).is_err());
+        assert!(estimate_jaccard(&[], &[]).is_err());
+        assert_eq!(estimate_jaccard(&[1, 2, 3, 4], &[1, 2, 0, 4]).unwrap(), 0.75);
+    }
+}
@@ -0,0 +1,182 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Multihash digests for IPFS/IPLD interop
+//
+// A multihash is `varint(code) || varint(length) || digest`, where `code`
+// comes from the multicodec table (0x12 sha2-256, 0x1e blake3) and the
+// varints are unsigned LEB128. The text form used here is plain base58btc,
+// the same encoding as a CIDv0 (`Qm...`).
+
+use crate::hashing::{to_hex, HashAlgorithm};
+use crate::varint::{self, VarintError};
+
+/// Longest varint accepted when decoding (the multiformats spec limit)
+const MAX_VARINT_LEN: usize = 9;
+
+/// Multicodec code for an algorithm
+pub fn code(algorithm: HashAlgorithm) -> u64 {
+    match algorithm {
+        HashAlgorithm::Sha256 => 0x12,
+        HashAlgorithm::Blake3 => 0x1e,
+    }
+}
+
+/// Algorithm for a multicodec code
+pub fn from_code(code: u64) -> Option<HashAlgorithm> {
+    match code {
+        0x12 => Some(HashAlgorithm::Sha256),
+        0x1e => Some(HashAlgorithm::Blake3),
+        _ => None,
+    }
+}
+
+/// Algorithm for a multicodec name ("sha2-256" or "blake3")
+pub fn from_name(name: &str) -> Option<HashAlgorithm> {
+    match name {
+        "sha2-256" => Some(HashAlgorithm::Sha256),
+        "blake3" => Some(HashAlgorithm::Blake3),
+        _ => None,
+    }
+}
+
+/// Binary multihash of `data`
+pub fn multihash(algorithm: HashAlgorithm, data: &[u8]) -> Vec<u8> {
+    let digest = algorithm.digest(data);
+    let mut out = Vec::with_capacity(digest.len() + 2);
+    varint::write(code(algorithm), &mut out);
+    varint::write(digest.len() as u64, &mut out);
+    out.extend_from_slice(&digest);
+    out
+}
+
+/// Base58btc multihash of `data`
+pub fn encode(algorithm: HashAlgorithm, data: &[u8]) -> String {
+    bs58::encode(multihash(algorithm, data)).into_string()
+}
+
+/// Parse a base58btc multihash into its algorithm and digest
+///
+/// Rejects unknown codes, non-minimal varints, and lengths that do not
+/// match the algorithm or the remaining bytes.
+pub fn decode(mh: &str) -> Result<(HashAlgorithm, Vec<u8>), String> {
+    let bytes = bs58::decode(mh)
+        .into_vec()
+        .map_err(|e| format!("Invalid base58btc multihash: {}", e))?;
+
+    let (code, rest) = read_varint(&bytes)?;
+    let (length, digest) = read_varint(rest)?;
+    let algorithm = from_code(code).ok_or_else(|| format!("Unsupported multihash code: 0x{:x}", code))?;
+
+    if length != 32 {
+        return Err(format!("Multihash length {} does not match {} digest", length, algorithm_name(algorithm)));
+    }
+    if digest.len() as u64 != length {
+        return Err(format!("Multihash declares {} digest bytes but has {}", length, digest.len()));
+    }
+
+    Ok((algorithm, digest.to_vec()))
+}
+
+/// Digest of a base58btc multihash as lowercase hex
+pub fn to_hex_digest(mh: &str) -> Result<String, String> {
+    decode(mh).map(|(_, digest)| to_hex(&digest))
+}
+
+fn algorithm_name(algorithm: HashAlgorithm) -> &'static str {
+    match algorithm {
+        HashAlgorithm::Sha256 => "sha2-256",
+        HashAlgorithm::Blake3 => "blake3",
+    }
+}
+
+/// Read a minimally-encoded unsigned varint, returning it and the remaining bytes
+fn read_varint(bytes: &[u8]) -> Result<(u64, &[u8]), String> {
+    match varint::read(bytes, MAX_VARINT_LEN) {
+        Ok((value, len)) => Ok((value, &bytes[len..])),
+        Err(VarintError::NotMinimal) => Err("Multihash varint is not minimally encoded".to_string()),
+        Err(VarintError::TooLong | VarintError::Overflow) => Err("Multihash varint is too long".to_string()),
+        Err(VarintError::Truncated) => Err("Multihash is truncated".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_ipfs_vectors() {
+        // Block hashes reported by `ipfs add --only-hash` (CIDv0) are the
+        // sha2-256 multihash of the dag-pb block bytes
+        // Empty directory: `ipfs object new unixfs-dir`
+        assert_eq!(
+            encode(HashAlgorithm::Sha256, &[0x0a, 0x02, 0x08, 0x01]),
+            "QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nn"
+        );
+        // Empty file: `ipfs add --only-hash /dev/null`
+        assert_eq!(
+            encode(HashAlgorithm::Sha256, &[0x0a, 0x04, 0x08, 0x02, 0x18, 0x00]),
+            "QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH"
+        );
+    }
+
+    #[test]
+    fn test_prefix_and_round_trip() {
+        let mh = multihash(HashAlgorithm::Sha256, b"abc");
+        assert_eq!(&mh[..2], &[0x12, 0x20]);
+        assert_eq!(mh[2..], crate::core::hash_content("abc"));
+
+        let encoded = encode(HashAlgorithm::Sha256, b"abc");
+        assert!(encoded.starts_with("Qm"));
+        assert_eq!(to_hex_digest(&encoded).unwrap(), to_hex(&crate::core::hash_content("abc")));
+    }
+
+    #[test]
+    fn test_blake3_codec() {
+        let mh = multihash(HashAlgorithm::Blake3, b"abc");
+        assert_eq!(&mh[..2], &[0x1e, 0x20]);
+
+        let (algorithm, digest) = decode(&encode(HashAlgorithm::Blake3, b"abc")).unwrap();
+        assert_eq!(algorithm, HashAlgorithm::Blake3);
+        assert_eq!(to_hex(&digest), HashAlgorithm::Blake3.hash(b"abc"));
+    }
+
+    #[test]
+    fn test_codec_names() {
+        assert_eq!(from_name("sha2-256"), Some(HashAlgorithm::Sha256));
+        assert_eq!(from_name("blake3"), Some(HashAlgorithm::Blake3));
+        assert_eq!(from_name("md5"), None);
+    }
+
+    #[test]
+    fn test_varint() {
+        for value in [0u64, 1, 0x7f, 0x80, 0xb220, 0x3fff, 0x4000, u32::MAX as u64] {
+            let mut out = Vec::new();
+            varint::write(value, &mut out);
+            assert_eq!(read_varint(&out).unwrap(), (value, &[][..]));
+        }
+
+        assert!(read_varint(&[0x80, 0x00]).is_err());
+        assert!(read_varint(&[0x80]).is_err());
+        assert!(read_varint(&[]).is_err());
+        assert!(read_varint(&[0xff; 10]).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed() {
+        let digest = crate::hashing::sha256(b"abc");
+        let wrap = |prefix: &[u8], digest: &[u8]| {
+            let mut bytes = prefix.to_vec();
+            bytes.extend_from_slice(digest);
+            bs58::encode(bytes).into_string()
+        };
+
+        assert!(decode(&wrap(&[0x12, 0x20], &digest)).is_ok());
+        assert!(decode(&wrap(&[0x12, 0x20], &digest[..31])).is_err());
+        assert!(decode(&wrap(&[0x12, 0x20], &[&digest[..], &[0]].concat())).is_err());
+        assert!(decode(&wrap(&[0x12, 0x10], &digest[..16])).is_err());
+        assert!(decode(&wrap(&[0x13, 0x20], &digest)).is_err());
+        assert!(decode(&wrap(&[0x92, 0x00, 0x20], &digest)).is_err());
+        assert!(decode("").is_err());
+        assert!(decode("Qm0OIl").is_err());
+    }
+}
@@ -0,0 +1,645 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Content normalization - configurable whitespace and line-ending policy
+
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use unicode_normalization::{
+    is_nfc_quick, is_nfd_quick, is_nfkc_quick, is_nfkd_quick, IsNormalized, UnicodeNormalization,
+};
+
+/// Line terminator written to normalized output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    /// Unix `\n`
+    #[default]
+    Lf,
+    /// Windows `\r\n`
+    Crlf,
+    /// Keep each line's original terminator
+    Preserve,
+}
+
+/// Unicode normalization form
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnicodeForm {
+    /// Leave code points untouched
+    #[default]
+    None,
+    /// Canonical composition (é as U+00E9)
+    Nfc,
+    /// Canonical decomposition (é as e + U+0301)
+    Nfd,
+    /// Compatibility composition (also folds ligatures, full-width forms)
+    Nfkc,
+    /// Compatibility decomposition
+    Nfkd,
+}
+
+impl UnicodeForm {
+    /// Get form name as string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnicodeForm::None => "none",
+            UnicodeForm::Nfc => "nfc",
+            UnicodeForm::Nfd => "nfd",
+            UnicodeForm::Nfkc => "nfkc",
+            UnicodeForm::Nfkd => "nfkd",
+        }
+    }
+
+    /// Parse form from its name (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" => Some(UnicodeForm::None),
+            "nfc" => Some(UnicodeForm::Nfc),
+            "nfd" => Some(UnicodeForm::Nfd),
+            "nfkc" => Some(UnicodeForm::Nfkc),
+            "nfkd" => Some(UnicodeForm::Nfkd),
+            _ => None,
+        }
+    }
+}
+
+/// Normalization policy
+///
+/// `NormalizeOptions::default()` is the policy used by `normalize_content`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NormalizeOptions {
+    /// Line terminator for the output
+    pub line_ending: LineEnding,
+    /// Trim leading/trailing whitespace from the whole document
+    pub trim: bool,
+    /// Trim trailing whitespace from every line
+    pub trim_lines: bool,
+    /// Maximum consecutive blank lines to keep, `None` to keep all
+    pub collapse_blank_lines: Option<usize>,
+    /// Remove leading UTF-8 byte order marks
+    pub strip_bom: bool,
+    /// Collapse runs of spaces and tabs inside a line to a single space
+    pub collapse_spaces: bool,
+    /// Unicode normalization form, applied before whitespace handling
+    pub unicode_form: UnicodeForm,
+    /// Remove BOMs, zero-width spaces, word joiners, soft hyphens, bidi
+    /// controls and C0 controls other than tab and line breaks
+    pub strip_invisible: bool,
+    /// Replace non-breaking spaces (U+00A0, U+2007, U+202F) with a space
+    pub replace_nbsp: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        NormalizeOptions {
+            line_ending: LineEnding::Lf,
+            trim: true,
+            trim_lines: true,
+            collapse_blank_lines: Some(1),
+            strip_bom: false,
+            collapse_spaces: false,
+            unicode_form: UnicodeForm::None,
+            strip_invisible: false,
+            replace_nbsp: false,
+        }
+    }
+}
+
+/// A line of text and the terminator that followed it in the input
+struct Line<'a> {
+    text: Cow<'a, str>,
+    terminator: &'a str,
+}
+
+/// Normalize content according to `options`
+pub fn normalize(content: &str, options: &NormalizeOptions) -> String {
+    // Invisible characters can block composition, so they go before the
+    // Unicode pass
+    let visible = sanitize(content, options.strip_invisible, options.replace_nbsp);
+    let unicode = normalize_unicode(&visible, options.unicode_form);
+    let mut content: &str = &unicode;
+    if options.strip_bom {
+        content = content.trim_start_matches('\u{feff}');
+    }
+    if options.trim {
+        // BOMs hiding behind leading whitespace would otherwise surface on a second pass
+        let leading = |c: char| c.is_whitespace() || (options.strip_bom && c == '\u{feff}');
+        content = content.trim_start_matches(leading).trim_end();
+    }
+
+    let mut lines = split_lines(content);
+
+    for line in &mut lines {
+        if options.collapse_spaces {
+            line.text = collapse_spaces(&line.text).into();
+        }
+        if options.trim_lines {
+            let trimmed = line.text.trim_end();
+            if trimmed.len() != line.text.len() {
+                line.text = trimmed.to_string().into();
+            }
+        }
+    }
+
+    if let Some(max) = options.collapse_blank_lines {
+        lines = collapse_blank_lines(lines, max);
+    }
+
+    join_lines(&lines, options.line_ending)
+}
+
+/// Remove invisible characters and/or replace non-breaking spaces
+///
+/// ZWJ and ZWNJ are kept: they change how emoji sequences and several
+/// scripts render, so they are content rather than noise. `\r` is kept for
+/// the line-ending logic. Content needing no change is returned borrowed.
+pub fn sanitize(content: &str, strip_invisible: bool, replace_nbsp: bool) -> Cow<'_, str> {
+    let changes = |c: char| (strip_invisible && is_invisible(c)) || (replace_nbsp && is_nbsp(c));
+    if !content.chars().any(changes) {
+        return Cow::Borrowed(content);
+    }
+
+    content
+        .chars()
+        .filter(|&c| !(strip_invisible && is_invisible(c)))
+        .map(|c| if replace_nbsp && is_nbsp(c) { ' ' } else { c })
+        .collect::<String>()
+        .into()
+}
+
+/// Characters with no visible rendering that Word and web exports leave behind
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        // C0 controls except tab, line feed and carriage return
+        '\u{0}'..='\u{8}' | '\u{b}' | '\u{c}' | '\u{e}'..='\u{1f}'
+        // Soft hyphen
+        | '\u{ad}'
+        // Zero-width space, word joiner, invisible operators, BOM
+        | '\u{200b}' | '\u{2060}'..='\u{2064}' | '\u{feff}'
+        // Bidi marks, embeddings, overrides and isolates
+        | '\u{61c}' | '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}'
+    )
+}
+
+fn is_nbsp(c: char) -> bool {
+    matches!(c, '\u{a0}' | '\u{2007}' | '\u{202f}')
+}
+
+/// Apply a Unicode normalization form
+///
+/// Input already in the requested form (always the case for ASCII) is
+/// returned borrowed without allocating.
+pub fn normalize_unicode(content: &str, form: UnicodeForm) -> Cow<'_, str> {
+    if content.is_ascii() {
+        return Cow::Borrowed(content);
+    }
+
+    match form {
+        UnicodeForm::None => Cow::Borrowed(content),
+        UnicodeForm::Nfc => apply_form(content, is_nfc_quick(content.chars()), |s| s.nfc().collect()),
+        UnicodeForm::Nfd => apply_form(content, is_nfd_quick(content.chars()), |s| s.nfd().collect()),
+        UnicodeForm::Nfkc => apply_form(content, is_nfkc_quick(content.chars()), |s| s.nfkc().collect()),
+        UnicodeForm::Nfkd => apply_form(content, is_nfkd_quick(content.chars()), |s| s.nfkd().collect()),
+    }
+}
+
+/// Only run the full normalization when the quick check can't rule it out
+fn apply_form(content: &str, quick: IsNormalized, normalize: impl FnOnce(&str) -> String) -> Cow<'_, str> {
+    match quick {
+        IsNormalized::Yes => Cow::Borrowed(content),
+        IsNormalized::No | IsNormalized::Maybe => Cow::Owned(normalize(content)),
+    }
+}
+
+/// Split on `\n`, treating any `\r`s right before it as part of the terminator
+fn split_lines(content: &str) -> Vec<Line<'_>> {
+    let mut lines = Vec::new();
+    let mut rest = content;
+
+    while let Some(pos) = rest.find('\n') {
+        let text = rest[..pos].trim_end_matches('\r');
+        let terminator = &rest[text.len()..=pos];
+        lines.push(Line { text: text.into(), terminator });
+        rest = &rest[pos + 1..];
+    }
+
+    if !rest.is_empty() {
+        lines.push(Line { text: rest.into(), terminator: "" });
+    }
+
+    lines
+}
+
+/// Replace each run of spaces/tabs with a single space
+fn collapse_spaces(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_run = false;
+
+    for ch in text.chars() {
+        if ch == ' ' || ch == '\t' {
+            if !in_run {
+                out.push(' ');
+            }
+            in_run = true;
+        } else {
+            out.push(ch);
+            in_run = false;
+        }
+    }
+
+    out
+}
+
+/// Cap every run of blank lines at `max`
+///
+/// A line is blank if it is empty or whitespace-only; the first `max`
+/// lines of each run are kept. Runs of any length collapse the same way,
+/// so applying the pass twice gives the same result as applying it once.
+fn collapse_blank_lines(lines: Vec<Line<'_>>, max: usize) -> Vec<Line<'_>> {
+    let mut out = Vec::with_capacity(lines.len());
+    let mut run = 0;
+
+    for line in lines {
+        if line.text.trim().is_empty() {
+            run += 1;
+            if run > max {
+                continue;
+            }
+        } else {
+            run = 0;
+        }
+        out.push(line);
+    }
+
+    out
+}
+
+fn join_lines(lines: &[Line<'_>], line_ending: LineEnding) -> String {
+    let mut out = String::with_capacity(lines.iter().map(|l| l.text.len() + 2).sum());
+
+    for line in lines {
+        out.push_str(&line.text);
+        if line.terminator.is_empty() {
+            continue;
+        }
+        out.push_str(match line_ending {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Preserve => line.terminator,
+        });
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Rng;
+
+    /// The pre-options implementation of `normalize_content`, correct for
+    /// runs of up to two blank lines
+    fn legacy_normalize(content: &str) -> String {
+        content
+            .trim()
+            .replace("\r\n", "\n")
+            .lines()
+            .map(|line| line.trim_end())
+            .collect::<Vec<_>>()
+            .join("\n")
+            .split("\n\n\n")
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Random document made of words, whitespace and blank-line runs
+    fn random_document(rng: &mut Rng) -> String {
+        let pieces = [
+            "word", " ", "\t", "\n", "\r\n", "\r", "  \n", "\u{feff}", "e\u{301}", "\u{301}", "\u{200b}", "\u{a0}",
+            "\u{202e}", "\u{7}",
+        ];
+        let mut doc = String::new();
+        for _ in 0..rng.below(40) {
+            if rng.below(4) == 0 {
+                let newline = if rng.below(2) == 0 { "\n" } else { "\r\n" };
+                doc.push_str(&newline.repeat(rng.below(12)));
+            } else {
+                doc.push_str(pieces[rng.below(pieces.len() as u64)]);
+            }
+        }
+        doc
+    }
+
+    fn max_blank_run(normalized: &str) -> usize {
+        let mut max = 0;
+        let mut run = 0;
+        for line in normalized.lines() {
+            if line.trim().is_empty() {
+                run += 1;
+                max = max.max(run);
+            } else {
+                run = 0;
+            }
+        }
+        max
+    }
+
+    fn with(f: impl FnOnce(&mut NormalizeOptions)) -> NormalizeOptions {
+        let mut options = NormalizeOptions::default();
+        f(&mut options);
+        options
+    }
+
+    #[test]
+    fn test_defaults_match_legacy_behavior() {
+        let fixtures = [
+            "",
+            "   ",
+            "  Hello  \r\n\r\n\r\nWorld  ",
+            "a\n\nb",
+            "a\n\n\nb",
+            "line one \t\nline two\r\n\r\nline three\n",
+            "\u{feff}bom stays",
+            "a\r\r\nb",
+        ];
+        for fixture in fixtures {
+            assert_eq!(
+                normalize(fixture, &NormalizeOptions::default()),
+                legacy_normalize(fixture),
+                "fixture {:?}",
+                fixture
+            );
+        }
+    }
+
+    #[test]
+    fn test_blank_line_runs_collapse_to_one() {
+        let options = NormalizeOptions::default();
+        for blank in 1..=12 {
+            let content = format!("a{}b", "\n".repeat(blank + 1));
+            assert_eq!(normalize(&content, &options), "a\n\nb", "{} blank lines", blank);
+        }
+        assert_eq!(normalize("a\n\n\n\n\nb", &options), "a\n\nb");
+        assert_eq!(normalize(&normalize("a\n\n\n\n\nb", &options), &options), "a\n\nb");
+        assert_eq!(normalize("a\n\n\n\nb\n\n\n\n\n\n\nc", &options), "a\n\nb\n\nc");
+        assert_eq!(normalize("a\n  \n\t\n\nb", &options), "a\n\nb");
+    }
+
+    #[test]
+    fn test_whitespace_only_lines_count_as_blank() {
+        let options = with(|o| o.trim_lines = false);
+        assert_eq!(normalize("a\n  \n\t\n\nb", &options), "a\n  \nb");
+    }
+
+    #[test]
+    fn test_normalize_is_idempotent() {
+        let configs = [
+            NormalizeOptions::default(),
+            with(|o| o.collapse_blank_lines = Some(0)),
+            with(|o| o.collapse_blank_lines = Some(3)),
+            with(|o| o.collapse_blank_lines = None),
+            with(|o| o.trim = false),
+            with(|o| o.trim_lines = false),
+            with(|o| o.line_ending = LineEnding::Crlf),
+            with(|o| o.line_ending = LineEnding::Preserve),
+            with(|o| {
+                o.strip_bom = true;
+                o.collapse_spaces = true;
+                o.unicode_form = UnicodeForm::Nfc;
+            }),
+            with(|o| {
+                o.strip_invisible = true;
+                o.replace_nbsp = true;
+                o.collapse_spaces = true;
+                o.unicode_form = UnicodeForm::Nfc;
+            }),
+            with(|o| {
+                o.strip_invisible = true;
+                o.trim = false;
+                o.line_ending = LineEnding::Preserve;
+            }),
+        ];
+
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..2000 {
+            let doc = random_document(&mut rng);
+            for options in &configs {
+                let once = normalize(&doc, options);
+                let twice = normalize(&once, options);
+                assert_eq!(once, twice, "input {:?} with {:?}", doc, options);
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_blank_runs_respect_max() {
+        let mut rng = Rng(42);
+        for _ in 0..1000 {
+            let doc = random_document(&mut rng);
+            for max in 0..3 {
+                let options = with(|o| o.collapse_blank_lines = Some(max));
+                let normalized = normalize(&doc, &options);
+                assert!(max_blank_run(&normalized) <= max, "input {:?} max {}", doc, max);
+            }
+        }
+    }
+
+    #[test]
+    fn test_line_ending_crlf() {
+        let options = with(|o| o.line_ending = LineEnding::Crlf);
+        assert_eq!(normalize("a\nb\r\nc", &options), "a\r\nb\r\nc");
+    }
+
+    #[test]
+    fn test_line_ending_preserve() {
+        let options = with(|o| o.line_ending = LineEnding::Preserve);
+        assert_eq!(normalize("a  \nb\r\nc", &options), "a\nb\r\nc");
+    }
+
+    #[test]
+    fn test_trim_lines_disabled() {
+        let options = with(|o| o.trim_lines = false);
+        assert_eq!(normalize("a  \r\nb\t\nc", &options), "a  \nb\t\nc");
+    }
+
+    #[test]
+    fn test_collapse_blank_lines_max() {
+        let keep_all = with(|o| o.collapse_blank_lines = None);
+        assert_eq!(normalize("a\n\n\nb", &keep_all), "a\n\n\nb");
+
+        let keep_two = with(|o| o.collapse_blank_lines = Some(2));
+        assert_eq!(normalize("a\n\n\nb", &keep_two), "a\n\n\nb");
+        assert_eq!(normalize("a\n\n\n\nb", &keep_two), "a\n\n\nb");
+        assert_eq!(normalize("a\n\n\n\n\n\n\n\nb", &keep_two), "a\n\n\nb");
+
+        let keep_none = with(|o| o.collapse_blank_lines = Some(0));
+        assert_eq!(normalize("a\n\nb", &keep_none), "a\nb");
+    }
+
+    #[test]
+    fn test_strip_bom() {
+        let options = with(|o| o.strip_bom = true);
+        assert_eq!(normalize("\u{feff}  text", &options), "text");
+        assert_eq!(normalize("text\u{feff}", &options), "text\u{feff}");
+    }
+
+    #[test]
+    fn test_collapse_spaces() {
+        let options = with(|o| o.collapse_spaces = true);
+        assert_eq!(normalize("a  b\t\tc \t d", &options), "a b c d");
+    }
+
+    #[test]
+    fn test_trim_disabled_keeps_trailing_newline() {
+        let options = with(|o| o.trim = false);
+        assert_eq!(normalize("  a\nb\n", &options), "  a\nb\n");
+    }
+
+    #[test]
+    fn test_combined_cms_policy() {
+        let options = NormalizeOptions {
+            line_ending: LineEnding::Crlf,
+            trim: true,
+            trim_lines: true,
+            collapse_blank_lines: Some(1),
+            strip_bom: true,
+            collapse_spaces: true,
+            unicode_form: UnicodeForm::Nfc,
+            strip_invisible: false,
+            replace_nbsp: false,
+        };
+        assert_eq!(
+            normalize("\u{feff}Title  \n\n\nBody   text\there \n", &options),
+            "Title\r\n\r\nBody text here"
+        );
+    }
+
+    #[test]
+    fn test_combined_raw_policy() {
+        let options = NormalizeOptions {
+            line_ending: LineEnding::Preserve,
+            trim: false,
+            trim_lines: false,
+            collapse_blank_lines: None,
+            strip_bom: false,
+            collapse_spaces: false,
+            unicode_form: UnicodeForm::None,
+            strip_invisible: false,
+            replace_nbsp: false,
+        };
+        let content = "\u{feff} a \r\n\n\n\tb\n";
+        assert_eq!(normalize(content, &options), content);
+    }
+
+    #[test]
+    fn test_unicode_nfc_equivalence() {
+        let precomposed = "caf\u{e9}";
+        let combining = "cafe\u{301}";
+        assert_ne!(precomposed, combining);
+        assert_eq!(normalize_unicode(combining, UnicodeForm::Nfc), precomposed);
+        assert_eq!(normalize_unicode(precomposed, UnicodeForm::Nfd), combining);
+
+        let options = with(|o| o.unicode_form = UnicodeForm::Nfc);
+        assert_eq!(normalize(precomposed, &options), normalize(combining, &options));
+    }
+
+    #[test]
+    fn test_unicode_hangul() {
+        // 한 = U+D55C = ᄒ U+1112 + ᅡ U+1161 + ᆫ U+11AB
+        let syllable = "\u{d55c}";
+        let jamo = "\u{1112}\u{1161}\u{11ab}";
+        assert_eq!(normalize_unicode(jamo, UnicodeForm::Nfc), syllable);
+        assert_eq!(normalize_unicode(syllable, UnicodeForm::Nfd), jamo);
+        assert_eq!(normalize_unicode(jamo, UnicodeForm::Nfkc), syllable);
+    }
+
+    #[test]
+    fn test_unicode_combining_marks_at_boundaries() {
+        // Leading combining mark has nothing to compose with and survives
+        assert_eq!(normalize_unicode("\u{301}abc", UnicodeForm::Nfc), "\u{301}abc");
+        // Trailing combining mark composes with the final base character
+        assert_eq!(normalize_unicode("abce\u{301}", UnicodeForm::Nfc), "abc\u{e9}");
+        // Canonical reordering of multiple marks
+        assert_eq!(
+            normalize_unicode("a\u{301}\u{323}", UnicodeForm::Nfc),
+            normalize_unicode("a\u{323}\u{301}", UnicodeForm::Nfc)
+        );
+    }
+
+    #[test]
+    fn test_unicode_compatibility_forms() {
+        assert_eq!(normalize_unicode("\u{fb01}", UnicodeForm::Nfkc), "fi");
+        assert_eq!(normalize_unicode("\u{ff21}", UnicodeForm::Nfkd), "A");
+        assert_eq!(normalize_unicode("\u{fb01}", UnicodeForm::Nfc), "\u{fb01}");
+    }
+
+    #[test]
+    fn test_unicode_ascii_passthrough_borrows() {
+        for form in [UnicodeForm::Nfc, UnicodeForm::Nfd, UnicodeForm::Nfkc, UnicodeForm::Nfkd] {
+            assert!(matches!(normalize_unicode("plain ascii", form), Cow::Borrowed(_)));
+        }
+        assert!(matches!(normalize_unicode("caf\u{e9}", UnicodeForm::Nfc), Cow::Borrowed(_)));
+        assert!(matches!(normalize_unicode("cafe\u{301}", UnicodeForm::None), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_unicode_form_names() {
+        for form in [UnicodeForm::None, UnicodeForm::Nfc, UnicodeForm::Nfd, UnicodeForm::Nfkc, UnicodeForm::Nfkd] {
+            assert_eq!(UnicodeForm::from_name(form.as_str()), Some(form));
+        }
+        assert_eq!(UnicodeForm::from_name("NFC"), Some(UnicodeForm::Nfc));
+        assert_eq!(UnicodeForm::from_name("nfx"), None);
+    }
+
+    #[test]
+    fn test_options_deserialize_partial() {
+        let options: NormalizeOptions =
+            serde_json::from_str(r#"{ "line_ending": "preserve", "collapse_blank_lines": null }"#).unwrap();
+        assert_eq!(options.line_ending, LineEnding::Preserve);
+        assert_eq!(options.collapse_blank_lines, None);
+        assert!(options.trim_lines);
+    }
+
+    #[test]
+    fn test_strip_invisible_fixture() {
+        let options = with(|o| o.strip_invisible = true);
+        let input = "\u{feff}Word\u{200b}export\u{2060} re\u{ad}con\u{ad}cile\u{0}\u{7}\u{1b}\n\
+                     \u{202e}bidi\u{202c} \u{200e}mark\u{200f}\u{2066}iso\u{2069}\t\u{61c}tab\u{b}\u{c}\n\
+                     nbsp\u{a0}stays";
+        assert_eq!(normalize(input, &options), "Wordexport reconcile\nbidi markiso\ttab\nnbsp\u{a0}stays");
+
+        let options = with(|o| {
+            o.strip_invisible = true;
+            o.replace_nbsp = true;
+        });
+        assert_eq!(normalize("a\u{a0}b\u{202f}c\u{2007}d\u{200b}", &options), "a b c d");
+    }
+
+    #[test]
+    fn test_strip_invisible_keeps_legitimate_content() {
+        let options = with(|o| {
+            o.strip_invisible = true;
+            o.replace_nbsp = true;
+        });
+        let content = "日本語のテキスト\ncafe\u{301} na\u{ef}ve\n\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467} \u{645}\u{6cc}\u{200c}\u{62e}\u{648}\u{627}\u{647}\u{645}";
+        assert_eq!(normalize(content, &options), content);
+        assert!(matches!(sanitize(content, true, true), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_strip_invisible_composes_with_trimming() {
+        let options = with(|o| {
+            o.strip_invisible = true;
+            o.replace_nbsp = true;
+            o.line_ending = LineEnding::Crlf;
+        });
+        // Invisible characters hiding whitespace from trim, and CR line endings
+        let input = "\u{200b}  title\u{a0}\u{200b}\r\n\u{feff}\r\n\r\nbody \u{2060}\r\n\u{200b}";
+        assert_eq!(normalize(input, &options), "title\r\n\r\nbody");
+
+        // Without the option nothing changes
+        assert_eq!(normalize("a\u{200b}b", &NormalizeOptions::default()), "a\u{200b}b");
+    }
+}
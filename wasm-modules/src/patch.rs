@@ -0,0 +1,397 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Unified diff patches - creation and strict application
+//
+// Patches use the `diff -u` text format: `---`/`+++` headers, then hunks
+// headed `@@ -start,len +start,len @@` with ` `, `-` and `+` lines. A line
+// without a trailing newline is followed by `\ No newline at end of file`,
+// so documents round-trip byte for byte. Application is strict: every
+// context and removed line must match the old document exactly at the
+// position the hunk header names.
+
+use crate::diff::{self, DiffOp};
+use std::collections::HashMap;
+
+/// Context lines around each change unless the caller overrides it
+pub const DEFAULT_CONTEXT: usize = 3;
+
+const NO_NEWLINE: &str = "\\ No newline at end of file\n";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Context,
+    Delete,
+    Insert,
+}
+
+impl Kind {
+    fn prefix(self) -> char {
+        match self {
+            Kind::Context => ' ',
+            Kind::Delete => '-',
+            Kind::Insert => '+',
+        }
+    }
+}
+
+/// Unified diff turning `old` into `new`; empty when they are identical
+pub fn create_patch(old: &str, new: &str, context: usize) -> String {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+
+    let mut ids = HashMap::new();
+    let old_ids = diff::intern(&old_lines, &mut ids);
+    let new_ids = diff::intern(&new_lines, &mut ids);
+
+    // One entry per line of the edit script
+    let mut script: Vec<(Kind, &str)> = Vec::new();
+    for span in diff::diff_slices(&old_ids, &new_ids) {
+        match span.op {
+            DiffOp::Equal => script.extend(old_lines[span.old].iter().map(|l| (Kind::Context, *l))),
+            DiffOp::Delete => script.extend(old_lines[span.old].iter().map(|l| (Kind::Delete, *l))),
+            DiffOp::Insert => script.extend(new_lines[span.new].iter().map(|l| (Kind::Insert, *l))),
+        }
+    }
+
+    let changes: Vec<usize> = (0..script.len()).filter(|&i| script[i].0 != Kind::Context).collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    // Line numbers on each side before every script entry
+    let mut positions = Vec::with_capacity(script.len() + 1);
+    let (mut o, mut n) = (0, 0);
+    for (kind, _) in &script {
+        positions.push((o, n));
+        match kind {
+            Kind::Context => (o, n) = (o + 1, n + 1),
+            Kind::Delete => o += 1,
+            Kind::Insert => n += 1,
+        }
+    }
+    positions.push((o, n));
+
+    let mut out = String::from("--- old\n+++ new\n");
+    let mut i = 0;
+    while i < changes.len() {
+        // Merge changes separated by at most 2 * context unchanged lines
+        let mut j = i;
+        while j + 1 < changes.len() && changes[j + 1] - changes[j] - 1 <= 2 * context {
+            j += 1;
+        }
+
+        let start = changes[i].saturating_sub(context);
+        let end = (changes[j] + 1 + context).min(script.len());
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(old_start, old_end - old_start),
+            range(new_start, new_end - new_start)
+        ));
+        for (kind, line) in &script[start..end] {
+            out.push(kind.prefix());
+            out.push_str(line);
+            if !line.ends_with('\n') {
+                out.push('\n');
+                out.push_str(NO_NEWLINE);
+            }
+        }
+
+        i = j + 1;
+    }
+
+    out
+}
+
+/// Hunk header range: 1-based start, with the length omitted when it is 1
+/// and the start naming the preceding line when it is 0
+fn range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+/// A parsed hunk
+struct Hunk {
+    header: String,
+    old_start: usize,
+    old_len: usize,
+    lines: Vec<(Kind, String)>,
+}
+
+/// A parsed unified diff
+pub struct Patch {
+    hunks: Vec<Hunk>,
+}
+
+impl Patch {
+    /// Parse a unified diff produced by `create_patch` (or `diff -u`);
+    /// fails only if the patch text itself is malformed
+    pub fn parse(patch: &str) -> Result<Patch, String> {
+        parse_patch(patch).map(|hunks| Patch { hunks })
+    }
+
+    /// Apply to `old`; fails if a hunk's context or deleted lines do not match
+    pub fn apply(&self, old: &str) -> Result<String, String> {
+        apply_hunks(old, &self.hunks)
+    }
+}
+
+/// Apply a unified diff produced by `create_patch` (or `diff -u`) to `old`
+pub fn apply_patch(old: &str, patch: &str) -> Result<String, String> {
+    Patch::parse(patch)?.apply(old)
+}
+
+fn apply_hunks(old: &str, hunks: &[Hunk]) -> Result<String, String> {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let mut out = String::with_capacity(old.len());
+    let mut cursor = 0;
+
+    for (number, hunk) in hunks.iter().enumerate() {
+        let number = number + 1;
+        // A zero-length range names the line before the hunk
+        let position = if hunk.old_len == 0 { hunk.old_start } else { hunk.old_start - 1 };
+        if position < cursor {
+            return Err(format!("Hunk {} ({}) overlaps the previous hunk", number, hunk.header));
+        }
+        if position > old_lines.len() {
+            return Err(format!(
+                "Hunk {} ({}) starts at line {} but the document has {} lines",
+                number,
+                hunk.header,
+                position + 1,
+                old_lines.len()
+            ));
+        }
+
+        out.extend(old_lines[cursor..position].iter().copied());
+        cursor = position;
+
+        for (kind, line) in &hunk.lines {
+            if *kind != Kind::Insert {
+                match old_lines.get(cursor) {
+                    Some(actual) if actual == line => {}
+                    actual => {
+                        return Err(format!(
+                            "Hunk {} ({}) does not apply at line {}: expected {:?}, found {:?}",
+                            number,
+                            hunk.header,
+                            cursor + 1,
+                            line,
+                            actual.copied().unwrap_or("end of document")
+                        ))
+                    }
+                }
+                cursor += 1;
+            }
+            if *kind != Kind::Delete {
+                out.push_str(line);
+            }
+        }
+    }
+
+    out.extend(old_lines[cursor..].iter().copied());
+    Ok(out)
+}
+
+fn parse_patch(patch: &str) -> Result<Vec<Hunk>, String> {
+    let lines: Vec<&str> = patch.split_inclusive('\n').collect();
+    let mut hunks: Vec<Hunk> = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+        if !line.starts_with("@@") {
+            // File headers and anything else between hunks
+            continue;
+        }
+
+        let header = line.trim_end().to_string();
+        let (old_start, old_len, new_len) =
+            parse_header(&header).ok_or_else(|| format!("Malformed hunk header at patch line {}: {}", i, header))?;
+        let mut hunk = Hunk { header, old_start, old_len, lines: Vec::new() };
+
+        let (mut old_seen, mut new_seen) = (0, 0);
+        while old_seen < old_len || new_seen < new_len || lines.get(i).is_some_and(|l| l.starts_with('\\')) {
+            let Some(body) = lines.get(i) else {
+                return Err(format!("Hunk {} ({}) is truncated", hunks.len() + 1, hunk.header));
+            };
+            i += 1;
+
+            let (kind, text) = match body.chars().next() {
+                Some(' ') => (Kind::Context, &body[1..]),
+                // Some tools strip the space from empty context lines
+                Some('\n') => (Kind::Context, *body),
+                Some('-') => (Kind::Delete, &body[1..]),
+                Some('+') => (Kind::Insert, &body[1..]),
+                Some('\\') => {
+                    let Some((_, previous)) = hunk.lines.last_mut() else {
+                        return Err(format!("Patch line {}: no line precedes the end-of-file marker", i));
+                    };
+                    if previous.ends_with('\n') {
+                        previous.pop();
+                    }
+                    continue;
+                }
+                _ => {
+                    return Err(format!(
+                        "Hunk {} ({}) has an invalid line at patch line {}: {:?}",
+                        hunks.len() + 1,
+                        hunk.header,
+                        i,
+                        body
+                    ))
+                }
+            };
+
+            match kind {
+                Kind::Context => (old_seen, new_seen) = (old_seen + 1, new_seen + 1),
+                Kind::Delete => old_seen += 1,
+                Kind::Insert => new_seen += 1,
+            }
+            if old_seen > old_len || new_seen > new_len {
+                return Err(format!("Hunk {} ({}) has more lines than its header declares", hunks.len() + 1, hunk.header));
+            }
+            hunk.lines.push((kind, text.to_string()));
+        }
+
+        hunks.push(hunk);
+    }
+
+    Ok(hunks)
+}
+
+/// Parse `@@ -a[,b] +c[,d] @@`, returning (a, b, d)
+fn parse_header(header: &str) -> Option<(usize, usize, usize)> {
+    let rest = header.strip_prefix("@@ -")?;
+    let (ranges, _) = rest.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+
+    let parse_range = |range: &str| -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_len) = parse_range(old)?;
+    let (_, new_len) = parse_range(new)?;
+    if old_len > 0 && old_start == 0 {
+        return None;
+    }
+    Some((old_start, old_len, new_len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Rng;
+
+    fn assert_round_trip(old: &str, new: &str, context: usize) {
+        let patch = create_patch(old, new, context);
+        assert_eq!(apply_patch(old, &patch).unwrap(), new, "old {:?} new {:?}\n{}", old, new, patch);
+    }
+
+    #[test]
+    fn test_matches_diff_u_format() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nb\nC\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        // Same hunks as `diff -u`
+        assert_eq!(
+            create_patch(old, new, 3),
+            "--- old\n+++ new\n\
+             @@ -1,6 +1,6 @@\n a\n b\n-c\n+C\n d\n e\n f\n\
+             @@ -8,3 +8,4 @@\n h\n i\n j\n+k\n"
+        );
+        // Wider context merges the two hunks
+        assert_eq!(create_patch(old, new, 4).matches("@@ -").count(), 1);
+    }
+
+    #[test]
+    fn test_identical_documents_give_empty_patch() {
+        assert_eq!(create_patch("same\n", "same\n", 3), "");
+        assert_eq!(apply_patch("same\n", "").unwrap(), "same\n");
+    }
+
+    #[test]
+    fn test_empty_files() {
+        assert_eq!(create_patch("", "a\n", 3), "--- old\n+++ new\n@@ -0,0 +1 @@\n+a\n");
+        assert_eq!(create_patch("a\n", "", 3), "--- old\n+++ new\n@@ -1 +0,0 @@\n-a\n");
+        assert_round_trip("", "a\nb", 3);
+        assert_round_trip("a\nb", "", 3);
+        assert_round_trip("", "", 3);
+    }
+
+    #[test]
+    fn test_missing_trailing_newline() {
+        let patch = create_patch("a\nb", "a\nb\n", 3);
+        assert_eq!(patch, "--- old\n+++ new\n@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n");
+        assert_round_trip("a\nb", "a\nb\n", 3);
+        assert_round_trip("a\nb\n", "a\nb", 0);
+        assert_round_trip("x\ny", "x\nz", 1);
+        assert_round_trip("keep\nlast", "new first\nkeep\nlast", 2);
+    }
+
+    #[test]
+    fn test_context_mismatch_names_hunk_and_line() {
+        let patch = create_patch("a\nb\nc\nd\n", "a\nb\nX\nd\n", 1);
+        let err = apply_patch("a\nB\nc\nd\n", &patch).unwrap_err();
+        assert_eq!(err, "Hunk 1 (@@ -2,3 +2,3 @@) does not apply at line 2: expected \"b\\n\", found \"B\\n\"");
+
+        let err = apply_patch("a\n", &patch).unwrap_err();
+        assert!(err.starts_with("Hunk 1 (@@ -2,3 +2,3 @@) does not apply at line 2"), "{}", err);
+    }
+
+    #[test]
+    fn test_malformed_patches() {
+        assert!(apply_patch("a\n", "@@ -1 +1 @@\n-a\n").unwrap_err().contains("truncated"));
+        assert!(apply_patch("a\n", "@@ -x +1 @@\n").unwrap_err().contains("Malformed hunk header"));
+        assert!(apply_patch("a\n", "@@ -1 +1 @@\n*a\n+b\n").unwrap_err().contains("invalid line"));
+        assert!(apply_patch("a\n", "@@ -1,2 +1 @@\n+b\n+c\n-a\n").unwrap_err().contains("more lines"));
+        assert!(apply_patch("a\n", "@@ -5 +5 @@\n-a\n+b\n").unwrap_err().contains("document has 1 lines"));
+    }
+
+    #[test]
+    fn test_applies_foreign_patch_with_stripped_empty_context() {
+        let patch = "--- a/doc\n+++ b/doc\n@@ -1,3 +1,3 @@\n one\n\n-three\n+3\n";
+        assert_eq!(apply_patch("one\n\nthree\n", patch).unwrap(), "one\n\n3\n");
+    }
+
+    #[test]
+    fn test_random_round_trip() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..1000 {
+            let lines = rng.next() % 12;
+            let old: Vec<String> = (0..lines).map(|_| format!("{}", (b'a' + (rng.next() % 5) as u8) as char)).collect();
+
+            // Random edits: replace, delete or insert lines
+            let mut new = old.clone();
+            for _ in 0..rng.next() % 4 {
+                let at = (rng.next() % (new.len() as u64 + 1)) as usize;
+                match rng.next() % 3 {
+                    0 if at < new.len() => new[at] = "changed".to_string(),
+                    1 if at < new.len() => {
+                        new.remove(at);
+                    }
+                    _ => new.insert(at, format!("new {}", rng.next() % 3)),
+                }
+            }
+
+            let join = |lines: &[String], trailing: bool| {
+                let mut text = lines.join("\n");
+                if trailing && !lines.is_empty() {
+                    text.push('\n');
+                }
+                text
+            };
+            let old = join(&old, rng.below(2) == 0);
+            let new = join(&new, rng.below(2) == 0);
+            assert_round_trip(&old, &new, (rng.next() % 4) as usize);
+        }
+    }
+}
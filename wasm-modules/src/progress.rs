@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Progress reporting for batch operations
+
+/// Items processed between progress reports unless the caller overrides it
+pub const DEFAULT_INTERVAL: usize = 1000;
+
+/// Calls `report(processed, total)` every `interval` items and once more
+/// after the last item
+///
+/// A report that returns an error is meant to abort the batch; `tick`
+/// hands the error back so callers can propagate it with `?`.
+pub struct Progress<F> {
+    total: usize,
+    interval: usize,
+    processed: usize,
+    report: F,
+}
+
+impl<E, F: FnMut(usize, usize) -> Result<(), E>> Progress<F> {
+    /// An `interval` of 0 is treated as 1
+    pub fn new(total: usize, interval: usize, report: F) -> Self {
+        Progress {
+            total,
+            interval: interval.max(1),
+            processed: 0,
+            report,
+        }
+    }
+
+    /// Record one processed item
+    pub fn tick(&mut self) -> Result<(), E> {
+        self.processed += 1;
+        if self.processed.is_multiple_of(self.interval) || self.processed == self.total {
+            (self.report)(self.processed, self.total)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(total: usize, interval: usize) -> Vec<(usize, usize)> {
+        let mut calls = Vec::new();
+        let mut progress = Progress::new(total, interval, |processed, total| {
+            calls.push((processed, total));
+            Ok::<(), ()>(())
+        });
+        for _ in 0..total {
+            progress.tick().unwrap();
+        }
+        calls
+    }
+
+    #[test]
+    fn test_reports_every_interval_and_at_end() {
+        assert_eq!(run(10, 4), vec![(4, 10), (8, 10), (10, 10)]);
+        assert_eq!(run(8, 4), vec![(4, 8), (8, 8)]);
+        assert_eq!(run(3, 1000), vec![(3, 3)]);
+        assert!(run(0, 10).is_empty());
+        assert_eq!(run(2, 0), vec![(1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn test_report_error_aborts() {
+        let mut processed_items = 0;
+        let mut progress = Progress::new(100, 10, |processed, _| if processed >= 30 { Err(processed) } else { Ok(()) });
+
+        let result: Result<(), usize> = (0..100).try_for_each(|_| {
+            processed_items += 1;
+            progress.tick()
+        });
+        assert_eq!(result, Err(30));
+        assert_eq!(processed_items, 30);
+    }
+}
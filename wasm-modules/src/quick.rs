@@ -0,0 +1,152 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Quick digests - a cheap "probably changed" check for large documents
+//
+// Scheme 1, the only one so far: inputs of up to SAMPLED_BYTES are hashed
+// in full. Larger inputs contribute their first and last 4 KiB and
+// WINDOWS windows of 1 KiB spaced evenly between them, about 24 KiB in all
+// whatever the input size. The length (as a little-endian u64) and the
+// sampled bytes go through BLAKE3 in key derivation mode with a context
+// naming the scheme, and the digest is the scheme byte followed by the
+// first 16 bytes of the output, as 34 hex characters.
+//
+// Different digests prove the inputs differ. Equal digests of large
+// inputs only say that the length and the sampled regions match: an edit
+// between windows that keeps the length is not seen, so a full hash is
+// still needed before trusting that nothing changed.
+
+/// Scheme identifier, the first byte of every digest
+pub const SCHEME: u8 = 1;
+
+const CONTEXT: &str = "recon-wasm 2025-06-01 quick digest scheme 1";
+const HEAD_BYTES: usize = 4096;
+const TAIL_BYTES: usize = 4096;
+const WINDOW_BYTES: usize = 1024;
+const WINDOWS: usize = 16;
+const DIGEST_BYTES: usize = 16;
+
+/// Inputs up to this size are hashed in full
+pub const SAMPLED_BYTES: usize = HEAD_BYTES + TAIL_BYTES + WINDOWS * WINDOW_BYTES;
+
+/// Quick digest of `data` as hex
+pub fn quick_digest(data: &[u8]) -> String {
+    let mut hasher = blake3::Hasher::new_derive_key(CONTEXT);
+    hasher.update(&(data.len() as u64).to_le_bytes());
+    for range in sample_ranges(data.len()) {
+        hasher.update(&data[range]);
+    }
+
+    let mut digest = [0u8; 1 + DIGEST_BYTES];
+    digest[0] = SCHEME;
+    hasher.finalize_xof().fill(&mut digest[1..]);
+    crate::hashing::to_hex(&digest)
+}
+
+/// Whether two quick digests match
+///
+/// Digests from different schemes never match, so callers fall back to a
+/// full comparison. Anything that is not a quick digest is an error.
+pub fn quick_digest_equal(a: &str, b: &str) -> Result<bool, String> {
+    let scheme = |digest: &str| -> Result<u8, String> {
+        let valid = digest.len() == 2 * (1 + DIGEST_BYTES) && digest.bytes().all(|b| b.is_ascii_hexdigit());
+        if !valid {
+            return Err(format!("Invalid quick digest: {:?}", digest));
+        }
+        u8::from_str_radix(&digest[..2], 16).map_err(|e| e.to_string())
+    };
+    let (scheme_a, scheme_b) = (scheme(a)?, scheme(b)?);
+    Ok(scheme_a == scheme_b && a.eq_ignore_ascii_case(b))
+}
+
+/// The regions of an input of `len` bytes that are hashed
+fn sample_ranges(len: usize) -> Vec<std::ops::Range<usize>> {
+    let mut ranges = Vec::with_capacity(WINDOWS + 2);
+    if len <= SAMPLED_BYTES {
+        ranges.push(0..len);
+        return ranges;
+    }
+
+    ranges.push(0..HEAD_BYTES);
+    // Window starts spread evenly from the end of the head to the start of
+    // the tail, both ends included
+    let first = HEAD_BYTES;
+    let last = len - TAIL_BYTES - WINDOW_BYTES;
+    for i in 0..WINDOWS {
+        let start = first + (last - first) * i / (WINDOWS - 1);
+        ranges.push(start..start + WINDOW_BYTES);
+    }
+    ranges.push(len - TAIL_BYTES..len);
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i.wrapping_mul(31) % 251) as u8).collect()
+    }
+
+    #[test]
+    fn test_shape_and_stability() {
+        let digest = quick_digest(b"hello");
+        assert_eq!(digest.len(), 34);
+        assert!(digest.starts_with("01"));
+        assert_eq!(quick_digest(b"hello"), digest);
+        // Pinned: a change here is a new scheme, not a fix
+        assert_eq!(digest, "01ea9a9ea06d244a8982f21474399d912f");
+        assert_eq!(quick_digest(&document(5_000_000)), quick_digest(&document(5_000_000)));
+    }
+
+    #[test]
+    fn test_small_inputs_are_hashed_in_full() {
+        for len in [0, 1, 100, SAMPLED_BYTES] {
+            let original = document(len);
+            for position in (0..len).step_by(997) {
+                let mut edited = original.clone();
+                edited[position] ^= 0xff;
+                assert_ne!(quick_digest(&original), quick_digest(&edited), "len {} position {}", len, position);
+            }
+        }
+        let full = sample_ranges(SAMPLED_BYTES);
+        assert_eq!((full.len(), full[0].clone()), (1, 0..SAMPLED_BYTES));
+    }
+
+    #[test]
+    fn test_large_inputs_are_sampled() {
+        let original = document(3_000_000);
+        let ranges = sample_ranges(original.len());
+        assert_eq!(ranges.len(), WINDOWS + 2);
+        assert_eq!(ranges.iter().map(|r| r.len()).sum::<usize>(), SAMPLED_BYTES);
+        assert!(ranges.windows(2).all(|pair| pair[0].end <= pair[1].start));
+
+        // An edit inside a middle window is caught
+        let window = &ranges[WINDOWS / 2];
+        let mut edited = original.clone();
+        edited[window.start + 10] ^= 1;
+        assert_ne!(quick_digest(&original), quick_digest(&edited));
+
+        // One between windows is not, which is why equality is not proof
+        let gap = ranges[WINDOWS / 2].end + 1;
+        assert!(gap < ranges[WINDOWS / 2 + 1].start);
+        let mut unseen = original.clone();
+        unseen[gap] ^= 1;
+        assert_eq!(quick_digest(&original), quick_digest(&unseen));
+
+        // Any length change is caught
+        assert_ne!(quick_digest(&original), quick_digest(&original[..original.len() - 1]));
+    }
+
+    #[test]
+    fn test_equality() {
+        let a = quick_digest(b"a");
+        let b = quick_digest(b"b");
+        assert!(quick_digest_equal(&a, &a.to_uppercase()).unwrap());
+        assert!(!quick_digest_equal(&a, &b).unwrap());
+
+        let other_scheme = format!("02{}", &a[2..]);
+        assert!(!quick_digest_equal(&a, &other_scheme).unwrap());
+        assert!(quick_digest_equal(&a, "01").is_err());
+        assert!(quick_digest_equal(&"zz".repeat(17), &a).is_err());
+    }
+}
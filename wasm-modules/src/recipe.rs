@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Recipes - persistable names for how a stored hash was computed
+//
+// A recipe id is a pipeline and a hash algorithm joined by `+`, such as
+// "norm-v1+sha256". The pipeline is either "raw" (the content as given) or
+// a versioned normalization policy whose options are pinned here, not
+// taken from `NormalizeOptions::default()`, so changing the default never
+// changes what an existing id computes. Store the id next to each hash and
+// `hash` reproduces the digest with the pipeline that made it.
+//
+// Every id this crate has shipped stays valid. Changing the default policy,
+// or `normalize` itself in a way that alters output under pinned options,
+// means adding a pipeline version and pointing `Pipeline::CURRENT` at it.
+//
+// Shipped pipelines:
+//
+// - raw      no normalization
+// - norm-v1  LF line endings, trim the document and every line, keep at
+//            most one consecutive blank line (the 0.2 default policy)
+
+use crate::hashing::HashAlgorithm;
+use crate::normalize::{self, LineEnding, NormalizeOptions, UnicodeForm};
+use std::fmt;
+
+/// How content is prepared before hashing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pipeline {
+    Raw,
+    NormV1,
+}
+
+impl Pipeline {
+    /// The pipeline matching `NormalizeOptions::default()`
+    pub const CURRENT: Pipeline = Pipeline::NormV1;
+
+    /// Every pipeline ever shipped, oldest first
+    pub const ALL: [Pipeline; 2] = [Pipeline::Raw, Pipeline::NormV1];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Pipeline::Raw => "raw",
+            Pipeline::NormV1 => "norm-v1",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Pipeline::ALL.into_iter().find(|pipeline| pipeline.as_str() == name)
+    }
+
+    /// The pinned normalization options, `None` for raw content
+    pub fn options(self) -> Option<NormalizeOptions> {
+        match self {
+            Pipeline::Raw => None,
+            Pipeline::NormV1 => Some(NormalizeOptions {
+                line_ending: LineEnding::Lf,
+                trim: true,
+                trim_lines: true,
+                collapse_blank_lines: Some(1),
+                strip_bom: false,
+                collapse_spaces: false,
+                unicode_form: UnicodeForm::None,
+                strip_invisible: false,
+                replace_nbsp: false,
+            }),
+        }
+    }
+}
+
+/// A pipeline and the algorithm applied to its output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Recipe {
+    pub pipeline: Pipeline,
+    pub algorithm: HashAlgorithm,
+}
+
+impl Recipe {
+    /// The recipe of the default normalization and algorithm
+    pub fn current() -> Self {
+        Recipe { pipeline: Pipeline::CURRENT, algorithm: HashAlgorithm::default() }
+    }
+
+    /// Parse a recipe id
+    pub fn parse(id: &str) -> Result<Self, String> {
+        let unknown = || format!("Unknown recipe: {:?}", id);
+        let (pipeline, algorithm) = id.split_once('+').ok_or_else(unknown)?;
+        Ok(Recipe {
+            pipeline: Pipeline::from_name(pipeline).ok_or_else(unknown)?,
+            algorithm: HashAlgorithm::from_name(algorithm).ok_or_else(unknown)?,
+        })
+    }
+
+    /// The recipe that hashes like `options` (`None` for raw content) and
+    /// `algorithm`, if any pipeline pins exactly those options
+    pub fn matching(options: Option<&NormalizeOptions>, algorithm: HashAlgorithm) -> Option<Self> {
+        Pipeline::ALL
+            .into_iter()
+            .find(|pipeline| pipeline.options().as_ref() == options)
+            .map(|pipeline| Recipe { pipeline, algorithm })
+    }
+
+    /// Hex digest of `content` prepared by this recipe's pipeline
+    pub fn hash(&self, content: &str) -> String {
+        match self.pipeline.options() {
+            Some(options) => self.algorithm.hash(normalize::normalize(content, &options).as_bytes()),
+            None => self.algorithm.hash(content.as_bytes()),
+        }
+    }
+
+    /// `hash` for bytes; a normalizing pipeline needs them to be UTF-8
+    pub fn hash_bytes(&self, data: &[u8]) -> Result<String, String> {
+        match self.pipeline {
+            Pipeline::Raw => Ok(self.algorithm.hash(data)),
+            _ => std::str::from_utf8(data)
+                .map(|content| self.hash(content))
+                .map_err(|e| format!("Recipe {} needs UTF-8 content: {}", self, e)),
+        }
+    }
+}
+
+impl fmt::Display for Recipe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}+{}", self.pipeline.as_str(), self.algorithm.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_recipe_is_the_default_policy() {
+        // Fails when the default changes without a new pipeline version
+        assert_eq!(Pipeline::CURRENT.options(), Some(NormalizeOptions::default()));
+        assert_eq!(Recipe::current().to_string(), "norm-v1+sha256");
+        assert_eq!(
+            Recipe::matching(Some(&NormalizeOptions::default()), HashAlgorithm::Sha256),
+            Some(Recipe::current())
+        );
+    }
+
+    #[test]
+    fn test_ids_round_trip() {
+        for pipeline in Pipeline::ALL {
+            for algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Blake3] {
+                let recipe = Recipe { pipeline, algorithm };
+                assert_eq!(Recipe::parse(&recipe.to_string()), Ok(recipe));
+            }
+        }
+        for id in ["", "norm-v1", "norm-v2+sha256", "norm-v1+md5", "raw+sha256+x", "NORM-V1+SHA256"] {
+            assert_eq!(Recipe::parse(id), Err(format!("Unknown recipe: {:?}", id)));
+        }
+    }
+
+    #[test]
+    fn test_pinned_digests() {
+        let recipe = Recipe::parse("norm-v1+sha256").unwrap();
+        assert_eq!(
+            recipe.hash("  Hello  \r\n\r\n\r\nWorld  "),
+            "286346b7b2f097fc1c8d8c0436c5e3b1b661768a549f7585a3bda9cc7af2b079"
+        );
+        assert_eq!(Recipe::parse("raw+sha256").unwrap().hash("abc"), HashAlgorithm::Sha256.hash(b"abc"));
+        assert_eq!(Recipe::parse("norm-v1+blake3").unwrap().hash("a\r\nb"), HashAlgorithm::Blake3.hash(b"a\nb"));
+        assert_eq!(Recipe::parse("raw+sha256").unwrap().hash_bytes(&[0xff]), Ok(HashAlgorithm::Sha256.hash(&[0xff])));
+        assert!(Recipe::current().hash_bytes(&[0xff]).is_err());
+    }
+
+    #[test]
+    fn test_old_recipe_survives_a_new_default() {
+        let content = "  Cafe\u{301}\u{200b} menu  \r\n";
+        let stored = "234a5abf99d846548ab5e80c947b13b4783efd35aad3be35a6e6f2eb44ccb576";
+        assert_eq!(Recipe::current().hash(content), stored);
+
+        // A later release composes accents and drops invisible characters
+        // by default
+        let bumped =
+            NormalizeOptions { unicode_form: UnicodeForm::Nfc, strip_invisible: true, ..NormalizeOptions::default() };
+        let rehashed = HashAlgorithm::Sha256.hash(normalize::normalize(content, &bumped).as_bytes());
+        assert_eq!(rehashed, HashAlgorithm::Sha256.hash("Caf\u{e9} menu".as_bytes()));
+        assert_ne!(rehashed, stored);
+
+        // The new policy matches no shipped pipeline, but the stored id
+        // still reproduces the old digest
+        assert_eq!(Recipe::matching(Some(&bumped), HashAlgorithm::Sha256), None);
+        assert_eq!(Recipe::parse("norm-v1+sha256").unwrap().hash(content), stored);
+    }
+}
@@ -302,7 +302,7 @@ pub fn parse_djot(content: &str) -> Result<DocumentStructure, Error> {
                     structure.elements.push(Element::Paragraph(text));
                 }
             }
-            Event::Start(Container::CodeBlock { language }, _) => {
+            Event::Start(Container::CodeBlock { .. }, _) => {
                 current_text.clear();
             }
             Event::End(Container::CodeBlock { language }) => {
@@ -314,7 +314,7 @@ pub fn parse_djot(content: &str) -> Result<DocumentStructure, Error> {
                 structure.code_blocks.push((lang.clone(), current_text.clone()));
                 structure.elements.push(Element::CodeBlock(lang, current_text.clone()));
             }
-            Event::Start(Container::Link(url, _), _) => {
+            Event::Start(Container::Link(_, _), _) => {
                 current_text.clear();
             }
             Event::End(Container::Link(url, _)) => {
@@ -424,7 +424,7 @@ pub fn has_heading(structure: &DocumentStructure, text: &str) -> bool {
 /// Check if document has a code block with a specific language
 pub fn has_code_language(structure: &DocumentStructure, lang: &str) -> bool {
     structure.code_blocks.iter().any(|(l, _)| {
-        l.as_ref().map_or(false, |l| l == lang)
+        l.as_ref().is_some_and(|l| l == lang)
     })
 }
 
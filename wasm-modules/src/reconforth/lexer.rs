@@ -61,7 +61,7 @@ fn next_token(&mut self) -> Result<Option<Token>, Error> {
         }
 
         // Definition start
-        if ch == ':' && self.peek_char_at(1).map_or(true, |c| c.is_whitespace()) {
+        if ch == ':' && self.peek_char_at(1).is_none_or(|c| c.is_whitespace()) {
             self.advance();
             return Ok(Some(Token::DefStart));
         }
@@ -256,6 +256,7 @@ fn test_tokenize_words() {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_tokenize_numbers() {
         let mut lexer = Lexer::new("42 -17 3.14");
         let tokens = lexer.tokenize().unwrap();
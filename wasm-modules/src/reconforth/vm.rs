@@ -15,6 +15,7 @@ pub struct VM {
     /// Data stack
     stack: Vec<Value>,
     /// Return stack (for quotation calls)
+    #[allow(dead_code)]
     return_stack: Vec<Vec<Token>>,
     /// Word dictionary
     dictionary: Dictionary,
@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Redaction-aware hashing - hash a document with some ranges masked
+//
+// Ranges are UTF-8 byte offsets, `start` inclusive and `end` exclusive, as
+// in the spans returned by the segmentation functions. Both ends must lie
+// on character boundaries; a range that would split a multi-byte character
+// is rejected rather than snapped, so a caller's offset bug cannot silently
+// widen or narrow a redaction. Ranges may come in any order and adjacent
+// ranges behave as one, but overlapping ranges are an error.
+//
+// Every character inside a range is replaced by PLACEHOLDER, so masked text
+// keeps its length in characters: two documents match when they differ
+// only in redacted values of the same length. Redacting hides the content
+// of a range, not how long it was.
+
+use crate::error::ReconError;
+use serde::Deserialize;
+
+/// Replacement for each redacted character (U+2588 FULL BLOCK)
+pub const PLACEHOLDER: char = '\u{2588}';
+
+/// A byte range to mask
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct Redaction {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// `content` with every range masked
+///
+/// Errors carry the index of the offending range in `ranges`.
+pub fn redact(content: &str, ranges: &[Redaction]) -> Result<String, ReconError> {
+    for (index, range) in ranges.iter().enumerate() {
+        if range.start > range.end || range.end > content.len() {
+            return Err(ReconError::invalid_input(format!(
+                "Redaction {}..{} is out of bounds for {} bytes of content",
+                range.start,
+                range.end,
+                content.len()
+            ))
+            .at(index));
+        }
+        if !content.is_char_boundary(range.start) || !content.is_char_boundary(range.end) {
+            return Err(ReconError::invalid_input(format!(
+                "Redaction {}..{} splits a multi-byte character",
+                range.start, range.end
+            ))
+            .at(index));
+        }
+    }
+
+    let mut order: Vec<usize> = (0..ranges.len()).filter(|&i| ranges[i].start < ranges[i].end).collect();
+    order.sort_by_key(|&i| (ranges[i].start, i));
+    for pair in order.windows(2) {
+        let (first, second) = (ranges[pair[0]], ranges[pair[1]]);
+        if second.start < first.end {
+            return Err(ReconError::invalid_input(format!(
+                "Redactions {}..{} and {}..{} overlap",
+                first.start, first.end, second.start, second.end
+            ))
+            .at(pair[1]));
+        }
+    }
+
+    let mut masked = String::with_capacity(content.len());
+    let mut position = 0;
+    for range in order.into_iter().map(|i| ranges[i]) {
+        masked.push_str(&content[position..range.start]);
+        masked.extend(content[range.start..range.end].chars().map(|_| PLACEHOLDER));
+        position = range.end;
+    }
+    masked.push_str(&content[position..]);
+    Ok(masked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    fn ranges(pairs: &[(usize, usize)]) -> Vec<Redaction> {
+        pairs.iter().map(|&(start, end)| Redaction { start, end }).collect()
+    }
+
+    fn range_of(content: &str, needle: &str) -> (usize, usize) {
+        let start = content.find(needle).unwrap();
+        (start, start + needle.len())
+    }
+
+    #[test]
+    fn test_different_redacted_values_match() {
+        let a = "Payee: Jane Roe, account 4417-1234, amount 100.00";
+        let b = "Payee: John Doe, account 9911-8765, amount 100.00";
+        let masked_a = redact(a, &ranges(&[range_of(a, "Jane Roe"), range_of(a, "4417-1234")])).unwrap();
+        // Ranges may be given in any order
+        let masked_b = redact(b, &ranges(&[range_of(b, "9911-8765"), range_of(b, "John Doe")])).unwrap();
+        assert_eq!(masked_a, "Payee: ████████, account █████████, amount 100.00");
+        assert_eq!(masked_a, masked_b);
+
+        // An unredacted difference still shows
+        let c = b.replace("100.00", "900.00");
+        let masked_c = redact(&c, &ranges(&[range_of(&c, "John Doe"), range_of(&c, "9911-8765")])).unwrap();
+        assert_ne!(masked_a, masked_c);
+    }
+
+    #[test]
+    fn test_placeholders_count_characters() {
+        assert_eq!(redact("name: Zoë!", &ranges(&[(6, 10)])).unwrap(), "name: ███!");
+        assert_eq!(redact("abc", &[]).unwrap(), "abc");
+        assert_eq!(redact("abc", &ranges(&[(1, 1)])).unwrap(), "abc");
+    }
+
+    #[test]
+    fn test_adjacent_ranges_merge() {
+        let content = "id=ABC123;";
+        let split = redact(content, &ranges(&[(6, 9), (3, 6)])).unwrap();
+        let whole = redact(content, &ranges(&[(3, 9)])).unwrap();
+        assert_eq!(split, "id=██████;");
+        assert_eq!(split, whole);
+    }
+
+    #[test]
+    fn test_offset_errors() {
+        let content = "café au lait";
+        let check = |pairs: &[(usize, usize)], index: usize, message: &str| {
+            let err = redact(content, &ranges(pairs)).unwrap_err();
+            assert_eq!((err.code, err.index), (ErrorCode::InvalidInput, Some(index)));
+            assert_eq!(err.message, message);
+        };
+
+        check(&[(0, 2), (5, 14)], 1, "Redaction 5..14 is out of bounds for 13 bytes of content");
+        check(&[(4, 2)], 0, "Redaction 4..2 is out of bounds for 13 bytes of content");
+        // é occupies bytes 3..5
+        check(&[(0, 4)], 0, "Redaction 0..4 splits a multi-byte character");
+        check(&[(8, 12), (0, 3), (6, 9)], 0, "Redactions 6..9 and 8..12 overlap");
+    }
+}
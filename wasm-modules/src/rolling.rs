@@ -0,0 +1,193 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Rolling hash - Rabin-Karp over a fixed byte window
+//
+// The hash of a window b_0..b_{w-1} is sum(b_i * B^(w-1-i)) mod 2^64.
+// Sliding drops the oldest byte's term and shifts the rest up by one power,
+// so each byte costs O(1). Matches are only candidates: windows with equal
+// hashes are compared byte for byte before being reported.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// Polynomial base (the 64-bit FNV prime, odd so powers never vanish)
+const BASE: u64 = 0x0000_0100_0000_01b3;
+
+/// Rabin-Karp hash over the last `window` bytes pushed
+#[derive(Debug, Clone)]
+pub struct RollingHash {
+    window: usize,
+    /// BASE^(window - 1), the weight of the byte about to leave the window
+    top_power: u64,
+    hash: u64,
+    bytes: VecDeque<u8>,
+}
+
+impl RollingHash {
+    pub fn new(window: usize) -> Result<Self, String> {
+        if window == 0 {
+            return Err("window_size must be greater than zero".to_string());
+        }
+        let top_power = (1..window).fold(1u64, |power, _| power.wrapping_mul(BASE));
+        Ok(RollingHash { window, top_power, hash: 0, bytes: VecDeque::with_capacity(window) })
+    }
+
+    /// Add a byte, dropping the oldest once the window is full; returns the
+    /// hash of the bytes now in the window
+    pub fn push_byte(&mut self, byte: u8) -> u64 {
+        if self.bytes.len() == self.window {
+            let oldest = self.bytes.pop_front().expect("window is full");
+            self.hash = self.hash.wrapping_sub(u64::from(oldest).wrapping_mul(self.top_power));
+        }
+        self.hash = self.hash.wrapping_mul(BASE).wrapping_add(u64::from(byte));
+        self.bytes.push_back(byte);
+        self.hash
+    }
+
+    /// Whether `window` bytes have been pushed since the last reset
+    pub fn is_full(&self) -> bool {
+        self.bytes.len() == self.window
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    pub fn reset(&mut self) {
+        self.hash = 0;
+        self.bytes.clear();
+    }
+}
+
+/// Hash of a whole byte string, equal to the rolling hash of a full window
+/// holding the same bytes
+pub fn hash_window(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0u64, |hash, &b| hash.wrapping_mul(BASE).wrapping_add(u64::from(b)))
+}
+
+/// Position of a verified match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct WindowMatch {
+    pub byte_offset: usize,
+    /// Offset in Unicode scalar values, for `[...str]`-style indexing
+    pub char_offset: usize,
+}
+
+/// Every (possibly overlapping) occurrence of `needle` in `haystack`
+///
+/// Windows are filtered on the hash of the first `window` bytes of the
+/// needle and confirmed by exact comparison of the whole needle.
+pub fn find_matches(haystack: &str, needle: &str, window: usize) -> Result<Vec<WindowMatch>, String> {
+    if needle.is_empty() {
+        return Err("Needle must not be empty".to_string());
+    }
+    if needle.len() < window {
+        return Err(format!("Needle is {} bytes, shorter than the {}-byte window", needle.len(), window));
+    }
+
+    let mut rolling = RollingHash::new(window)?;
+    let target = hash_window(&needle.as_bytes()[..window]);
+    let haystack_bytes = haystack.as_bytes();
+
+    let mut matches = Vec::new();
+    let mut chars_before = 0;
+    for (end, &byte) in haystack_bytes.iter().enumerate() {
+        let hash = rolling.push_byte(byte);
+        if !rolling.is_full() {
+            continue;
+        }
+
+        let start = end + 1 - window;
+        if start > 0 && !is_continuation(haystack_bytes[start - 1]) {
+            chars_before += 1;
+        }
+        if hash == target && haystack_bytes[start..].starts_with(needle.as_bytes()) {
+            matches.push(WindowMatch { byte_offset: start, char_offset: chars_before });
+        }
+    }
+
+    Ok(matches)
+}
+
+fn is_continuation(byte: u8) -> bool {
+    byte & 0xc0 == 0x80
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offsets(haystack: &str, needle: &str) -> Vec<usize> {
+        find_matches(haystack, needle, needle.len()).unwrap().iter().map(|m| m.byte_offset).collect()
+    }
+
+    #[test]
+    fn test_rolling_matches_direct_hash() {
+        let data = b"the quick brown fox jumps over the lazy dog";
+        let mut rolling = RollingHash::new(8).unwrap();
+        for (i, &b) in data.iter().enumerate() {
+            let hash = rolling.push_byte(b);
+            if i >= 7 {
+                assert_eq!(hash, hash_window(&data[i - 7..=i]), "window ending at {}", i);
+            }
+        }
+
+        rolling.reset();
+        assert!(!rolling.is_full());
+        assert!(RollingHash::new(0).is_err());
+    }
+
+    #[test]
+    fn test_overlapping_matches() {
+        assert_eq!(offsets("aaaaa", "aa"), vec![0, 1, 2, 3]);
+        assert_eq!(offsets("abababa", "aba"), vec![0, 2, 4]);
+        assert_eq!(offsets("no match here", "xyz"), Vec::<usize>::new());
+        assert_eq!(offsets("short", "longer than haystack"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_multibyte_offsets() {
+        let haystack = "héllo wörld, héllo again";
+        let matches = find_matches(haystack, "héllo", 5).unwrap();
+        assert_eq!(
+            matches,
+            vec![WindowMatch { byte_offset: 0, char_offset: 0 }, WindowMatch { byte_offset: 15, char_offset: 13 }]
+        );
+        assert_eq!(&haystack[15..21], "héllo");
+        assert_eq!(haystack.chars().skip(13).take(5).collect::<String>(), "héllo");
+
+        let emoji = "🦀 and 🦀🦀";
+        let matches = find_matches(emoji, "🦀", 4).unwrap();
+        assert_eq!(matches.iter().map(|m| (m.byte_offset, m.char_offset)).collect::<Vec<_>>(), vec![(0, 0), (9, 6), (13, 7)]);
+    }
+
+    #[test]
+    fn test_window_shorter_than_needle_verifies_whole_needle() {
+        // Only the first 3 bytes are hashed; the rest is checked exactly
+        let matches = find_matches("abcdef abcxyz abcdef", "abcdef", 3).unwrap();
+        assert_eq!(matches.iter().map(|m| m.byte_offset).collect::<Vec<_>>(), vec![0, 14]);
+    }
+
+    #[test]
+    fn test_needle_shorter_than_window_is_an_error() {
+        assert_eq!(
+            find_matches("haystack", "ab", 4).unwrap_err(),
+            "Needle is 2 bytes, shorter than the 4-byte window"
+        );
+        assert!(find_matches("haystack", "", 0).is_err());
+    }
+
+    #[test]
+    fn test_hash_collision_rejected_by_verification() {
+        // A Thue-Morse string and its complement collide under polynomial
+        // hashing mod 2^64; for this base from length 2^10 on
+        let thue_morse: Vec<bool> = (0..1024u32).map(|i| i.count_ones() % 2 == 1).collect();
+        let needle: String = thue_morse.iter().map(|&bit| if bit { 'b' } else { 'a' }).collect();
+        let haystack: String = thue_morse.iter().map(|&bit| if bit { 'a' } else { 'b' }).collect();
+
+        assert_ne!(needle, haystack);
+        assert_eq!(hash_window(needle.as_bytes()), hash_window(haystack.as_bytes()));
+        assert!(find_matches(&haystack, &needle, needle.len()).unwrap().is_empty());
+        assert_eq!(offsets(&format!("{}{}", haystack, needle), &needle), vec![1024]);
+    }
+}
@@ -0,0 +1,201 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Scratch buffers - retained storage for repeated batch calls
+//
+// A batch call builds its results as a fresh `Vec<String>`, one heap block
+// per result, and frees them all when it returns. WASM linear memory never
+// shrinks, so in a long-running worker every batch is a chance for the
+// allocator to fragment and grow the heap a little further.
+//
+// `BatchBuffers` instead writes every result of a batch into one string
+// and records where each ends, and copies byte documents through one input
+// buffer. The next batch clears and refills the same storage, so once it
+// has reached the size of the largest batch seen no call allocates more.
+// `shrink` gives that storage back after an unusually large batch.
+
+use crate::hashing::{DigestEncoding, HashAlgorithm};
+use crate::normalize::{self, NormalizeOptions};
+use serde::Serialize;
+
+/// Results of the last batch, in retained buffers
+#[derive(Debug, Clone, Default)]
+pub struct BatchBuffers {
+    /// Every result of the batch, concatenated
+    text: String,
+    /// End offset in `text` of each result
+    ends: Vec<usize>,
+    /// Copy of the byte document being hashed
+    input: Vec<u8>,
+}
+
+/// Capacities of the retained buffers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BufferStats {
+    /// Bytes reserved for result text
+    pub text_bytes: usize,
+    /// Results that fit before the offset list grows
+    pub results: usize,
+    /// Bytes reserved for copying byte documents
+    pub input_bytes: usize,
+}
+
+impl BatchBuffers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the results with the digests of `documents`
+    pub fn hash_batch<D: AsRef<[u8]>>(
+        &mut self,
+        documents: impl IntoIterator<Item = D>,
+        algorithm: HashAlgorithm,
+        encoding: DigestEncoding,
+    ) {
+        self.clear();
+        for doc in documents {
+            self.push_hash(doc.as_ref(), algorithm, encoding);
+        }
+    }
+
+    /// Replace the results with the normalized `documents`
+    pub fn normalize_batch<'a>(&mut self, documents: impl IntoIterator<Item = &'a str>, options: &NormalizeOptions) {
+        self.clear();
+        for doc in documents {
+            self.push_normalized(doc, options);
+        }
+    }
+
+    /// Drop the results, keeping their storage
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.ends.clear();
+    }
+
+    /// Append the digest of `data` to the results
+    pub fn push_hash(&mut self, data: &[u8], algorithm: HashAlgorithm, encoding: DigestEncoding) {
+        encoding.encode_into(&algorithm.digest(data), &mut self.text);
+        self.ends.push(self.text.len());
+    }
+
+    /// Append the digest of `len` bytes that `fill` writes into the input
+    /// buffer, for documents that must be copied before they can be read
+    pub fn push_hash_with(
+        &mut self,
+        len: usize,
+        fill: impl FnOnce(&mut [u8]),
+        algorithm: HashAlgorithm,
+        encoding: DigestEncoding,
+    ) {
+        self.input.clear();
+        self.input.resize(len, 0);
+        fill(&mut self.input);
+        encoding.encode_into(&algorithm.digest(&self.input), &mut self.text);
+        self.ends.push(self.text.len());
+    }
+
+    /// Append `content` normalized with `options` to the results
+    pub fn push_normalized(&mut self, content: &str, options: &NormalizeOptions) {
+        self.text.push_str(&normalize::normalize(content, options));
+        self.ends.push(self.text.len());
+    }
+
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// The result at `index`
+    pub fn get(&self, index: usize) -> Option<&str> {
+        let end = *self.ends.get(index)?;
+        let start = index.checked_sub(1).map_or(0, |previous| self.ends[previous]);
+        Some(&self.text[start..end])
+    }
+
+    /// The results in order
+    pub fn iter(&self) -> impl Iterator<Item = &str> + '_ {
+        let starts = std::iter::once(0).chain(self.ends.iter().copied());
+        starts.zip(&self.ends).map(|(start, &end)| &self.text[start..end])
+    }
+
+    pub fn stats(&self) -> BufferStats {
+        BufferStats {
+            text_bytes: self.text.capacity(),
+            results: self.ends.capacity(),
+            input_bytes: self.input.capacity(),
+        }
+    }
+
+    /// Drop the results and release all buffer storage
+    ///
+    /// The allocator can reuse the space for anything else, but linear
+    /// memory stays at its size; the next batch grows the buffers again.
+    pub fn shrink(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core;
+    use crate::hashing::to_hex;
+
+    fn docs(count: usize, batch: usize) -> Vec<String> {
+        (0..count).map(|i| format!("  batch {} document {}\r\n", batch, i)).collect()
+    }
+
+    #[test]
+    fn test_results_match_batch_functions() {
+        let docs = docs(50, 0);
+        let mut buffers = BatchBuffers::new();
+        for encoding in [DigestEncoding::Hex, DigestEncoding::Base64, DigestEncoding::Base64Url] {
+            for algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Blake3] {
+                buffers.hash_batch(&docs, algorithm, encoding);
+                assert_eq!(buffers.iter().collect::<Vec<_>>(), core::batch_hash(&docs, algorithm, encoding));
+            }
+        }
+
+        let options = NormalizeOptions::default();
+        buffers.normalize_batch(docs.iter().map(String::as_str), &options);
+        assert_eq!(buffers.len(), 50);
+        assert_eq!(buffers.iter().collect::<Vec<_>>(), core::batch_normalize(&docs, &options));
+
+        buffers.clear();
+        buffers.push_hash_with(3, |input| input.copy_from_slice(b"abc"), HashAlgorithm::Sha256, DigestEncoding::Hex);
+        buffers.push_normalized("", &options);
+        let abc = to_hex(&core::hash_content("abc"));
+        assert_eq!(buffers.iter().collect::<Vec<_>>(), [abc.as_str(), ""]);
+    }
+
+    #[test]
+    fn test_capacity_settles_across_batches() {
+        let mut buffers = BatchBuffers::new();
+        let mut settled = None;
+        for batch in 0..200 {
+            buffers.hash_batch(docs(1000, batch), HashAlgorithm::Sha256, DigestEncoding::Hex);
+            assert_eq!(buffers.len(), 1000);
+            match settled {
+                None if batch == 2 => settled = Some(buffers.stats()),
+                Some(stats) => assert_eq!(buffers.stats(), stats, "buffers grew in batch {}", batch),
+                None => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_shrink_releases_storage() {
+        let mut buffers = BatchBuffers::new();
+        let big = "x".repeat(1 << 20);
+        buffers.normalize_batch([big.as_str()], &NormalizeOptions::default());
+        buffers.push_hash_with(1 << 16, |input| input.fill(7), HashAlgorithm::Sha256, DigestEncoding::Hex);
+        assert!(buffers.stats().text_bytes >= 1 << 20);
+        assert!(buffers.stats().input_bytes >= 1 << 16);
+
+        buffers.shrink();
+        assert!(buffers.is_empty());
+        assert_eq!(buffers.stats(), BufferStats { text_bytes: 0, results: 0, input_bytes: 0 });
+    }
+}
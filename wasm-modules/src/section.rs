@@ -0,0 +1,232 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Sections - split Markdown-style documents at their ATX headings
+//
+// A heading is a line of 1 to 6 `#` characters, indented by at most three
+// spaces and followed by a space, a tab or the end of the line, as in
+// CommonMark. An optional closing run of `#` is dropped from the title.
+// Setext headings (text underlined with `===` or `---`) are not recognised,
+// and nothing inside a fenced code block is a heading: a fence opens with
+// three or more backticks or tildes and closes with a run of the same
+// character at least as long, or at the end of the document.
+//
+// Each section runs from its heading to the next heading of any level, so
+// nesting does not matter to the split. Text before the first heading is a
+// preamble section with no heading and level 0, omitted when blank. A
+// section's content leaves out the heading line and the blank lines around
+// it, and its hash is the SHA-256 of that content alone: an edit in one
+// section leaves every other hash unchanged, and so does renaming the
+// heading.
+
+use crate::hashing::HashAlgorithm;
+use serde::Serialize;
+
+/// One section of a document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Section<'a> {
+    /// Heading text, `None` for the preamble
+    pub heading: Option<&'a str>,
+    /// Number of `#` characters, 0 for the preamble
+    pub level: u8,
+    pub content: &'a str,
+    /// SHA-256 hex digest of `content`
+    pub hash: String,
+}
+
+/// Sections of `content`, in document order
+pub fn sections(content: &str) -> Vec<Section<'_>> {
+    let mut sections = Vec::new();
+    let mut heading: Option<(&str, u8)> = None;
+    let mut body: Option<(usize, usize)> = None;
+    let mut fence: Option<Fence> = None;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let text = line.strip_suffix('\n').unwrap_or(line);
+        let text = text.strip_suffix('\r').unwrap_or(text);
+
+        let mut found = None;
+        match fence {
+            Some(open) => {
+                if open.closed_by(text) {
+                    fence = None;
+                }
+            }
+            None => {
+                fence = Fence::opened_by(text);
+                if fence.is_none() {
+                    found = atx_heading(text);
+                }
+            }
+        }
+
+        if found.is_some() {
+            push_section(&mut sections, content, heading, body.take());
+            heading = found;
+        } else if !text.trim().is_empty() {
+            let end = offset + text.len();
+            body = Some(body.map_or((offset, end), |(start, _)| (start, end)));
+        }
+        offset += line.len();
+    }
+
+    push_section(&mut sections, content, heading, body);
+    sections
+}
+
+/// Add the section with `heading` whose non-blank lines span `body`, unless
+/// it is a blank preamble
+fn push_section<'a>(
+    sections: &mut Vec<Section<'a>>,
+    content: &'a str,
+    heading: Option<(&'a str, u8)>,
+    body: Option<(usize, usize)>,
+) {
+    if heading.is_none() && body.is_none() {
+        return;
+    }
+    let text = body.map_or("", |(start, end)| &content[start..end]);
+    sections.push(Section {
+        heading: heading.map(|(title, _)| title),
+        level: heading.map_or(0, |(_, level)| level),
+        content: text,
+        hash: HashAlgorithm::Sha256.hash(text.as_bytes()),
+    });
+}
+
+/// An open code fence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fence {
+    marker: u8,
+    len: usize,
+}
+
+impl Fence {
+    /// The fence `line` opens, if any
+    fn opened_by(line: &str) -> Option<Fence> {
+        let rest = unindent(line)?;
+        let marker = *rest.as_bytes().first()?;
+        if marker != b'`' && marker != b'~' {
+            return None;
+        }
+        let len = rest.bytes().take_while(|&b| b == marker).count();
+        // A backtick fence's info string cannot contain backticks
+        if len < 3 || (marker == b'`' && rest[len..].contains('`')) {
+            return None;
+        }
+        Some(Fence { marker, len })
+    }
+
+    /// Whether `line` closes this fence
+    fn closed_by(&self, line: &str) -> bool {
+        let Some(rest) = unindent(line) else {
+            return false;
+        };
+        let len = rest.bytes().take_while(|&b| b == self.marker).count();
+        len >= self.len && rest[len..].trim().is_empty()
+    }
+}
+
+/// `line` without up to three spaces of indentation, or `None` if it is
+/// indented further (an indented code block)
+fn unindent(line: &str) -> Option<&str> {
+    let rest = line.trim_start_matches(' ');
+    if line.len() - rest.len() > 3 || rest.starts_with('\t') {
+        return None;
+    }
+    Some(rest)
+}
+
+/// The title and level of an ATX heading line
+fn atx_heading(line: &str) -> Option<(&str, u8)> {
+    let rest = unindent(line)?;
+    let level = rest.bytes().take_while(|&b| b == b'#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let title = &rest[level..];
+    if !(title.is_empty() || title.starts_with([' ', '\t'])) {
+        return None;
+    }
+
+    let title = title.trim();
+    let unclosed = title.trim_end_matches('#');
+    let title = if unclosed.is_empty() {
+        unclosed
+    } else if unclosed.ends_with([' ', '\t']) {
+        unclosed.trim_end()
+    } else {
+        title
+    };
+    Some((title, level as u8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline(content: &str) -> Vec<(Option<&str>, u8, &str)> {
+        sections(content).into_iter().map(|section| (section.heading, section.level, section.content)).collect()
+    }
+
+    #[test]
+    fn test_nested_heading_levels() {
+        let doc = "Intro text.\n\n# Guide\n\nOverview.\n\n## Install\n\nRun it.\n\n### Linux\n\nUse apt.\n\n## Usage ##\n\nCall it.\n";
+        assert_eq!(
+            outline(doc),
+            vec![
+                (None, 0, "Intro text."),
+                (Some("Guide"), 1, "Overview."),
+                (Some("Install"), 2, "Run it."),
+                (Some("Linux"), 3, "Use apt."),
+                (Some("Usage"), 2, "Call it."),
+            ]
+        );
+
+        let all = sections(doc);
+        assert_eq!(all[1].hash, HashAlgorithm::Sha256.hash(b"Overview."));
+    }
+
+    #[test]
+    fn test_hash_in_fenced_code_is_not_a_heading() {
+        let doc =
+            "# Build\n\n```sh\n# install deps\nmake\n```\n\n~~~~\n## not a heading\n~~~\n```\n~~~~\n\n# Next\nDone.";
+        let found = outline(doc);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].0, Some("Build"));
+        assert_eq!(found[0].2, "```sh\n# install deps\nmake\n```\n\n~~~~\n## not a heading\n~~~\n```\n~~~~");
+        assert_eq!(found[1], (Some("Next"), 1, "Done."));
+
+        // An unclosed fence runs to the end of the document
+        assert_eq!(outline("# A\n```\n# B\n").len(), 1);
+    }
+
+    #[test]
+    fn test_no_headings() {
+        assert_eq!(
+            outline("Just a paragraph.\n\nAnd another.\n"),
+            vec![(None, 0, "Just a paragraph.\n\nAnd another.")]
+        );
+        assert!(sections("").is_empty());
+        assert!(sections("\n\n").is_empty());
+    }
+
+    #[test]
+    fn test_lines_that_are_not_headings() {
+        let doc = "Title\n=====\n\nSub\n---\n\n#hashtag\n    # indented code\n####### seven\n\\# escaped";
+        assert_eq!(outline(doc).len(), 1);
+        assert_eq!(
+            outline("#\n# #\n#\tTabbed #   \n# C# #"),
+            vec![(Some(""), 1, ""), (Some(""), 1, ""), (Some("Tabbed"), 1, ""), (Some("C#"), 1, "")]
+        );
+    }
+
+    #[test]
+    fn test_edit_only_changes_its_section() {
+        let before = sections("# A\none\n# B\ntwo\n# C\nthree\n");
+        let after = sections("# A\none\n# B\ntwo, edited\n# C\n\nthree\n\n");
+        assert_eq!(before[0].hash, after[0].hash);
+        assert_ne!(before[1].hash, after[1].hash);
+        assert_eq!(before[2].hash, after[2].hash);
+    }
+}
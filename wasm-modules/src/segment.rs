@@ -0,0 +1,155 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Segmentation - paragraph and sentence spans of normalized content
+//
+// Spans are byte ranges into the string they were taken from, so a caller
+// holding the output of `normalize_content` can slice it directly.
+//
+// A paragraph is a maximal run of non-blank lines, where a line is blank if
+// it is empty or whitespace-only - the same rule `collapse_blank_lines`
+// uses, so one blank line separates paragraphs just as ten do. A span ends
+// at the last line's text; the terminator (and any `\r`) is not included.
+//
+// Sentences follow UAX #29 sentence boundaries. The rules keep "e.g. this",
+// "3.14" and "U.S.A." together but have no abbreviation dictionary, so
+// "Dr. Smith" is two sentences, and a line break always ends a sentence.
+// Surrounding whitespace is left out of each span and whitespace-only
+// segments are dropped.
+
+use serde::Serialize;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A slice of the segmented string and its byte range
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Span<'a> {
+    pub text: &'a str,
+    pub start_byte: usize,
+    pub end_byte: usize,
+}
+
+impl<'a> Span<'a> {
+    fn new(content: &'a str, start_byte: usize, end_byte: usize) -> Self {
+        Span { text: &content[start_byte..end_byte], start_byte, end_byte }
+    }
+}
+
+/// Paragraphs of `content`
+pub fn paragraphs(content: &str) -> Vec<Span<'_>> {
+    let mut spans = Vec::new();
+    let mut current: Option<(usize, usize)> = None;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let text = line.strip_suffix('\n').unwrap_or(line);
+        let text = text.strip_suffix('\r').unwrap_or(text);
+        if text.trim().is_empty() {
+            if let Some((start, end)) = current.take() {
+                spans.push(Span::new(content, start, end));
+            }
+        } else {
+            let end = offset + text.len();
+            current = Some(current.map_or((offset, end), |(start, _)| (start, end)));
+        }
+        offset += line.len();
+    }
+
+    if let Some((start, end)) = current {
+        spans.push(Span::new(content, start, end));
+    }
+    spans
+}
+
+/// Sentences of `content`
+pub fn sentences(content: &str) -> Vec<Span<'_>> {
+    content
+        .split_sentence_bound_indices()
+        .filter_map(|(offset, segment)| {
+            let text = segment.trim();
+            let start = offset + (segment.len() - segment.trim_start().len());
+            (!text.is_empty()).then(|| Span::new(content, start, start + text.len()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalize::{normalize, NormalizeOptions};
+
+    fn texts<'a>(spans: &[Span<'a>]) -> Vec<&'a str> {
+        spans.iter().map(|s| s.text).collect()
+    }
+
+    fn assert_slices(content: &str, spans: &[Span<'_>]) {
+        for span in spans {
+            assert_eq!(&content[span.start_byte..span.end_byte], span.text);
+        }
+    }
+
+    #[test]
+    fn test_paragraphs_split_on_any_blank_run() {
+        let content = "first line\nstill first\n\nsecond\n\n\n\nthird\n   \t\nfourth";
+        let spans = paragraphs(content);
+        assert_eq!(texts(&spans), ["first line\nstill first", "second", "third", "fourth"]);
+        assert_slices(content, &spans);
+        assert_eq!((spans[1].start_byte, spans[1].end_byte), (24, 30));
+    }
+
+    #[test]
+    fn test_paragraph_offsets_match_normalized_content() {
+        let raw = "  \r\nIntro text.  \r\n\r\n\r\n\r\nBody one\r\nbody two \r\n\r\n";
+        let normalized = normalize(raw, &NormalizeOptions::default());
+        assert_eq!(normalized, "Intro text.\n\nBody one\nbody two");
+
+        let spans = paragraphs(&normalized);
+        assert_eq!(texts(&spans), ["Intro text.", "Body one\nbody two"]);
+        assert_eq!((spans[1].start_byte, spans[1].end_byte), (13, 30));
+        assert_slices(&normalized, &spans);
+    }
+
+    #[test]
+    fn test_paragraph_edges() {
+        assert!(paragraphs("").is_empty());
+        assert!(paragraphs("\n\n  \n").is_empty());
+        let spans = paragraphs("\r\n\r\nonly one\r\n");
+        assert_eq!(spans, [Span { text: "only one", start_byte: 4, end_byte: 12 }]);
+    }
+
+    #[test]
+    fn test_sentences_keep_abbreviations_and_decimals() {
+        let content = "Prices rose 3.14 percent, e.g. in Q.4 of the U.S.A. market. Then they fell! Why? ";
+        let spans = sentences(content);
+        assert_eq!(
+            texts(&spans),
+            [
+                "Prices rose 3.14 percent, e.g. in Q.4 of the U.S.A. market.",
+                "Then they fell!",
+                "Why?",
+            ]
+        );
+        assert_slices(content, &spans);
+    }
+
+    #[test]
+    fn test_sentences_follow_uax29_without_a_dictionary() {
+        // A capitalised word after an abbreviation starts a new sentence
+        assert_eq!(texts(&sentences("Ask Dr. Smith first.")), ["Ask Dr.", "Smith first."]);
+        // Line breaks are paragraph separators
+        assert_eq!(texts(&sentences("no stop\nhere")), ["no stop", "here"]);
+    }
+
+    #[test]
+    fn test_sentences_drop_surrounding_whitespace() {
+        let content = "One.   Two.\n\n  \n";
+        let spans = sentences(content);
+        assert_eq!(
+            spans,
+            [
+                Span { text: "One.", start_byte: 0, end_byte: 4 },
+                Span { text: "Two.", start_byte: 7, end_byte: 11 },
+            ]
+        );
+        assert!(sentences("   \n").is_empty());
+        assert_eq!(sentences("  Indented.")[0], Span { text: "Indented.", start_byte: 2, end_byte: 11 });
+    }
+}
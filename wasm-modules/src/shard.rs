@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Sharding - split a batch into contiguous ranges for parallel Workers
+//
+// Each Worker loads its own module instance and hashes one shard; the
+// results are concatenated in shard order (or placed at each shard's
+// `start`). Shards are contiguous so that reassembly is a concatenation,
+// and their lengths differ by at most one. Every element is hashed exactly
+// as the serial path hashes it, so the reassembled output is identical.
+
+use std::ops::Range;
+
+/// The element range of `shard` when `len` elements are split into
+/// `total_shards` parts
+pub fn shard_range(len: usize, shard: usize, total_shards: usize) -> Result<Range<usize>, String> {
+    if total_shards == 0 {
+        return Err("total_shards must be at least 1".to_string());
+    }
+    if shard >= total_shards {
+        return Err(format!("shard must be below total_shards ({}), got {}", total_shards, shard));
+    }
+
+    // The first `len % total_shards` shards take one extra element
+    let base = len / total_shards;
+    let extra = len % total_shards;
+    let start = shard * base + shard.min(extra);
+    let end = start + base + usize::from(shard < extra);
+    Ok(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shards_partition_the_batch() {
+        for len in [0, 1, 3, 4, 10, 1001] {
+            for total in 1..=8 {
+                let ranges: Vec<_> = (0..total).map(|shard| shard_range(len, shard, total).unwrap()).collect();
+                assert_eq!(ranges[0].start, 0);
+                assert_eq!(ranges[total - 1].end, len);
+                for pair in ranges.windows(2) {
+                    assert_eq!(pair[0].end, pair[1].start);
+                }
+                let sizes: Vec<usize> = ranges.iter().map(|r| r.len()).collect();
+                assert!(sizes.iter().max().unwrap() - sizes.iter().min().unwrap() <= 1, "{} / {}", len, total);
+            }
+        }
+        assert_eq!(shard_range(10, 3, 4).unwrap(), 8..10);
+        assert_eq!(shard_range(10, 0, 4).unwrap(), 0..3);
+    }
+
+    #[test]
+    fn test_invalid_shards() {
+        assert!(shard_range(10, 0, 0).is_err());
+        assert!(shard_range(10, 4, 4).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Document similarity - Jaccard index and containment over word shingles
+//
+// Both documents are normalized with the default policy, split into
+// whitespace-separated words, and turned into sets of k-word shingles
+// (contiguous word windows). Similarity is |A ∩ B| / |A ∪ B|; containment
+// of A in B is |A ∩ B| / |A|, which stays high when A is an excerpt of a
+// much longer B. Shingles are hashed to u64 with FNV-1a so the cost is
+// linear in document size and the hashes are stable across builds.
+
+use crate::normalize::{self, NormalizeOptions};
+use std::collections::HashSet;
+
+/// Words per shingle
+pub const DEFAULT_SHINGLE_SIZE: usize = 3;
+
+/// Words per shingle for containment; longer, so that stock phrases shared
+/// by unrelated documents count for little
+pub const DEFAULT_CONTAINMENT_SHINGLE_SIZE: usize = 5;
+
+/// Similarity score in [0, 1] between two documents
+///
+/// Symmetric; 1.0 when the normalized documents have the same shingle set
+/// (including both being empty), 0.0 when they share no shingles.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let options = NormalizeOptions::default();
+    let a = normalize::normalize(a, &options);
+    let b = normalize::normalize(b, &options);
+
+    word_similarity(&words(&a), &words(&b))
+}
+
+/// `similarity` of two already-normalized documents given as words
+pub fn word_similarity(a_words: &[&str], b_words: &[&str]) -> f64 {
+    if a_words.is_empty() && b_words.is_empty() {
+        return 1.0;
+    }
+    if a_words.is_empty() || b_words.is_empty() {
+        return 0.0;
+    }
+
+    // Shrink the window for very short documents so they still compare;
+    // both sides use the same size to stay symmetric
+    let k = DEFAULT_SHINGLE_SIZE.min(a_words.len()).min(b_words.len());
+
+    jaccard(&shingles(a_words, k), &shingles(b_words, k))
+}
+
+/// Fraction of `needle`'s `k`-word shingles that also occur in `haystack`
+///
+/// 1.0 when the needle's words appear verbatim in the haystack (and for an
+/// empty needle), 0.0 when none of its shingles do. A needle of fewer than
+/// `k` words is compared with shingles as long as the needle itself.
+pub fn containment(needle: &str, haystack: &str, k: usize) -> f64 {
+    containment_many(needle, &[haystack], k)[0]
+}
+
+/// `containment` of one needle in each haystack, in order
+pub fn containment_many(needle: &str, haystacks: &[impl AsRef<str>], k: usize) -> Vec<f64> {
+    let options = NormalizeOptions::default();
+    let needle = normalize::normalize(needle, &options);
+    let needle_words = words(&needle);
+    if needle_words.is_empty() {
+        return vec![1.0; haystacks.len()];
+    }
+
+    let k = k.max(1).min(needle_words.len());
+    let needle_shingles = shingles(&needle_words, k);
+
+    haystacks
+        .iter()
+        .map(|haystack| {
+            let haystack = normalize::normalize(haystack.as_ref(), &options);
+            let haystack_shingles = shingles(&words(&haystack), k);
+            let shared = needle_shingles.iter().filter(|h| haystack_shingles.contains(h)).count();
+            shared as f64 / needle_shingles.len() as f64
+        })
+        .collect()
+}
+
+/// Split normalized content into words
+pub fn words(content: &str) -> Vec<&str> {
+    content.split_whitespace().collect()
+}
+
+/// Hashes of every `k`-word window
+pub fn shingles(words: &[&str], k: usize) -> HashSet<u64> {
+    words.windows(k.max(1)).map(shingle_hash).collect()
+}
+
+/// Jaccard index of two shingle sets; 1.0 for two empty sets
+pub fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    let intersection = small.iter().filter(|h| large.contains(h)).count();
+    let union = a.len() + b.len() - intersection;
+    intersection as f64 / union as f64
+}
+
+/// FNV-1a over the words of a shingle, with a separator between words
+pub fn shingle_hash(window: &[&str]) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut hash = OFFSET;
+    for (i, word) in window.iter().enumerate() {
+        if i > 0 {
+            hash ^= 0x1f;
+            hash = hash.wrapping_mul(PRIME);
+        }
+        for byte in word.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FOX: &str = "the quick brown fox jumps over the lazy dog";
+
+    #[test]
+    fn test_identical_after_normalization() {
+        assert_eq!(similarity(FOX, FOX), 1.0);
+        assert_eq!(similarity("  a b\r\nc d  ", "a b\nc d"), 1.0);
+    }
+
+    #[test]
+    fn test_disjoint() {
+        assert_eq!(similarity("alpha beta gamma delta", "one two three four"), 0.0);
+    }
+
+    #[test]
+    fn test_empty_inputs() {
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("  \n ", ""), 1.0);
+        assert_eq!(similarity("", FOX), 0.0);
+        assert_eq!(similarity(FOX, ""), 0.0);
+    }
+
+    #[test]
+    fn test_symmetric() {
+        let pairs = [
+            (FOX, "the quick brown fox"),
+            ("a b c d e", "c d e f g"),
+            ("short", FOX),
+        ];
+        for (a, b) in pairs {
+            assert_eq!(similarity(a, b), similarity(b, a));
+        }
+    }
+
+    #[test]
+    fn test_single_word_change() {
+        // 7 shingles each, 3 touch the changed word: 4 shared of 10
+        let edited = "the quick brown fox leaps over the lazy dog";
+        assert!((similarity(FOX, edited) - 0.4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_known_ranges() {
+        let paragraph = "reconciliation compares documents from several repositories and decides \
+                         which version is canonical based on confidence scores";
+        let appended = format!("{} and a short trailing remark", paragraph);
+        let score = similarity(paragraph, &appended);
+        assert!(score > 0.6 && score < 0.9, "score {}", score);
+
+        let unrelated = "completely different text about gardening tomatoes in spring";
+        assert!(similarity(paragraph, unrelated) < 0.05);
+    }
+
+    #[test]
+    fn test_short_documents_use_smaller_shingles() {
+        assert_eq!(similarity("hello", "hello"), 1.0);
+        assert_eq!(similarity("hello world", "hello world again"), 0.5);
+    }
+
+    #[test]
+    fn test_wildly_different_sizes() {
+        let large: String = (0..1_000_000).map(|i| format!("w{} ", i % 5000)).collect();
+        let score = similarity("tiny doc", &large);
+        assert!((0.0..=1.0).contains(&score));
+        assert_eq!(score, 0.0);
+    }
+
+    const REPORT: &str = "quarterly reconciliation report\n\n\
+        the audit found that three repositories carried divergent copies of the licence file \
+        and that the canonical source had not been updated since the spring release \
+        maintainers agreed to adopt the upstream text and to record the decision in the changelog \
+        a follow up review is scheduled for the end of the next quarter";
+
+    const SUMMARY: &str = "three repositories carried divergent copies of the licence file \
+        and that the canonical source had not been updated since the spring release";
+
+    #[test]
+    fn test_excerpt_is_fully_contained() {
+        assert_eq!(containment(SUMMARY, REPORT, DEFAULT_CONTAINMENT_SHINGLE_SIZE), 1.0);
+        // Whitespace and line breaks differ but the words are verbatim
+        let reflowed = SUMMARY.replace(' ', "\n  ");
+        assert_eq!(containment(&reflowed, REPORT, DEFAULT_CONTAINMENT_SHINGLE_SIZE), 1.0);
+    }
+
+    #[test]
+    fn test_superset_is_partially_contained() {
+        // The report has far more shingles than the summary can supply
+        let score = containment(REPORT, SUMMARY, DEFAULT_CONTAINMENT_SHINGLE_SIZE);
+        assert!(score > 0.2 && score < 0.5, "score {}", score);
+        assert!(similarity(SUMMARY, REPORT) < 0.5);
+    }
+
+    #[test]
+    fn test_small_reordering_degrades_gracefully() {
+        let reordered = "and that the canonical source had not been updated since the spring release \
+            three repositories carried divergent copies of the licence file";
+        let score = containment(reordered, REPORT, DEFAULT_CONTAINMENT_SHINGLE_SIZE);
+        assert!(score > 0.7 && score < 1.0, "score {}", score);
+        assert_eq!(containment("spring release the canonical", REPORT, 5), 0.0);
+    }
+
+    #[test]
+    fn test_short_needle_uses_smaller_shingles() {
+        assert_eq!(containment("licence file", REPORT, 5), 1.0);
+        assert_eq!(containment("audit", REPORT, 5), 1.0);
+        assert_eq!(containment("licence banana", REPORT, 5), 0.0);
+        assert_eq!(containment("divergent copies of the banana", REPORT, 5), 0.0);
+        assert_eq!(containment("", REPORT, 5), 1.0);
+        assert_eq!(containment("audit", "", 5), 0.0);
+        assert_eq!(containment("audit found", "audit", 5), 0.0);
+    }
+
+    #[test]
+    fn test_containment_many_preserves_order() {
+        let scores = containment_many(SUMMARY, &[REPORT, "", SUMMARY, "unrelated words entirely"], 5);
+        assert_eq!(scores, vec![1.0, 0.0, 1.0, 0.0]);
+    }
+}
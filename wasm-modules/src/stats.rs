@@ -0,0 +1,155 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Content statistics - cheap per-document features for reconciliation
+//
+// Definitions shared with the rest of the crate: a line is a run of text
+// ended by `\n` or by the end of input (a trailing `\n` does not start a new
+// line, and a `\r` before it belongs to the terminator), a word is a Unicode
+// word as defined by UAX #29 that contains at least one letter or digit,
+// and lengths are counted in extended grapheme clusters.
+
+use serde::Serialize;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Share of suspicious bytes above which content is reported as binary
+pub const BINARY_RATIO: f64 = 0.1;
+
+/// Features of one document
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ContentStats {
+    pub lines: usize,
+    pub words: usize,
+    /// Grapheme clusters, line terminators included (`\r\n` counts once)
+    pub graphemes: usize,
+    /// UTF-8 byte length
+    pub bytes: usize,
+    /// Lines that are empty or contain only whitespace
+    pub blank_lines: usize,
+    /// Longest line in grapheme clusters, terminator excluded
+    pub max_line_length: usize,
+    pub likely_binary: bool,
+}
+
+/// Statistics of `text`, with the binary check run on `raw`
+///
+/// `raw` is the content as received, before any decoding or normalization,
+/// so that normalization stripping control characters does not hide that
+/// the input was binary. Pass `text.as_bytes()` when there is no difference.
+pub fn content_stats(text: &str, raw: &[u8]) -> ContentStats {
+    let mut stats = ContentStats {
+        words: text.unicode_words().count(),
+        graphemes: text.graphemes(true).count(),
+        bytes: text.len(),
+        likely_binary: is_likely_binary(raw),
+        ..ContentStats::default()
+    };
+
+    for line in text.split_inclusive('\n') {
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        stats.lines += 1;
+        if line.trim().is_empty() {
+            stats.blank_lines += 1;
+        }
+        stats.max_line_length = stats.max_line_length.max(line.graphemes(true).count());
+    }
+
+    stats
+}
+
+/// Whether `bytes` look like binary data rather than text
+///
+/// Any NUL byte makes content binary. Otherwise it is binary when more than
+/// 10% of its bytes are control characters other than tab, line feed, form
+/// feed, carriage return and escape, or are not part of valid UTF-8.
+pub fn is_likely_binary(bytes: &[u8]) -> bool {
+    if bytes.contains(&0) {
+        return true;
+    }
+    if bytes.is_empty() {
+        return false;
+    }
+
+    let mut suspicious = 0;
+    for chunk in bytes.utf8_chunks() {
+        suspicious += chunk.invalid().len();
+        suspicious += chunk.valid().bytes().filter(|&b| is_binary_control(b)).count();
+    }
+    suspicious as f64 > bytes.len() as f64 * BINARY_RATIO
+}
+
+/// Control characters that do not occur in ordinary text: C0 controls other
+/// than tab, line feed, form feed, carriage return and escape, and DEL
+pub fn is_binary_control(byte: u8) -> bool {
+    (byte < 0x20 && !matches!(byte, b'\t' | b'\n' | 0x0c | b'\r' | 0x1b)) || byte == 0x7f
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(text: &str) -> ContentStats {
+        content_stats(text, text.as_bytes())
+    }
+
+    #[test]
+    fn test_crlf_input() {
+        let text = "one two\r\n\r\nthree  \r\n";
+        assert_eq!(
+            stats(text),
+            ContentStats {
+                lines: 3,
+                words: 3,
+                graphemes: 17,
+                bytes: 20,
+                blank_lines: 1,
+                max_line_length: 7,
+                likely_binary: false,
+            }
+        );
+
+        // Same text with LF endings differs only in bytes
+        let lf = stats(&text.replace("\r\n", "\n"));
+        assert_eq!(lf.lines, 3);
+        assert_eq!(lf.graphemes, 17);
+        assert_eq!(lf.bytes, 17);
+    }
+
+    #[test]
+    fn test_emoji_heavy_input() {
+        // Family ZWJ sequence and flag are one grapheme each; emoji are not words
+        let text = "👨\u{200d}👩\u{200d}👧 🎉🎉\n🇫🇷 ok";
+        let stats = stats(text);
+        assert_eq!(stats.lines, 2);
+        assert_eq!(stats.words, 1);
+        assert_eq!(stats.graphemes, 9);
+        assert_eq!(stats.bytes, 39);
+        assert_eq!(stats.max_line_length, 4);
+        assert_eq!(stats.blank_lines, 0);
+        assert!(!stats.likely_binary);
+    }
+
+    #[test]
+    fn test_empty_string() {
+        assert_eq!(stats(""), ContentStats::default());
+    }
+
+    #[test]
+    fn test_unicode_word_boundaries() {
+        // Contractions and decimals stay whole; punctuation is not a word
+        assert_eq!(stats("can't stop — 3.14, ok?").words, 4);
+        assert_eq!(stats("日本語").words, 3);
+    }
+
+    #[test]
+    fn test_binary_detection() {
+        assert!(is_likely_binary(b"text\0with a nul"));
+        assert!(is_likely_binary(&[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0xff, 0xfe]));
+        assert!(!is_likely_binary("plain\ttext\r\n\x1b[1mbold\x1b[0m\n".as_bytes()));
+        assert!(!is_likely_binary("ünïcödé".as_bytes()));
+        assert!(!is_likely_binary(b""));
+
+        // The check uses the raw bytes, not the counted text
+        assert!(content_stats("", b"\0").likely_binary);
+    }
+}
@@ -0,0 +1,404 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Tabular content - per-row hashes and keyed row diffs for CSV and TSV
+//
+// Parsing follows RFC 4180 with a configurable delimiter and quote
+// character. A quoted field may hold delimiters, doubled quotes and line
+// breaks; records end at LF, CRLF or CR outside quotes. Leniencies for
+// hand-edited exports:
+//
+// - a leading BOM is dropped, and blank lines between records are skipped
+// - every cell is trimmed, and line breaks inside quoted fields become LF,
+//   so re-saving a file on another platform keeps its row hashes
+// - blanks around a quoted field are ignored, and a quote inside an
+//   unquoted field is an ordinary character
+//
+// Anything else after a closing quote, or a quote left open at the end of
+// the input, is `invalid_input`.
+//
+// A row hash is the SHA-256 of the row's cells as a JSON array, so
+// `["a","b,c"]` and `["a,b","c"]` hash differently.
+//
+// `diff_rows` pairs rows by the values of the key columns, named in the
+// header. Duplicate keys are an error rather than a group: with two rows
+// under one key there is no telling which old row became which new one.
+// Columns compare by name, so reordering columns changes nothing, and a
+// cell missing from a short row, or from a column one side lacks, is empty.
+
+use crate::error::ReconError;
+use crate::hashing::{sha256, to_hex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Options for `hash_rows` and `diff_rows`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct TableOptions {
+    /// Field separator, "\t" for TSV
+    pub delimiter: char,
+    /// Quote character
+    pub quote: char,
+    /// Whether the first record names the columns
+    pub has_header: bool,
+}
+
+impl Default for TableOptions {
+    fn default() -> Self {
+        TableOptions { delimiter: ',', quote: '"', has_header: true }
+    }
+}
+
+/// One parsed record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// Line on which the record starts, from 1
+    pub line: usize,
+    pub cells: Vec<String>,
+}
+
+/// Hash of one data row
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RowHash {
+    pub line: usize,
+    pub hash: String,
+}
+
+/// Result of `hash_rows`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RowHashes {
+    /// Hash of the header row; `None` without a header or for empty input
+    pub header_hash: Option<String>,
+    /// Data rows in order
+    pub rows: Vec<RowHash>,
+}
+
+/// A row present on both sides whose cells differ
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChangedRow {
+    pub key: Vec<String>,
+    /// Names of the differing columns, old header order first
+    pub columns: Vec<String>,
+}
+
+/// Result of `diff_rows`, keys given as the key column values
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RowDiff {
+    /// Keys only in the new table, in its order
+    pub added: Vec<Vec<String>>,
+    /// Keys only in the old table, in its order
+    pub removed: Vec<Vec<String>>,
+    /// Keys in both with different cells, in the new table's order
+    pub changed: Vec<ChangedRow>,
+}
+
+/// Parse `content` into records
+pub fn parse(content: &str, options: &TableOptions) -> Result<Vec<Record>, ReconError> {
+    let TableOptions { delimiter, quote, .. } = *options;
+    if delimiter == quote || [delimiter, quote].iter().any(|&c| c == '\n' || c == '\r') {
+        return Err(ReconError::invalid_options(format!(
+            "Delimiter {:?} and quote {:?} must differ and not be line breaks",
+            delimiter, quote
+        )));
+    }
+
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut chars = content.chars().peekable();
+    let mut line = 1;
+    let mut records = Vec::new();
+    while chars.peek().is_some() {
+        let start = line;
+        let mut cells = Vec::new();
+        let mut quoted_any = false;
+        loop {
+            let (cell, quoted) = field(&mut chars, options, &mut line, start)?;
+            cells.push(cell.trim().to_string());
+            quoted_any |= quoted;
+            match chars.next() {
+                Some(c) if c == delimiter => continue,
+                Some('\r') => {
+                    chars.next_if_eq(&'\n');
+                    line += 1;
+                }
+                Some('\n') => line += 1,
+                _ => {}
+            }
+            break;
+        }
+        if quoted_any || cells.len() > 1 || !cells[0].is_empty() {
+            records.push(Record { line: start, cells });
+        }
+    }
+    Ok(records)
+}
+
+/// Read one field, stopping before its delimiter or line break
+fn field(
+    chars: &mut Peekable<Chars>,
+    options: &TableOptions,
+    line: &mut usize,
+    start: usize,
+) -> Result<(String, bool), ReconError> {
+    let blank = |c: &char| (*c == ' ' || *c == '\t') && *c != options.delimiter;
+    let ends = |c: &char| *c == options.delimiter || *c == '\n' || *c == '\r';
+
+    let mut cell = String::new();
+    while let Some(c) = chars.next_if(blank) {
+        cell.push(c);
+    }
+    if chars.next_if_eq(&options.quote).is_none() {
+        while let Some(c) = chars.next_if(|c| !ends(c)) {
+            cell.push(c);
+        }
+        return Ok((cell, false));
+    }
+
+    cell.clear();
+    loop {
+        match chars.next() {
+            None => {
+                return Err(ReconError::invalid_input(format!("Quoted field opened on line {} is never closed", start)))
+            }
+            Some(c) if c == options.quote => {
+                if chars.next_if_eq(&options.quote).is_none() {
+                    break;
+                }
+                cell.push(c);
+            }
+            Some(c @ ('\r' | '\n')) => {
+                if c == '\r' {
+                    chars.next_if_eq(&'\n');
+                }
+                cell.push('\n');
+                *line += 1;
+            }
+            Some(c) => cell.push(c),
+        }
+    }
+    while chars.next_if(blank).is_some() {}
+    match chars.peek() {
+        Some(c) if !ends(c) => {
+            Err(ReconError::invalid_input(format!("Unexpected {:?} after a closing quote on line {}", c, line)))
+        }
+        _ => Ok((cell, true)),
+    }
+}
+
+/// SHA-256 of the cells as a JSON array, in hex
+pub fn row_hash(cells: &[String]) -> String {
+    let json = serde_json::to_string(cells).expect("strings serialize");
+    to_hex(&sha256(json.as_bytes()))
+}
+
+/// Hash the header and every data row of `content`
+pub fn hash_rows(content: &str, options: &TableOptions) -> Result<RowHashes, ReconError> {
+    let mut records = parse(content, options)?.into_iter();
+    let header_hash = if options.has_header { records.next().map(|header| row_hash(&header.cells)) } else { None };
+    let rows = records.map(|record| RowHash { line: record.line, hash: row_hash(&record.cells) }).collect();
+    Ok(RowHashes { header_hash, rows })
+}
+
+/// Added, removed and changed rows between two tables, paired by the values
+/// of `key_columns`
+pub fn diff_rows(old: &str, new: &str, key_columns: &[String], options: &TableOptions) -> Result<RowDiff, ReconError> {
+    if !options.has_header {
+        return Err(ReconError::invalid_options("diff_rows needs a header to name the key columns"));
+    }
+    if key_columns.is_empty() {
+        return Err(ReconError::invalid_input("diff_rows needs at least one key column"));
+    }
+    let old = Keyed::new(parse(old, options)?, key_columns)?;
+    let new = Keyed::new(parse(new, options)?, key_columns)?;
+
+    let mut columns = old.columns.clone();
+    columns.extend(new.columns.iter().filter(|name| !old.columns.contains(name)).cloned());
+
+    let mut diff = RowDiff::default();
+    for (new_index, (key, _)) in new.rows.iter().enumerate() {
+        let Some(&old_index) = old.index.get(key) else {
+            diff.added.push(key.clone());
+            continue;
+        };
+        let changed: Vec<String> =
+            columns.iter().filter(|name| old.cell(old_index, name) != new.cell(new_index, name)).cloned().collect();
+        if !changed.is_empty() {
+            diff.changed.push(ChangedRow { key: key.clone(), columns: changed });
+        }
+    }
+    diff.removed =
+        old.rows.iter().filter(|(key, _)| !new.index.contains_key(key)).map(|(key, _)| key.clone()).collect();
+    Ok(diff)
+}
+
+/// Data rows of one table by key
+struct Keyed {
+    columns: Vec<String>,
+    /// Each row's key and record, in order
+    rows: Vec<(Vec<String>, Record)>,
+    index: HashMap<Vec<String>, usize>,
+}
+
+impl Keyed {
+    fn new(records: Vec<Record>, key_columns: &[String]) -> Result<Self, ReconError> {
+        let mut records = records.into_iter();
+        let Some(header) = records.next() else {
+            return Ok(Keyed { columns: Vec::new(), rows: Vec::new(), index: HashMap::new() });
+        };
+        let columns = header.cells;
+        for (i, name) in columns.iter().enumerate() {
+            if columns[..i].contains(name) {
+                return Err(ReconError::invalid_input(format!("Duplicate column {:?} in the header", name)));
+            }
+        }
+        let key_positions = key_columns
+            .iter()
+            .map(|name| {
+                columns
+                    .iter()
+                    .position(|column| column == name)
+                    .ok_or_else(|| ReconError::invalid_input(format!("Key column {:?} is not in the header", name)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut rows: Vec<(Vec<String>, Record)> = Vec::new();
+        let mut index: HashMap<Vec<String>, usize> = HashMap::new();
+        for record in records {
+            if record.cells.len() > columns.len() {
+                return Err(ReconError::invalid_input(format!(
+                    "Row on line {} has {} cells, the header has {}",
+                    record.line,
+                    record.cells.len(),
+                    columns.len()
+                )));
+            }
+            let key: Vec<String> =
+                key_positions.iter().map(|&i| record.cells.get(i).cloned().unwrap_or_default()).collect();
+            if let Some(&first) = index.get(&key) {
+                return Err(ReconError::invalid_input(format!(
+                    "Duplicate key {:?} on lines {} and {}",
+                    key, rows[first].1.line, record.line
+                )));
+            }
+            index.insert(key.clone(), rows.len());
+            rows.push((key, record));
+        }
+        Ok(Keyed { columns, rows, index })
+    }
+
+    /// The named cell of row `row`, empty when absent
+    fn cell(&self, row: usize, name: &str) -> &str {
+        self.columns
+            .iter()
+            .position(|column| column == name)
+            .and_then(|i| self.rows[row].1.cells.get(i))
+            .map_or("", String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    fn cells(content: &str, options: &TableOptions) -> Vec<Vec<String>> {
+        parse(content, options).unwrap().into_iter().map(|record| record.cells).collect()
+    }
+
+    fn keys(keys: &[&[&str]]) -> Vec<Vec<String>> {
+        keys.iter().map(|key| key.iter().map(|s| s.to_string()).collect()).collect()
+    }
+
+    fn row_diff(old: &str, new: &str, key: &[&str]) -> Result<RowDiff, ReconError> {
+        let key: Vec<String> = key.iter().map(|s| s.to_string()).collect();
+        diff_rows(old, new, &key, &TableOptions::default())
+    }
+
+    #[test]
+    fn test_quoted_fields() {
+        let content = "id,note\r\n1,\"a, b\"\r\n2,\"two\r\nlines\"\r\n3, \"say \"\"hi\"\"\" \r\n4,5\"6\n";
+        let records = parse(content, &TableOptions::default()).unwrap();
+        let lines: Vec<usize> = records.iter().map(|record| record.line).collect();
+        assert_eq!(lines, [1, 2, 3, 5, 6]);
+        assert_eq!(
+            cells(content, &TableOptions::default())[1..],
+            keys(&[&["1", "a, b"], &["2", "two\nlines"], &["3", "say \"hi\""], &["4", "5\"6"]])
+        );
+
+        // Line endings inside quotes do not change the row hash
+        let unix = hash_rows(&content.replace("\r\n", "\n"), &TableOptions::default()).unwrap();
+        let classic = hash_rows(&content.replace("\r\n", "\r"), &TableOptions::default()).unwrap();
+        assert_eq!(unix, hash_rows(content, &TableOptions::default()).unwrap());
+        assert_eq!(unix, classic);
+    }
+
+    #[test]
+    fn test_tsv_and_custom_quote() {
+        let options = TableOptions { delimiter: '\t', quote: '\'', has_header: false };
+        assert_eq!(cells("a\t'b\tc'\t d \n\n'e''f'\n", &options), keys(&[&["a", "b\tc", "d"], &["e'f"]]));
+        assert_eq!(hash_rows("a\tb", &options).unwrap().header_hash, None);
+        assert_eq!(cells("\u{feff}a,b\n", &TableOptions::default()), keys(&[&["a", "b"]]));
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        let code = |content: &str| parse(content, &TableOptions::default()).unwrap_err().code;
+        assert_eq!(code("a,\"open\nb,c\n"), ErrorCode::InvalidInput);
+        assert_eq!(code("a,\"closed\"x,b\n"), ErrorCode::InvalidInput);
+        let options = TableOptions { delimiter: '"', ..TableOptions::default() };
+        assert_eq!(parse("a", &options).unwrap_err().code, ErrorCode::InvalidOptions);
+    }
+
+    #[test]
+    fn test_row_hashes_are_unambiguous() {
+        assert_ne!(row_hash(&keys(&[&["a", "b,c"]])[0]), row_hash(&keys(&[&["a,b", "c"]])[0]));
+        let hashes = hash_rows("x,y\n\"a\",\"b,c\"\n a , b,c\n", &TableOptions::default()).unwrap();
+        assert_eq!(hashes.header_hash, Some(row_hash(&keys(&[&["x", "y"]])[0])));
+        assert_eq!(hashes.rows[0].hash, row_hash(&keys(&[&["a", "b,c"]])[0]));
+        assert_eq!(hashes.rows[1].hash, row_hash(&keys(&[&["a", "b", "c"]])[0]));
+    }
+
+    #[test]
+    fn test_header_only_file() {
+        let hashes = hash_rows("id,name\n", &TableOptions::default()).unwrap();
+        assert!(hashes.header_hash.is_some());
+        assert!(hashes.rows.is_empty());
+        assert_eq!(hash_rows("", &TableOptions::default()).unwrap(), RowHashes { header_hash: None, rows: vec![] });
+
+        let diff = row_diff("id,name\n", "id,name\n1,a\n", &["id"]).unwrap();
+        assert_eq!(diff.added, keys(&[&["1"]]));
+        assert!(diff.removed.is_empty() && diff.changed.is_empty());
+        assert_eq!(row_diff("", "id\n1\n", &["id"]).unwrap().added, keys(&[&["1"]]));
+    }
+
+    #[test]
+    fn test_diff_rows() {
+        let old = "id,region,name,qty\n1,eu,Widget,4\n2,eu,\"Gadget,\nlarge\",1\n3,us,Gizmo,9\n";
+        let new = "region,id,qty,name\neu,2,1,\"Gadget,\r\nlarge\"\nus,3,10,Gizmo!\neu,4,1,Doohickey\n";
+        let diff = row_diff(old, new, &["id", "region"]).unwrap();
+        assert_eq!(diff.added, keys(&[&["4", "eu"]]));
+        assert_eq!(diff.removed, keys(&[&["1", "eu"]]));
+        let changed = ChangedRow { key: vec!["3".into(), "us".into()], columns: vec!["name".into(), "qty".into()] };
+        assert_eq!(diff.changed, vec![changed]);
+
+        // A dropped trailing cell and a new column compare as empty cells
+        let diff = row_diff("id,a\n1,\n2,x\n", "id,a,b\n1\n2,x,y\n", &["id"]).unwrap();
+        assert_eq!(diff.changed, vec![ChangedRow { key: vec!["2".into()], columns: vec!["b".into()] }]);
+    }
+
+    #[test]
+    fn test_diff_rows_rejects_duplicate_keys() {
+        let err = row_diff("id,v\n1,a\n2,b\n1,c\n", "id,v\n", &["id"]).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidInput);
+        assert!(err.message.contains("lines 2 and 4"), "{}", err.message);
+        // Distinct once the second key column is included
+        assert_eq!(row_diff("id,v\n1,a\n1,b\n", "id,v\n1,a\n1,b\n", &["id", "v"]).unwrap(), RowDiff::default());
+
+        assert_eq!(row_diff("id\n1\n", "id\n1\n", &["missing"]).unwrap_err().code, ErrorCode::InvalidInput);
+        assert_eq!(row_diff("id,id\n1,2\n", "id\n", &["id"]).unwrap_err().code, ErrorCode::InvalidInput);
+        assert_eq!(row_diff("id\n1,2\n", "id\n", &["id"]).unwrap_err().code, ErrorCode::InvalidInput);
+        assert_eq!(row_diff("id\n", "id\n", &[]).unwrap_err().code, ErrorCode::InvalidInput);
+        let headerless = TableOptions { has_header: false, ..TableOptions::default() };
+        assert_eq!(diff_rows("", "", &["id".into()], &headerless).unwrap_err().code, ErrorCode::InvalidOptions);
+    }
+}
@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Tree hashes - one digest for a set of named documents
+//
+// Scheme (all hashes SHA-256, lengths and counts as 8-byte big-endian):
+//
+//   content_hash = SHA-256(content)
+//   entry_hash   = SHA-256(len(path) || path || content_hash)
+//   root         = SHA-256(count || entry_hash_1 || ... || entry_hash_n)
+//
+// `path` is the UTF-8 bytes of the path exactly as given (no separator or
+// case normalization) and `len(path)` is its byte length. Entries are
+// sorted by path bytes before the root is computed, so the order the
+// caller supplies them in does not matter. Paths must be unique. An empty
+// tree hashes to SHA-256 of eight zero bytes.
+
+use crate::hashing::sha256;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+/// A named document
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TreeEntry {
+    pub path: String,
+    pub content: String,
+}
+
+/// Hash of a single entry
+pub fn entry_hash(path: &str, content: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update((path.len() as u64).to_be_bytes());
+    hasher.update(path.as_bytes());
+    hasher.update(sha256(content));
+    hasher.finalize().into()
+}
+
+/// Root hash over all entries, independent of their order
+pub fn hash_tree(entries: &[TreeEntry]) -> Result<[u8; 32], String> {
+    let mut seen = HashSet::new();
+    for entry in entries {
+        if !seen.insert(entry.path.as_str()) {
+            return Err(format!("Duplicate path in tree: {}", entry.path));
+        }
+    }
+
+    let mut sorted: Vec<&TreeEntry> = entries.iter().collect();
+    sorted.sort_by(|a, b| a.path.as_bytes().cmp(b.path.as_bytes()));
+
+    let mut hasher = Sha256::new();
+    hasher.update((sorted.len() as u64).to_be_bytes());
+    for entry in sorted {
+        hasher.update(entry_hash(&entry.path, entry.content.as_bytes()));
+    }
+    Ok(hasher.finalize().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashing::to_hex;
+
+    fn entry(path: &str, content: &str) -> TreeEntry {
+        TreeEntry { path: path.to_string(), content: content.to_string() }
+    }
+
+    fn sample() -> Vec<TreeEntry> {
+        vec![
+            entry("README.md", "# Project\n"),
+            entry("docs/guide.md", "Guide\n"),
+            entry("docs/api.md", "API\n"),
+            entry("LICENSE", "AGPL-3.0-or-later\n"),
+            entry("src/main.rs", "fn main() {}\n"),
+        ]
+    }
+
+    #[test]
+    fn test_permutation_invariance() {
+        let entries = sample();
+        let root = hash_tree(&entries).unwrap();
+
+        let mut reversed = entries.clone();
+        reversed.reverse();
+        assert_eq!(hash_tree(&reversed).unwrap(), root);
+
+        for shift in 1..entries.len() {
+            let mut rotated = entries.clone();
+            rotated.rotate_left(shift);
+            assert_eq!(hash_tree(&rotated).unwrap(), root);
+        }
+    }
+
+    #[test]
+    fn test_single_entry_matches_documented_scheme() {
+        let mut preimage = Vec::new();
+        preimage.extend_from_slice(&5u64.to_be_bytes());
+        preimage.extend_from_slice(b"a.txt");
+        preimage.extend_from_slice(&sha256(b"hello"));
+        let expected_entry = sha256(&preimage);
+        assert_eq!(entry_hash("a.txt", b"hello"), expected_entry);
+
+        let mut root_preimage = 1u64.to_be_bytes().to_vec();
+        root_preimage.extend_from_slice(&expected_entry);
+        assert_eq!(hash_tree(&[entry("a.txt", "hello")]).unwrap(), sha256(&root_preimage));
+    }
+
+    #[test]
+    fn test_one_byte_path_change() {
+        let root = hash_tree(&sample()).unwrap();
+        let mut renamed = sample();
+        renamed[1].path = "docs/guidf.md".to_string();
+        assert_ne!(hash_tree(&renamed).unwrap(), root);
+
+        let mut edited = sample();
+        edited[1].content = "Guide!\n".to_string();
+        assert_ne!(hash_tree(&edited).unwrap(), root);
+    }
+
+    #[test]
+    fn test_sorting_is_bytewise() {
+        // Uppercase sorts before lowercase and "a/b" before "a0"
+        let mut entries = vec![entry("b", "1"), entry("B", "2"), entry("a0", "3"), entry("a/b", "4")];
+        let root = hash_tree(&entries).unwrap();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(entries.iter().map(|e| e.path.as_str()).collect::<Vec<_>>(), vec!["B", "a/b", "a0", "b"]);
+        assert_eq!(hash_tree(&entries).unwrap(), root);
+    }
+
+    #[test]
+    fn test_duplicate_paths_rejected() {
+        let entries = vec![entry("a", "1"), entry("b", "2"), entry("a", "1")];
+        assert_eq!(hash_tree(&entries).unwrap_err(), "Duplicate path in tree: a");
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        assert_eq!(to_hex(&hash_tree(&[]).unwrap()), to_hex(&sha256(&[0u8; 8])));
+    }
+}
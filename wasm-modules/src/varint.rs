@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Unsigned LEB128 varints, as used by multihash and binary deltas
+//
+// Seven bits per byte, least significant group first, the high bit set on
+// every byte but the last. Decoding is strict: a value has exactly one
+// encoding, so a trailing zero group is rejected, as is a tenth byte
+// carrying bits beyond the 64th.
+
+/// Longest encoding of a `u64`
+pub const MAX_LEN: usize = 10;
+
+/// Why a varint could not be read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VarintError {
+    /// The input ended before the last byte
+    Truncated,
+    /// More than the permitted number of bytes
+    TooLong,
+    /// A longer encoding than the value needs
+    NotMinimal,
+    /// The value does not fit in 64 bits
+    Overflow,
+}
+
+/// Append `value` as an unsigned LEB128 varint
+pub fn write(value: u64, out: &mut Vec<u8>) {
+    let mut value = value;
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Read a varint of at most `max_len` bytes from the start of `bytes`,
+/// returning it and the number of bytes it took
+pub fn read(bytes: &[u8], max_len: usize) -> Result<(u64, usize), VarintError> {
+    let mut value: u64 = 0;
+    for (i, &byte) in bytes.iter().enumerate().take(max_len.min(MAX_LEN)) {
+        if i == MAX_LEN - 1 && byte > 1 {
+            return Err(VarintError::Overflow);
+        }
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            if byte == 0 && i > 0 {
+                return Err(VarintError::NotMinimal);
+            }
+            return Ok((value, i + 1));
+        }
+    }
+    if bytes.len() >= max_len.min(MAX_LEN) {
+        Err(VarintError::TooLong)
+    } else {
+        Err(VarintError::Truncated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(value: u64) -> Vec<u8> {
+        let mut out = Vec::new();
+        write(value, &mut out);
+        out
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for value in [0u64, 1, 0x7f, 0x80, 0x3fff, 0x4000, 0xb220, u32::MAX as u64, 1 << 63, u64::MAX] {
+            let bytes = encode(value);
+            assert_eq!(read(&bytes, MAX_LEN), Ok((value, bytes.len())));
+        }
+        assert_eq!(encode(0xb220), [0xa0, 0xe4, 0x02]);
+        assert_eq!(encode(u64::MAX).len(), MAX_LEN);
+        assert_eq!(read(&[0x05, 0xff], MAX_LEN), Ok((5, 1)));
+    }
+
+    #[test]
+    fn test_rejects_malformed() {
+        assert_eq!(read(&[], MAX_LEN), Err(VarintError::Truncated));
+        assert_eq!(read(&[0x80], MAX_LEN), Err(VarintError::Truncated));
+        assert_eq!(read(&[0x80, 0x00], MAX_LEN), Err(VarintError::NotMinimal));
+        assert_eq!(read(&[0x81, 0x80, 0x00], MAX_LEN), Err(VarintError::NotMinimal));
+        assert_eq!(read(&[0xff; 11], MAX_LEN), Err(VarintError::Overflow));
+        assert_eq!(read(&[0x80, 0x80, 0x80], 2), Err(VarintError::TooLong));
+
+        // The tenth byte holds bit 63 and nothing more
+        let mut max = encode(u64::MAX);
+        max[9] = 0x02;
+        assert_eq!(read(&max, MAX_LEN), Err(VarintError::Overflow));
+        let mut extra = encode(u64::MAX);
+        extra[9] |= 0x80;
+        extra.push(0x01);
+        assert_eq!(read(&extra, MAX_LEN), Err(VarintError::Overflow));
+    }
+}
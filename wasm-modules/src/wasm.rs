@@ -0,0 +1,2707 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// WASM bindings - the `#[wasm_bindgen]` exports, built with the default
+// `wasm` feature
+//
+// These functions convert between JS values and Rust types, check inputs
+// against the limits and report errors as JS `Error`s; the work itself is
+// done by `core` and the feature modules, so native callers get the same
+// results.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::{
+    align, cdc, chain, core, dedupe, delta, diff, distance, encoding, fingerprint, fold, hashing, keyed, limits,
+    merge, merkle, minhash, multihash, normalize, patch, progress, provenance, quick, reconforth, redact, rolling,
+    section, segment, shard, similarity, stats, table, tree
+};
+use crate::align::AlignOptions;
+use crate::diff::{DiffOptions, Granularity};
+use crate::error::{ErrorCode, ReconError};
+use crate::fold::FoldOptions;
+use crate::hashing::{digest_from_hex, to_hex, DigestEncoding, HashAlgorithm};
+use crate::limits::BatchBudget;
+use crate::manifest::{Manifest, ManifestEntries};
+use crate::merge::MergeOptions;
+use crate::normalize::{NormalizeOptions, UnicodeForm};
+use crate::progress::Progress;
+use crate::recipe::Recipe;
+use crate::redact::Redaction;
+use crate::reconforth::{Bundle, Document, VM};
+use crate::scratch::{BatchBuffers, BufferStats};
+use crate::table::TableOptions;
+
+// ============================================================================
+// Original WASM functions
+// ============================================================================
+
+/// WASM-accelerated SHA-256 content hashing
+/// Provides AOT-compiled performance for critical operations
+#[wasm_bindgen]
+pub fn hash_content(content: &str) -> Result<String, ReconError> {
+    hash_bytes(content.as_bytes())
+}
+
+/// SHA-256 hashing of raw bytes (`Uint8Array` on the JS side)
+/// Output matches `sha256sum` on the same file
+#[wasm_bindgen]
+pub fn hash_bytes(data: &[u8]) -> Result<String, ReconError> {
+    limits::check_input(data.len())?;
+    Ok(to_hex(&core::hash_bytes(data)))
+}
+
+/// BLAKE3 content hashing
+/// Much faster than SHA-256 for bulk runs; use for internal dedup keys only,
+/// digests are not interchangeable with `hash_content`
+#[wasm_bindgen]
+pub fn hash_content_blake3(content: &str) -> Result<String, ReconError> {
+    limits::check_input(content.len())?;
+    Ok(HashAlgorithm::Blake3.hash(content.as_bytes()))
+}
+
+/// Check content against an expected SHA-256 hex digest
+///
+/// The comparison is case-insensitive and constant-time; malformed,
+/// whitespace-padded or wrong-length digests return false
+#[wasm_bindgen]
+pub fn verify_content(content: &str, expected_hash: &str) -> Result<bool, ReconError> {
+    limits::check_input(content.len())?;
+    Ok(match digest_from_hex(expected_hash) {
+        Some(expected) => hashing::digests_equal(&hashing::sha256(content.as_bytes()), &expected),
+        None => false,
+    })
+}
+
+/// Constant-time, case-insensitive comparison of two stored hex digests
+/// Malformed or wrong-length digests compare unequal
+#[wasm_bindgen]
+pub fn hashes_equal(a: &str, b: &str) -> bool {
+    hashing::hex_digests_equal(a, b)
+}
+
+/// SHA-256 content hash in a chosen text encoding
+/// `encoding` is one of "hex", "base64", "base64url" (unpadded, RFC 4648 §5)
+#[wasm_bindgen]
+pub fn hash_content_encoded(content: &str, encoding: &str) -> Result<String, ReconError> {
+    limits::check_input(content.len())?;
+    let encoding = DigestEncoding::from_name(encoding)
+        .ok_or_else(|| ReconError::invalid_input(format!("Unknown digest encoding: {}", encoding)))?;
+    Ok(encoding.encode(&hashing::sha256(content.as_bytes())))
+}
+
+/// Raw 32-byte SHA-256 digest (`Uint8Array` on the JS side)
+#[wasm_bindgen]
+pub fn hash_content_raw(content: &str) -> Result<Vec<u8>, ReconError> {
+    limits::check_input(content.len())?;
+    Ok(hashing::sha256(content.as_bytes()).to_vec())
+}
+
+/// Multihash of UTF-8 content, base58btc-encoded (CIDv0-style `Qm...` for sha2-256)
+/// `codec` is a multicodec name, "sha2-256" (default) or "blake3"
+#[wasm_bindgen]
+pub fn hash_content_multihash(content: &str, codec: Option<String>) -> Result<String, ReconError> {
+    limits::check_input(content.len())?;
+    let algorithm = match codec.as_deref() {
+        None => HashAlgorithm::Sha256,
+        Some(name) => multihash::from_name(name)
+            .ok_or_else(|| ReconError::invalid_input(format!("Unknown multihash codec: {}", name)))?,
+    };
+    Ok(multihash::encode(algorithm, content.as_bytes()))
+}
+
+/// Unwrap a base58btc multihash to its digest as lowercase hex
+#[wasm_bindgen]
+pub fn multihash_to_hex(mh: &str) -> Result<String, ReconError> {
+    multihash::to_hex_digest(mh).map_err(ReconError::decode)
+}
+
+/// SHA-256 of the RFC 8785 (JCS) canonical form of a JSON document
+///
+/// Documents holding the same data hash equally regardless of key order,
+/// whitespace or number formatting. Invalid JSON, NaN/Infinity and
+/// duplicate keys are errors; the message includes the byte offset
+#[wasm_bindgen]
+pub fn hash_json_canonical(json: &str) -> Result<String, ReconError> {
+    limits::check_input(json.len())?;
+    core::hash_json_canonical(json).map(|digest| to_hex(&digest))
+}
+
+/// Options accepted by `batch_hash`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct BatchHashOptions {
+    algorithm: HashAlgorithm,
+    encoding: DigestEncoding,
+    /// Items between progress callback invocations
+    progress_interval: usize,
+    /// Return `{ recipe, hashes }` instead of the bare hashes
+    include_recipe: bool,
+}
+
+impl Default for BatchHashOptions {
+    fn default() -> Self {
+        BatchHashOptions {
+            algorithm: HashAlgorithm::default(),
+            encoding: DigestEncoding::default(),
+            progress_interval: progress::DEFAULT_INTERVAL,
+            include_recipe: false,
+        }
+    }
+}
+
+/// WASM-accelerated content normalization
+/// Handles whitespace normalization faster than JS
+#[wasm_bindgen]
+pub fn normalize_content(content: &str) -> Result<String, ReconError> {
+    limits::check_input(content.len())?;
+    Ok(core::normalize(content, &NormalizeOptions::default()))
+}
+
+/// Content normalization with a caller-supplied policy
+///
+/// `options` fields (all optional, defaults match `normalize_content`):
+/// `line_ending` ("lf" | "crlf" | "preserve"), `trim`, `trim_lines`,
+/// `collapse_blank_lines` (max consecutive blank lines, `null` for no limit),
+/// `strip_bom`, `collapse_spaces`, `unicode_form` ("none" | "nfc" | "nfd" | "nfkc" | "nfkd"),
+/// `strip_invisible` (zero-width, bidi and control characters), `replace_nbsp`
+#[wasm_bindgen]
+pub fn normalize_content_with_options(content: &str, options: &JsValue) -> Result<String, ReconError> {
+    limits::check_input(content.len())?;
+    let options: NormalizeOptions = parse_options(options)?;
+    Ok(core::normalize(content, &options))
+}
+
+/// Unicode normalization
+/// `form` is one of "none", "nfc", "nfd", "nfkc", "nfkd"
+#[wasm_bindgen]
+pub fn normalize_unicode(content: &str, form: &str) -> Result<String, ReconError> {
+    limits::check_input(content.len())?;
+    let form = UnicodeForm::from_name(form)
+        .ok_or_else(|| ReconError::invalid_input(format!("Unknown Unicode normalization form: {}", form)))?;
+    Ok(normalize::normalize_unicode(content, form).into_owned())
+}
+
+/// Lossy folding for fuzzy match keys
+///
+/// `options` fields (all optional, all default true): `compatibility`
+/// (NFKC: ligatures and full-width forms), `case_fold` (full Unicode case
+/// folding, so ß matches "ss"), `strip_accents` (NFD, then drop combining
+/// marks), `strip_punctuation` (drop everything but letters, digits, marks
+/// and whitespace) and `collapse_whitespace`. Steps run in that order and
+/// the result is NFC
+#[wasm_bindgen]
+pub fn fold_content(content: &str, options: &JsValue) -> Result<String, ReconError> {
+    limits::check_input(content.len())?;
+    let options: FoldOptions = parse_options(options)?;
+    Ok(fold::fold(content, &options))
+}
+
+/// SHA-256 hex digest of `content` folded with every `fold_content` step on
+///
+/// "Straße", "STRASSE" and "strasse" share a key, as do "Ｃafé" and "cafe"
+#[wasm_bindgen]
+pub fn match_key(content: &str) -> Result<String, ReconError> {
+    limits::check_input(content.len())?;
+    Ok(to_hex(&core::match_key(content)))
+}
+
+/// Report on raw bytes before they are treated as text
+///
+/// Returns `{ encoding_guess, has_bom, line_ending, valid_utf8, sample_invalid_offset? }`
+/// where `encoding_guess` is "utf-8", "utf-16le", "utf-16be", "windows-1252"
+/// or "binary", `line_ending` is "lf", "crlf", "cr", "mixed" or "none", and
+/// `sample_invalid_offset` is the byte offset of the first invalid UTF-8
+/// sequence. BOM-less UTF-16 is recognised by its alternating NUL bytes, so
+/// only mostly-Latin text is detected that way
+#[wasm_bindgen]
+pub fn detect_encoding(data: &[u8]) -> Result<JsValue, ReconError> {
+    limits::check_input(data.len())?;
+    to_js(&encoding::detect(data))
+}
+
+/// Transcode legacy bytes to a UTF-8 string
+///
+/// `source_encoding` is a WHATWG label such as "windows-1252", "iso-8859-1",
+/// "utf-16le" or "utf-16be" ("iso-8859-1" decodes as windows-1252, as in
+/// browsers). A BOM for the source encoding is dropped. Fails with
+/// `invalid_input` for an unsupported encoding and `decode_error`, naming the
+/// byte offset, when the bytes are not valid in it
+#[wasm_bindgen]
+pub fn to_utf8(data: &[u8], source_encoding: &str) -> Result<String, ReconError> {
+    limits::check_input(data.len())?;
+    encoding::to_utf8(data, source_encoding)
+}
+
+/// Transcode, normalize and hash legacy bytes in one call
+///
+/// The decoded text never crosses back into JS. `options` are those of
+/// `batch_normalize_and_hash`
+#[wasm_bindgen]
+pub fn hash_bytes_as(data: &[u8], source_encoding: &str, options: &JsValue) -> Result<String, ReconError> {
+    limits::check_input(data.len())?;
+    let options: NormalizeHashOptions = parse_options(options)?;
+    let text = encoding::to_utf8(data, source_encoding)?;
+    Ok(core::normalize_and_hash(&text, &options.normalize, options.algorithm))
+}
+
+/// First `hex_chars` characters of the SHA-256 hex digest of `content`
+///
+/// `hex_chars` must be 1..=64, otherwise fails with `invalid_input`
+#[wasm_bindgen]
+pub fn hash_prefix(content: &str, hex_chars: usize) -> Result<String, ReconError> {
+    hashing::validate_prefix_len(hex_chars).map_err(ReconError::invalid_input)?;
+    Ok(hashing::hex_prefix(&hash_content(content)?, hex_chars).to_string())
+}
+
+/// `hash_prefix` of every string in an array, in order
+#[wasm_bindgen]
+pub fn batch_hash_prefix(documents: &JsValue, hex_chars: usize) -> Result<JsValue, ReconError> {
+    hashing::validate_prefix_len(hex_chars).map_err(ReconError::invalid_input)?;
+    let docs = string_array(documents, "batch_hash_prefix")?;
+
+    let prefixes: Vec<String> = docs
+        .iter()
+        .map(|doc| hashing::hex_prefix(&to_hex(&core::hash_content(doc)), hex_chars).to_string())
+        .collect();
+
+    to_js(&prefixes)
+}
+
+/// Find documents whose SHA-256 prefixes of `hex_chars` characters collide
+///
+/// Returns `[{ prefix, indices }]` for every prefix shared by two or more
+/// documents, ordered by first occurrence with indices ascending. Identical
+/// documents always collide; run with increasing `hex_chars` to find the
+/// shortest prefix that keeps a corpus's distinct documents apart
+#[wasm_bindgen]
+pub fn find_prefix_collisions(documents: &JsValue, hex_chars: usize) -> Result<JsValue, ReconError> {
+    hashing::validate_prefix_len(hex_chars).map_err(ReconError::invalid_input)?;
+    let docs = string_array(documents, "find_prefix_collisions")?;
+
+    let hashes = docs.iter().map(|doc| to_hex(&core::hash_content(doc)));
+    to_js(&dedupe::prefix_collisions(hashes, hex_chars))
+}
+
+/// Batch hash multiple documents
+/// Optimized for bulk operations
+///
+/// Entries may be strings (hashed as UTF-8) or `Uint8Array`s (hashed as raw bytes).
+/// `options` is optional: `{ algorithm: "sha256" | "blake3", encoding: "hex" | "base64" | "base64url",
+/// progress_interval, include_recipe }`, default sha256 in hex. With
+/// `include_recipe` the result is `{ recipe, hashes }`, naming the `raw`
+/// recipe that `hash_with_recipe` (or `hash_bytes_with_recipe`, for
+/// `Uint8Array` entries) reproduces the hashes with; it needs hex encoding.
+/// `progress` is an optional `(processed, total)` callback invoked every
+/// `progress_interval` items (default 1000) and after the last one; if it
+/// throws, the batch stops and the exception is rethrown
+#[wasm_bindgen]
+pub fn batch_hash(
+    documents: &JsValue,
+    options: &JsValue,
+    progress: Option<js_sys::Function>,
+) -> Result<JsValue, JsValue> {
+    let options: BatchHashOptions = parse_options(options)?;
+    let recipe = requested_recipe(options.include_recipe, None, options.algorithm, options.encoding)?;
+    let docs = js_array(documents, "batch_hash")?;
+
+    let total = docs.length() as usize;
+    let mut progress = Progress::new(total, options.progress_interval, js_progress(progress.as_ref()));
+    let mut hashes = Vec::with_capacity(total);
+    let mut budget = BatchBudget::new(total)?;
+    for (index, doc) in docs.iter().enumerate() {
+        let hash = BatchEntry::from_js(&doc).within(index, &mut budget)?
+            .hash(options.algorithm, options.encoding)
+            .map_err(|reason| entry_error("batch_hash", index, &reason))?;
+        hashes.push(hash);
+        progress.tick()?;
+    }
+
+    Ok(with_recipe(recipe, hashes)?)
+}
+
+/// Hash one shard of a batch, for fanning a batch out across Workers
+///
+/// The batch is split into `total_shards` contiguous ranges whose lengths
+/// differ by at most one; this call hashes range number `shard` (from 0)
+/// and returns `{ start, hashes }`, the hashes of
+/// `documents[start .. start + hashes.length]`. Concatenating the shards in
+/// order gives exactly what `batch_hash` returns for the same documents and
+/// options. Error indices refer to the whole batch. With `include_recipe`
+/// every shard also carries `recipe`
+#[wasm_bindgen]
+pub fn batch_hash_sharded(
+    documents: &JsValue,
+    shard: usize,
+    total_shards: usize,
+    options: &JsValue,
+) -> Result<JsValue, ReconError> {
+    let options: BatchHashOptions = parse_options(options)?;
+    let recipe = requested_recipe(options.include_recipe, None, options.algorithm, options.encoding)?;
+    let docs = js_array(documents, "batch_hash_sharded")?;
+    let range =
+        shard::shard_range(docs.length() as usize, shard, total_shards).map_err(ReconError::invalid_input)?;
+
+    let mut budget = BatchBudget::new(range.len())?;
+    let entries = range
+        .clone()
+        .map(|index| BatchEntry::from_js(&docs.get(index as u32)).within(index, &mut budget))
+        .collect::<Result<Vec<_>, ReconError>>()?;
+    let hashes = hash_range(range.start, entries.into_iter(), &options, "batch_hash_sharded")?;
+
+    to_js(&ShardHashes { start: range.start, hashes, recipe: recipe.map(|recipe| recipe.to_string()) })
+}
+
+/// Result of `batch_hash_sharded`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct ShardHashes {
+    start: usize,
+    hashes: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recipe: Option<String>,
+}
+
+/// Hash consecutive entries, the first being batch element `start`
+fn hash_range(
+    start: usize,
+    entries: impl Iterator<Item = BatchEntry>,
+    options: &BatchHashOptions,
+    function: &str,
+) -> Result<Vec<String>, ReconError> {
+    entries
+        .enumerate()
+        .map(|(offset, entry)| {
+            entry
+                .hash(options.algorithm, options.encoding)
+                .map_err(|reason| entry_error(function, start + offset, &reason))
+        })
+        .collect()
+}
+
+/// Batch hash with per-element error recovery
+///
+/// Unlike `batch_hash`, a malformed entry does not abort the batch: every
+/// element yields `{ index, hash }` or `{ index, error }` so callers can
+/// retry just the failures. `null`/`undefined` entries are reported as errors.
+/// With `include_recipe` the result is `{ recipe, hashes }`, `hashes` being
+/// that array
+#[wasm_bindgen]
+pub fn batch_hash_v2(documents: &JsValue, options: &JsValue) -> Result<JsValue, ReconError> {
+    let options: BatchHashOptions = parse_options(options)?;
+    let recipe = requested_recipe(options.include_recipe, None, options.algorithm, options.encoding)?;
+
+    let docs = js_array(documents, "batch_hash_v2")?;
+
+    let mut budget = BatchBudget::new(docs.length() as usize)?;
+    let entries = docs
+        .iter()
+        .enumerate()
+        .map(|(index, doc)| BatchEntry::from_js(&doc).within(index, &mut budget))
+        .collect::<Result<Vec<_>, ReconError>>()?;
+    let results = hash_entries(entries.into_iter(), options.algorithm, options.encoding);
+
+    with_recipe(recipe, results)
+}
+
+/// Hash documents keyed by caller-supplied IDs
+///
+/// `documents` is either an array of `{ id, content }` objects or a plain
+/// object mapping id → content. Content may be a string, or missing/`null`,
+/// which hashes as the empty string. Duplicate IDs in the array form are an
+/// error. Returns a plain object (not a `Map`) of id → hash.
+/// `options` as for `batch_hash` (`progress_interval` is ignored); with
+/// `include_recipe` the object is returned as `{ recipe, hashes }`
+#[wasm_bindgen]
+pub fn batch_hash_keyed(documents: &JsValue, options: &JsValue) -> Result<JsValue, ReconError> {
+    let options: BatchHashOptions = parse_options(options)?;
+    let recipe = requested_recipe(options.include_recipe, None, options.algorithm, options.encoding)?;
+    check_records(documents, &["id", "content"])?;
+    let documents: KeyedDocuments = serde_wasm_bindgen::from_value(documents.clone()).map_err(|_| {
+        ReconError::invalid_input("batch_hash_keyed expects an array of { id, content } or an object of id to content")
+    })?;
+
+    let hashes = hash_keyed(documents, options.algorithm, options.encoding)?;
+
+    let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+    let result = match recipe {
+        Some(recipe) => RecipeHashes { recipe: recipe.to_string(), hashes }.serialize(&serializer),
+        None => hashes.serialize(&serializer),
+    };
+    result.map_err(|e| ReconError::serialization(e.to_string()))
+}
+
+/// Input accepted by `batch_hash_keyed`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+enum KeyedDocuments {
+    List(Vec<KeyedDocument>),
+    Map(BTreeMap<String, Option<String>>),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct KeyedDocument {
+    id: String,
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// Apply the batch limits to an array of records or an object of key to
+/// value before copying it
+///
+/// Array elements are measured by the `fields` named, object entries by key
+/// and value. Shapes other than an array or an object are left for
+/// deserialization to reject
+fn check_records(records: &JsValue, fields: &[&str]) -> Result<(), ReconError> {
+    let field = |value: &JsValue, key: &JsValue| js_sys::Reflect::get(value, key).unwrap_or(JsValue::UNDEFINED);
+    if let Some(list) = records.dyn_ref::<js_sys::Array>() {
+        let fields: Vec<JsValue> = fields.iter().map(|name| JsValue::from_str(name)).collect();
+        limits::check_batch((0..list.length()).map(|i| {
+            let record = list.get(i);
+            fields.iter().map(|name| BatchEntry::size_hint(&field(&record, name))).sum()
+        }))
+    } else if records.is_object() {
+        let keys = js_sys::Object::keys(records.unchecked_ref());
+        limits::check_batch((0..keys.length()).map(|i| {
+            let key = keys.get(i);
+            BatchEntry::size_hint(&key) + BatchEntry::size_hint(&field(records, &key))
+        }))
+    } else {
+        Ok(())
+    }
+}
+
+/// Hash every document, keyed by ID
+fn hash_keyed(
+    documents: KeyedDocuments,
+    algorithm: HashAlgorithm,
+    encoding: DigestEncoding,
+) -> Result<BTreeMap<String, String>, ReconError> {
+    let pairs: Vec<(String, Option<String>)> = match documents {
+        KeyedDocuments::List(list) => list.into_iter().map(|doc| (doc.id, doc.content)).collect(),
+        KeyedDocuments::Map(map) => map.into_iter().collect(),
+    };
+    let mut hashes = BTreeMap::new();
+    for (index, (id, content)) in pairs.into_iter().enumerate() {
+        let hash = encoding.encode(&algorithm.digest(content.unwrap_or_default().as_bytes()));
+        if hashes.insert(id.clone(), hash).is_some() {
+            // Only the array form can repeat an id, so the index is a position in it
+            return Err(ReconError::invalid_input(format!("Duplicate document id: {}", id)).at(index));
+        }
+    }
+    Ok(hashes)
+}
+
+/// Adapt an optional JS `(processed, total)` callback for `Progress`
+fn js_progress(callback: Option<&js_sys::Function>) -> impl FnMut(usize, usize) -> Result<(), JsValue> + '_ {
+    move |processed, total| match callback {
+        Some(callback) => callback
+            .call2(&JsValue::NULL, &JsValue::from_f64(processed as f64), &JsValue::from_f64(total as f64))
+            .map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+/// Parse an optional options object, falling back to defaults for `undefined`/`null`
+fn parse_options<T: Default + serde::de::DeserializeOwned>(options: &JsValue) -> Result<T, ReconError> {
+    if options.is_undefined() || options.is_null() {
+        return Ok(T::default());
+    }
+    serde_wasm_bindgen::from_value(options.clone()).map_err(|e| ReconError::invalid_options(e.to_string()))
+}
+
+/// Deserialize a JS argument, reporting failure as `invalid_input`
+fn from_js<T: serde::de::DeserializeOwned>(value: &JsValue) -> Result<T, ReconError> {
+    serde_wasm_bindgen::from_value(value.clone()).map_err(|e| ReconError::invalid_input(e.to_string()))
+}
+
+/// Convert a result for JS, reporting failure as `serialization_error`
+fn to_js<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, ReconError> {
+    serde_wasm_bindgen::to_value(value).map_err(|e| ReconError::serialization(e.to_string()))
+}
+
+/// `documents` as a JS array, checked against the batch limits before any
+/// element is copied into WASM memory
+fn js_array<'a>(documents: &'a JsValue, function: &str) -> Result<&'a js_sys::Array, ReconError> {
+    let docs = documents
+        .dyn_ref::<js_sys::Array>()
+        .ok_or_else(|| ReconError::invalid_input(format!("{} expects an array of documents", function)))?;
+    limits::check_batch((0..docs.length()).map(|i| BatchEntry::size_hint(&docs.get(i))))?;
+    Ok(docs)
+}
+
+/// A JS array of strings; the first non-string element is reported by index
+fn string_array(documents: &JsValue, function: &str) -> Result<Vec<String>, ReconError> {
+    js_array(documents, function)?
+        .iter()
+        .enumerate()
+        .map(|(index, doc)| {
+            doc.as_string().ok_or_else(|| {
+                ReconError::invalid_input(format!("{} entries must be strings", function)).at(index)
+            })
+        })
+        .collect()
+}
+
+/// Error for a batch element that is neither a string nor a `Uint8Array`
+fn entry_error(function: &str, index: usize, reason: &str) -> ReconError {
+    ReconError::invalid_input(format!("{} entries must be strings or Uint8Arrays: {}", function, reason)).at(index)
+}
+
+/// A single batch element after conversion from JS
+#[derive(Debug, Clone, PartialEq)]
+enum BatchEntry {
+    /// String content, hashed as UTF-8
+    Text(String),
+    /// `Uint8Array` content, hashed as raw bytes
+    Bytes(Vec<u8>),
+    /// Anything else, with a description of why it was rejected
+    Invalid(String),
+}
+
+impl BatchEntry {
+    /// Classify a JS value as string, bytes, or invalid
+    fn from_js(doc: &JsValue) -> BatchEntry {
+        if let Some(text) = doc.as_string() {
+            BatchEntry::Text(text)
+        } else if let Some(bytes) = doc.dyn_ref::<js_sys::Uint8Array>() {
+            BatchEntry::Bytes(bytes.to_vec())
+        } else if doc.is_null() {
+            BatchEntry::Invalid("entry is null".to_string())
+        } else if doc.is_undefined() {
+            BatchEntry::Invalid("entry is undefined".to_string())
+        } else {
+            let kind = doc.js_typeof().as_string().unwrap_or_default();
+            BatchEntry::Invalid(format!("expected string or Uint8Array, got {}", kind))
+        }
+    }
+
+    /// Count the exact size of batch element `index` against `budget`
+    ///
+    /// `js_array` checks strings by `size_hint` before anything is copied;
+    /// this keeps the limit on their real UTF-8 size once they are
+    fn within(self, index: usize, budget: &mut BatchBudget) -> Result<BatchEntry, ReconError> {
+        if let Ok(bytes) = self.bytes() {
+            budget.add(index, bytes.len())?;
+        }
+        Ok(self)
+    }
+
+    /// Size in bytes as far as it can be told without copying the value:
+    /// exact for `Uint8Array`s, the UTF-16 length (a lower bound on the UTF-8
+    /// length) for strings, and 0 for anything else
+    fn size_hint(doc: &JsValue) -> usize {
+        if let Some(text) = doc.dyn_ref::<js_sys::JsString>() {
+            text.length() as usize
+        } else if let Some(bytes) = doc.dyn_ref::<js_sys::Uint8Array>() {
+            bytes.byte_length() as usize
+        } else {
+            0
+        }
+    }
+
+    /// The bytes to hash, or the reason the entry cannot be hashed
+    fn bytes(&self) -> Result<&[u8], String> {
+        match self {
+            BatchEntry::Text(text) => Ok(text.as_bytes()),
+            BatchEntry::Bytes(bytes) => Ok(bytes),
+            BatchEntry::Invalid(reason) => Err(reason.clone()),
+        }
+    }
+
+    /// Hash the entry, or return the reason it cannot be hashed
+    fn hash(&self, algorithm: HashAlgorithm, encoding: DigestEncoding) -> Result<String, String> {
+        self.bytes().map(|bytes| encoding.encode(&algorithm.digest(bytes)))
+    }
+}
+
+/// Per-element result of `batch_hash_v2`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatchHashResult {
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Hash every entry independently, recording failures instead of aborting
+fn hash_entries(
+    entries: impl Iterator<Item = BatchEntry>,
+    algorithm: HashAlgorithm,
+    encoding: DigestEncoding,
+) -> Vec<BatchHashResult> {
+    entries
+        .enumerate()
+        .map(|(index, entry)| match entry.hash(algorithm, encoding) {
+            Ok(hash) => BatchHashResult { index, hash: Some(hash), error: None },
+            Err(error) => BatchHashResult { index, hash: None, error: Some(error) },
+        })
+        .collect()
+}
+
+/// Options accepted by `batch_normalize`: the normalization options plus
+/// `progress_interval`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct BatchNormalizeOptions {
+    #[serde(flatten)]
+    normalize: NormalizeOptions,
+    progress_interval: usize,
+}
+
+impl Default for BatchNormalizeOptions {
+    fn default() -> Self {
+        BatchNormalizeOptions {
+            normalize: NormalizeOptions::default(),
+            progress_interval: progress::DEFAULT_INTERVAL,
+        }
+    }
+}
+
+/// Batch normalize multiple documents
+///
+/// Takes an array of strings and one options object (same shape as
+/// `normalize_content_with_options`, plus `progress_interval`) applied to
+/// every entry; returns the normalized strings in input order.
+/// `progress` behaves as for `batch_hash`
+#[wasm_bindgen]
+pub fn batch_normalize(
+    documents: &JsValue,
+    options: &JsValue,
+    progress: Option<js_sys::Function>,
+) -> Result<JsValue, JsValue> {
+    let options: BatchNormalizeOptions = parse_options(options)?;
+    let docs = string_array(documents, "batch_normalize")?;
+
+    let mut progress = Progress::new(docs.len(), options.progress_interval, js_progress(progress.as_ref()));
+    let mut normalized = Vec::with_capacity(docs.len());
+    for doc in &docs {
+        normalized.push(core::normalize(doc, &options.normalize));
+        progress.tick()?;
+    }
+
+    Ok(to_js(&normalized)?)
+}
+
+/// Options accepted by `batch_normalize_and_hash`: the normalization
+/// options plus `algorithm`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct NormalizeHashOptions {
+    #[serde(flatten)]
+    normalize: NormalizeOptions,
+    algorithm: HashAlgorithm,
+}
+
+/// Options accepted by `batch_normalize_and_hash`: the normalization
+/// options, `algorithm` and `include_recipe`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct BatchNormalizeHashOptions {
+    #[serde(flatten)]
+    hash: NormalizeHashOptions,
+    include_recipe: bool,
+}
+
+/// Normalize and hash multiple documents in one pass
+///
+/// The normalized intermediate never crosses back into JS; the result is
+/// the array of hashes in input order. With `include_recipe` it is
+/// `{ recipe, hashes }`, which fails with `invalid_options` when the
+/// normalization options are not those of any recipe
+#[wasm_bindgen]
+pub fn batch_normalize_and_hash(documents: &JsValue, options: &JsValue) -> Result<JsValue, ReconError> {
+    let BatchNormalizeHashOptions { hash: options, include_recipe } = parse_options(options)?;
+    let recipe = requested_recipe(include_recipe, Some(&options.normalize), options.algorithm, DigestEncoding::Hex)?;
+    let docs = string_array(documents, "batch_normalize_and_hash")?;
+
+    with_recipe(recipe, core::batch_normalize_and_hash(&docs, &options.normalize, options.algorithm))
+}
+
+/// A batch result with the recipe that produced its hashes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct RecipeHashes<T> {
+    recipe: String,
+    hashes: T,
+}
+
+/// The recipe to report when `include_recipe` is set, for `normalize`
+/// options (`None` for raw content), `algorithm` and `encoding`
+///
+/// Recipes reproduce hex digests only, so any other encoding is
+/// `invalid_options` rather than a recipe the hashes cannot be checked with
+fn requested_recipe(
+    include_recipe: bool,
+    normalize: Option<&NormalizeOptions>,
+    algorithm: HashAlgorithm,
+    encoding: DigestEncoding,
+) -> Result<Option<Recipe>, ReconError> {
+    if !include_recipe {
+        return Ok(None);
+    }
+    if encoding != DigestEncoding::Hex {
+        return Err(ReconError::invalid_options("include_recipe needs hex encoding; recipes reproduce hex digests"));
+    }
+    Recipe::matching(normalize, algorithm)
+        .map(Some)
+        .ok_or_else(|| ReconError::invalid_options("include_recipe: the normalization options match no recipe"))
+}
+
+/// `hashes`, wrapped as `{ recipe, hashes }` if there is a recipe to report
+fn with_recipe<T: Serialize>(recipe: Option<Recipe>, hashes: T) -> Result<JsValue, ReconError> {
+    match recipe {
+        Some(recipe) => to_js(&RecipeHashes { recipe: recipe.to_string(), hashes }),
+        None => to_js(&hashes),
+    }
+}
+
+/// Options accepted by `dedupe_documents`: the `batch_normalize_and_hash`
+/// options plus `include_singletons`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct DedupeOptions {
+    #[serde(flatten)]
+    hash: NormalizeHashOptions,
+    include_singletons: bool,
+}
+
+/// Group identical documents after normalization
+///
+/// Takes an array of strings and returns `[{ hash, indices }]`, one group per
+/// distinct normalized document, ordered by first occurrence with indices in
+/// input order. Options are those of `batch_normalize_and_hash` plus
+/// `include_singletons` (default false, only groups with duplicates are returned)
+#[wasm_bindgen]
+pub fn dedupe_documents(documents: &JsValue, options: &JsValue) -> Result<JsValue, ReconError> {
+    let options: DedupeOptions = parse_options(options)?;
+    let docs = string_array(documents, "dedupe_documents")?;
+
+    let groups = core::dedupe_documents(
+        &docs,
+        &options.hash.normalize,
+        options.hash.algorithm,
+        options.include_singletons,
+    );
+
+    to_js(&groups)
+}
+
+/// Options accepted by `consensus`: the `batch_normalize_and_hash` options
+/// plus `raw`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct ConsensusOptions {
+    #[serde(flatten)]
+    hash: NormalizeHashOptions,
+    /// Compare the content exactly as given, skipping normalization
+    raw: bool,
+}
+
+/// Find the version most replicas of a document agree on
+///
+/// Takes an array of strings, one per replica, and returns
+/// `{ groups: [{ hash, indices }], majority_index_group?, tie }`. Groups are
+/// the distinct versions, largest first with equal sizes ordered by hash.
+/// `majority_index_group` (always 0) is present when one version is held by
+/// more than half of the replicas, and `tie` is true when the two largest
+/// groups are the same size. Options are those of `batch_normalize_and_hash`
+/// plus `raw` (default false) to compare un-normalized content
+#[wasm_bindgen]
+pub fn consensus(documents: &JsValue, options: &JsValue) -> Result<JsValue, ReconError> {
+    let options: ConsensusOptions = parse_options(options)?;
+    let docs = string_array(documents, "consensus")?;
+
+    let normalize = (!options.raw).then_some(&options.hash.normalize);
+
+    to_js(&core::consensus(&docs, normalize, options.hash.algorithm))
+}
+
+// ============================================================================
+// Hash recipes
+// ============================================================================
+
+/// Id of the recipe `normalize_content` and SHA-256 hash with, such as
+/// "norm-v1+sha256"
+///
+/// Store it next to each hash: `hash_with_recipe` keeps reproducing the
+/// digest after a release changes the default normalization
+#[wasm_bindgen]
+pub fn current_recipe() -> String {
+    Recipe::current().to_string()
+}
+
+/// Hash `content` with the pipeline of a recipe id, as a hex digest
+///
+/// Every id a release has shipped is accepted: "raw" or "norm-v1", then
+/// "+sha256" or "+blake3". Anything else fails with `unsupported_version`
+#[wasm_bindgen]
+pub fn hash_with_recipe(content: &str, recipe_id: &str) -> Result<String, ReconError> {
+    limits::check_input(content.len())?;
+    let recipe = Recipe::parse(recipe_id).map_err(|message| ReconError::new(ErrorCode::UnsupportedVersion, message))?;
+    Ok(recipe.hash(content))
+}
+
+/// `hash_with_recipe` for raw bytes, such as `Uint8Array` batch entries
+///
+/// A normalizing recipe needs the bytes to be UTF-8 and fails with
+/// `decode_error` otherwise
+#[wasm_bindgen]
+pub fn hash_bytes_with_recipe(data: &[u8], recipe_id: &str) -> Result<String, ReconError> {
+    limits::check_input(data.len())?;
+    let recipe = Recipe::parse(recipe_id).map_err(|message| ReconError::new(ErrorCode::UnsupportedVersion, message))?;
+    recipe.hash_bytes(data).map_err(ReconError::decode)
+}
+
+// ============================================================================
+// Content statistics
+// ============================================================================
+
+/// Options accepted by `content_stats`: the normalization options plus
+/// `raw` and `progress_interval`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct StatsOptions {
+    #[serde(flatten)]
+    normalize: NormalizeOptions,
+    /// Count the content as given instead of its normalized form
+    raw: bool,
+    /// Items between progress callback invocations (`batch_content_stats` only)
+    progress_interval: usize,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        StatsOptions {
+            normalize: NormalizeOptions::default(),
+            raw: false,
+            progress_interval: progress::DEFAULT_INTERVAL,
+        }
+    }
+}
+
+/// Per-document features for reconciliation heuristics
+///
+/// Returns `{ lines, words, graphemes, bytes, blank_lines, max_line_length,
+/// likely_binary }`. Counts are taken on the normalized content (options as
+/// for `normalize_content_with_options`) unless `raw: true` is set; words
+/// follow Unicode word boundaries and lengths are in grapheme clusters.
+/// `likely_binary` always looks at the content as given
+#[wasm_bindgen]
+pub fn content_stats(content: &str, options: &JsValue) -> Result<JsValue, ReconError> {
+    limits::check_input(content.len())?;
+    let options: StatsOptions = parse_options(options)?;
+    let stats = entry_stats(&BatchEntry::Text(content.to_string()), &options).map_err(ReconError::invalid_input)?;
+
+    to_js(&stats)
+}
+
+/// `content_stats` for many documents
+///
+/// Entries may be strings or `Uint8Array`s (decoded as UTF-8, with invalid
+/// sequences replaced); returns the stats in input order. `options` and
+/// `progress` as for `content_stats` and `batch_hash`
+#[wasm_bindgen]
+pub fn batch_content_stats(
+    documents: &JsValue,
+    options: &JsValue,
+    progress: Option<js_sys::Function>,
+) -> Result<JsValue, JsValue> {
+    let options: StatsOptions = parse_options(options)?;
+    let docs = js_array(documents, "batch_content_stats")?;
+
+    let total = docs.length() as usize;
+    let mut progress = Progress::new(total, options.progress_interval, js_progress(progress.as_ref()));
+    let mut all_stats = Vec::with_capacity(total);
+    let mut budget = BatchBudget::new(total)?;
+    for (index, doc) in docs.iter().enumerate() {
+        let stats = entry_stats(&BatchEntry::from_js(&doc).within(index, &mut budget)?, &options)
+            .map_err(|reason| entry_error("batch_content_stats", index, &reason))?;
+        all_stats.push(stats);
+        progress.tick()?;
+    }
+
+    Ok(to_js(&all_stats)?)
+}
+
+/// Statistics of one batch entry under `options`
+fn entry_stats(entry: &BatchEntry, options: &StatsOptions) -> Result<stats::ContentStats, String> {
+    let raw = entry.bytes()?;
+    let text = String::from_utf8_lossy(raw);
+    let stats = if options.raw {
+        stats::content_stats(&text, raw)
+    } else {
+        stats::content_stats(&normalize::normalize(&text, &options.normalize), raw)
+    };
+    Ok(stats)
+}
+
+// ============================================================================
+// Segmentation
+// ============================================================================
+
+/// Split content into paragraphs
+///
+/// `content` is normalized as by `normalize_content` and the result is
+/// `[{ text, start_byte, end_byte }]`, UTF-8 byte ranges into that
+/// normalized string. Paragraphs are separated by one or more blank lines
+#[wasm_bindgen]
+pub fn split_paragraphs(content: &str) -> Result<JsValue, ReconError> {
+    let normalized = normalize_content(content)?;
+    to_js(&segment::paragraphs(&normalized))
+}
+
+/// Split content into sentences using Unicode (UAX #29) sentence boundaries
+///
+/// Same normalization and result shape as `split_paragraphs`. Decimal
+/// numbers and lowercase continuations such as "e.g. this" do not split,
+/// but an abbreviation followed by a capital ("Dr. Smith") does, and a line
+/// break always ends a sentence
+#[wasm_bindgen]
+pub fn split_sentences(content: &str) -> Result<JsValue, ReconError> {
+    let normalized = normalize_content(content)?;
+    to_js(&segment::sentences(&normalized))
+}
+
+/// Split Markdown-style content into sections at its ATX headings
+///
+/// `content` is normalized as by `normalize_content` and the result is
+/// `[{ heading, level, content, hash }]` in document order. Text before the
+/// first heading is a section with `heading: null` and level 0. Lines in
+/// fenced code blocks and setext underlines are never headings. `content`
+/// leaves out the heading line and surrounding blank lines, and `hash` is
+/// its SHA-256 hex digest, so an edit in one section keeps the other hashes
+#[wasm_bindgen]
+pub fn split_sections(content: &str) -> Result<JsValue, ReconError> {
+    let normalized = normalize_content(content)?;
+    to_js(&section::sections(&normalized))
+}
+
+/// Just the `{ heading, hash }` of every `split_sections` section, in order
+///
+/// An array rather than an object, as headings can repeat
+#[wasm_bindgen]
+pub fn hash_sections(content: &str) -> Result<JsValue, ReconError> {
+    let normalized = normalize_content(content)?;
+    let hashes: Vec<SectionHash> = section::sections(&normalized)
+        .into_iter()
+        .map(|section| SectionHash { heading: section.heading, hash: section.hash })
+        .collect();
+    to_js(&hashes)
+}
+
+/// Element of the `hash_sections` result
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct SectionHash<'a> {
+    heading: Option<&'a str>,
+    hash: String,
+}
+
+// ============================================================================
+// Tabular data
+// ============================================================================
+
+/// Per-row hashes of a CSV or TSV table
+///
+/// Quoted fields may span lines and hold delimiters; cells are trimmed and
+/// line breaks inside quotes become LF before hashing. `options` is
+/// optional: `{ delimiter, quote, has_header }` (default ",", "\"", true).
+/// Returns `{ header_hash, rows: [{ line, hash }] }`, where `line` is the
+/// line each row starts on and `header_hash` is null without a header
+#[wasm_bindgen]
+pub fn hash_rows(content: &str, options: &JsValue) -> Result<JsValue, ReconError> {
+    limits::check_input(content.len())?;
+    let options: TableOptions = parse_options(options)?;
+    to_js(&table::hash_rows(content, &options)?)
+}
+
+/// Rows added, removed and changed between two versions of a table
+///
+/// Rows are paired by the values of the columns named in `key_columns`;
+/// a key that occurs twice in one table is an `invalid_input` error.
+/// `options` is as for `hash_rows` and needs a header. Returns
+/// `{ added, removed, changed: [{ key, columns }] }`, each key an array of
+/// the key column values and `columns` the names of the differing columns
+#[wasm_bindgen]
+pub fn diff_rows(old: &str, new: &str, key_columns: &JsValue, options: &JsValue) -> Result<JsValue, ReconError> {
+    limits::check_input(old.len())?;
+    limits::check_input(new.len())?;
+    let key_columns = string_array(key_columns, "diff_rows")?;
+    let options: TableOptions = parse_options(options)?;
+    to_js(&table::diff_rows(old, new, &key_columns, &options)?)
+}
+
+// ============================================================================
+// Limits
+// ============================================================================
+
+/// Reject single inputs over `max_input_bytes` and batches of more than
+/// `max_batch_items` elements with a `size_limit` error; 0 means unlimited
+///
+/// Defaults are 256 MiB and 100,000 items. A batch's elements together may
+/// not exceed `max_input_bytes` either; batch strings are measured in UTF-16
+/// code units so the check can run before anything is copied. Arrays of
+/// strings and `Uint8Array`s are counted again at their exact UTF-8 size as
+/// each element is converted.
+///
+/// Every export taking content applies them. Exempt are `StreamingHasher`,
+/// which exists for inputs too large to pass in one piece, the bundle
+/// objects of `reconforth_eval_bundle`, `validate_bundle` and
+/// `bundle_add_document`, and exports whose arguments are only digests,
+/// keys or saved state: `hashes_equal`, `multihash_to_hex`,
+/// `quick_digest_equal`, `fingerprint_distance`, `verify_merkle_proof`,
+/// `derive_tenant_key` and `HashChain.import_state`
+#[wasm_bindgen]
+pub fn set_limits(max_input_bytes: usize, max_batch_items: usize) {
+    limits::set(limits::Limits { max_input_bytes, max_batch_items });
+}
+
+/// The limits in force, as `{ max_input_bytes, max_batch_items }`
+#[wasm_bindgen]
+pub fn get_limits() -> Result<JsValue, ReconError> {
+    to_js(&limits::get())
+}
+
+// ============================================================================
+// Keyed hashing
+// ============================================================================
+
+/// HMAC-SHA256 of UTF-8 content under `key`, as lowercase hex
+#[wasm_bindgen]
+pub fn hmac_content(key: &[u8], content: &str) -> Result<String, ReconError> {
+    hmac_bytes(key, content.as_bytes())
+}
+
+/// HMAC-SHA256 of raw bytes under `key`, as lowercase hex
+#[wasm_bindgen]
+pub fn hmac_bytes(key: &[u8], data: &[u8]) -> Result<String, ReconError> {
+    limits::check_input(data.len())?;
+    Ok(keyed::hmac_bytes(key, data))
+}
+
+/// Constant-time check of an HMAC-SHA256 digest
+///
+/// `expected_hex` may be upper or lower case; malformed hex returns false
+#[wasm_bindgen]
+pub fn verify_hmac(key: &[u8], content: &str, expected_hex: &str) -> Result<bool, ReconError> {
+    limits::check_input(content.len())?;
+    Ok(keyed::verify_hmac_bytes(key, content.as_bytes(), expected_hex))
+}
+
+/// Keyed BLAKE3 of UTF-8 content, as lowercase hex
+///
+/// `key` must be 32 bytes, typically from `derive_tenant_key`, so digests of
+/// the same content under different tenants are unrelated
+#[wasm_bindgen]
+pub fn hash_content_keyed(key: &[u8], content: &str) -> Result<String, ReconError> {
+    limits::check_input(content.len())?;
+    keyed::blake3_keyed(key, content.as_bytes()).map_err(ReconError::invalid_input)
+}
+
+/// Derive a tenant's 32-byte hashing key from a 32-byte master key
+///
+/// BLAKE3 in key-derivation mode over `master || tenant_id`, under a fixed
+/// context string; the same inputs always give the same key
+#[wasm_bindgen]
+pub fn derive_tenant_key(master: &[u8], tenant_id: &str) -> Result<Vec<u8>, ReconError> {
+    keyed::derive_tenant_key(master, tenant_id)
+        .map(|key| key.to_vec())
+        .map_err(ReconError::invalid_input)
+}
+
+/// Batch HMAC-SHA256 under one key
+///
+/// Entries may be strings or `Uint8Array`s, as for `batch_hash`
+#[wasm_bindgen]
+pub fn batch_hmac(key: &[u8], documents: &JsValue) -> Result<JsValue, ReconError> {
+    let docs = js_array(documents, "batch_hmac")?;
+    let mut budget = BatchBudget::new(docs.length() as usize)?;
+    let macs = docs
+        .iter()
+        .enumerate()
+        .map(|(index, doc)| {
+            BatchEntry::from_js(&doc).within(index, &mut budget)?
+                .bytes()
+                .map(|bytes| keyed::hmac_bytes(key, bytes))
+                .map_err(|reason| entry_error("batch_hmac", index, &reason))
+        })
+        .collect::<Result<Vec<String>, ReconError>>()?;
+
+    to_js(&macs)
+}
+
+// ============================================================================
+// Snapshot manifests
+// ============================================================================
+
+/// Build a manifest from `[{ id, hash }]` or an object of id to hash
+///
+/// The output of `batch_hash_keyed` can be passed straight in. The result
+/// is canonical JSON with the ids sorted, a format `version` and an
+/// `integrity` digest over the rest, so equal inputs give identical text
+#[wasm_bindgen]
+pub fn build_manifest(entries: &JsValue) -> Result<String, ReconError> {
+    check_records(entries, &["id", "hash"])?;
+    let entries: ManifestEntries = from_js(entries).map_err(|_| {
+        ReconError::invalid_input("build_manifest expects an array of { id, hash } or an object of id to hash")
+    })?;
+
+    Ok(Manifest::from_entries(entries)?.encode())
+}
+
+/// Compare two manifests from `build_manifest`
+///
+/// Returns `{ added, removed, changed, unchanged_count }`: ids only in
+/// `remote`, ids only in `local`, and ids whose hash differs, each sorted.
+/// Both manifests are verified first; a digest that does not match fails
+/// with `integrity_mismatch` and a format version this build does not know
+/// with `unsupported_version`. Members added by newer writers are ignored
+#[wasm_bindgen]
+pub fn diff_manifests(local: &str, remote: &str) -> Result<JsValue, ReconError> {
+    limits::check_input(local.len())?;
+    limits::check_input(remote.len())?;
+    let local = Manifest::parse(local)?;
+    let remote = Manifest::parse(remote)?;
+    to_js(&local.diff(&remote))
+}
+
+// ============================================================================
+// Streaming hashing
+// ============================================================================
+
+fn finalized_error() -> ReconError {
+    ReconError::new(
+        ErrorCode::InvalidState,
+        "StreamingHasher has already been finalized; call reset() to reuse it",
+    )
+}
+
+/// Incremental SHA-256 hasher for documents too large to copy across the
+/// WASM boundary in one go
+///
+/// Feed chunks (e.g. from a `ReadableStream`) with `update`, then call
+/// `finalize` to get the same hex digest `hash_content` would produce for
+/// the concatenated input.
+#[wasm_bindgen]
+pub struct StreamingHasher {
+    /// `None` once the digest has been taken
+    hasher: Option<Sha256>,
+}
+
+#[wasm_bindgen]
+impl StreamingHasher {
+    /// Create a new hasher
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> StreamingHasher {
+        StreamingHasher {
+            hasher: Some(Sha256::new()),
+        }
+    }
+
+    /// Feed a chunk of bytes into the hasher
+    pub fn update(&mut self, chunk: &[u8]) -> Result<(), ReconError> {
+        let hasher = self.hasher.as_mut().ok_or_else(finalized_error)?;
+        hasher.update(chunk);
+        Ok(())
+    }
+
+    /// Finish hashing and return the lowercase hex digest
+    pub fn finalize(&mut self) -> Result<String, ReconError> {
+        let hasher = self.hasher.take().ok_or_else(finalized_error)?;
+        Ok(to_hex(&hasher.finalize()))
+    }
+
+    /// Reset the hasher so it can be reused for a new document
+    pub fn reset(&mut self) {
+        self.hasher = Some(Sha256::new());
+    }
+
+    /// Whether `finalize` has been called since the last reset
+    #[wasm_bindgen(getter)]
+    pub fn finalized(&self) -> bool {
+        self.hasher.is_none()
+    }
+}
+
+impl Default for StreamingHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================================
+// Redaction-aware hashing
+// ============================================================================
+
+/// SHA-256 hex digest of `content` with some ranges masked
+///
+/// `ranges` is an array of `{ start, end }` UTF-8 byte offsets (end
+/// exclusive), in any order. Each character inside a range is replaced by
+/// U+2588 before hashing, so the result equals `hash_content` of the masked
+/// text and redacted values of the same length hash alike. Out-of-bounds
+/// or overlapping ranges, and ranges splitting a multi-byte character, fail
+/// with `invalid_input` carrying the range's index
+#[wasm_bindgen]
+pub fn hash_with_redactions(content: &str, ranges: &JsValue) -> Result<String, ReconError> {
+    limits::check_input(content.len())?;
+    let ranges: Vec<Redaction> = from_js(ranges)?;
+    Ok(to_hex(&core::hash_content(&redact::redact(content, &ranges)?)))
+}
+
+/// Whether two documents are identical outside their redacted ranges
+///
+/// Ranges are as for `hash_with_redactions`
+#[wasm_bindgen]
+pub fn equal_except_redactions(a: &str, a_ranges: &JsValue, b: &str, b_ranges: &JsValue) -> Result<bool, ReconError> {
+    limits::check_input(a.len())?;
+    limits::check_input(b.len())?;
+    let a_ranges: Vec<Redaction> = from_js(a_ranges)?;
+    let b_ranges: Vec<Redaction> = from_js(b_ranges)?;
+    Ok(redact::redact(a, &a_ranges)? == redact::redact(b, &b_ranges)?)
+}
+
+// ============================================================================
+// Quick digests
+// ============================================================================
+
+/// Cheap digest for deciding whether a large document probably changed
+///
+/// Inputs up to 24 KiB are hashed in full. Larger ones are sampled: the
+/// length plus the first and last 4 KiB and 16 evenly spaced 1 KiB windows,
+/// so the cost does not grow with the input. Returns 34 hex characters, the
+/// first two naming the sampling scheme (currently "01")
+#[wasm_bindgen]
+pub fn quick_digest(data: &[u8]) -> Result<String, ReconError> {
+    limits::check_input(data.len())?;
+    Ok(quick::quick_digest(data))
+}
+
+/// Whether two `quick_digest` results match
+///
+/// False means the inputs differ. True is necessary but not sufficient for
+/// large inputs: an edit outside the sampled regions that keeps the length
+/// the same goes unseen, so confirm with a full hash before skipping work.
+/// Digests from different schemes never match; anything that is not a quick
+/// digest fails with `decode_error`
+#[wasm_bindgen]
+pub fn quick_digest_equal(a_digest: &str, b_digest: &str) -> Result<bool, ReconError> {
+    quick::quick_digest_equal(a_digest, b_digest).map_err(ReconError::decode)
+}
+
+// ============================================================================
+// Hash chains
+// ============================================================================
+
+/// Tamper-evident log: each head commits to the previous head and the entry
+///
+/// The first head is 32 zero bytes; appending an entry sets the head to
+/// `sha256(prev_head || sha256(entry))` over the raw digests, with entries
+/// hashed as UTF-8. Heads are returned as lowercase hex.
+#[wasm_bindgen]
+pub struct HashChain {
+    inner: chain::HashChain,
+}
+
+#[wasm_bindgen]
+impl HashChain {
+    /// Create an empty chain
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> HashChain {
+        HashChain { inner: chain::HashChain::new() }
+    }
+
+    /// Append an entry and return the new head
+    pub fn append(&mut self, entry: &str) -> Result<String, ReconError> {
+        limits::check_input(entry.len())?;
+        Ok(to_hex(&self.inner.append(entry.as_bytes())))
+    }
+
+    /// Current head; 64 zeros for an empty chain
+    pub fn head(&self) -> String {
+        to_hex(&self.inner.head())
+    }
+
+    /// Number of entries appended
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Whether nothing has been appended
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Saved state, `{"head":"<hex>","length":n}`, for `import_state`
+    pub fn export_state(&self) -> Result<String, ReconError> {
+        serde_json::to_string(&self.inner.state()).map_err(|e| ReconError::serialization(e.to_string()))
+    }
+
+    /// Resume a chain saved with `export_state`
+    pub fn import_state(state: &str) -> Result<HashChain, ReconError> {
+        let state: chain::ChainState =
+            serde_json::from_str(state).map_err(|e| ReconError::decode(format!("Invalid chain state: {}", e)))?;
+        let inner = chain::HashChain::from_state(&state).map_err(ReconError::decode)?;
+        Ok(HashChain { inner })
+    }
+
+    /// Whether replaying an array of entry strings from an empty chain ends
+    /// at `expected_head`
+    pub fn verify(entries: &JsValue, expected_head: &str) -> Result<bool, ReconError> {
+        let expected = digest_from_hex(expected_head)
+            .ok_or_else(|| ReconError::decode(format!("Invalid chain head: {:?}", expected_head)))?;
+        let entries = string_array(entries, "HashChain.verify")?;
+        Ok(chain::verify(&entries, &expected))
+    }
+}
+
+impl Default for HashChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================================
+// Incremental batch hashing
+// ============================================================================
+
+/// Options accepted by `BatchHasher`
+///
+/// Unknown fields are rejected, so the `batch_hash` options that have no
+/// meaning here (`include_recipe`, `progress_interval`) are not silently ignored
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct BatchHasherOptions {
+    algorithm: HashAlgorithm,
+    encoding: DigestEncoding,
+}
+
+/// Batch hasher fed one document at a time
+///
+/// Lets callers hash a large batch in slices and yield to the event loop
+/// between them: `push` documents, then `take_results` to collect the
+/// hashes so far (in push order) and start a new slice.
+#[wasm_bindgen]
+pub struct BatchHasher {
+    options: BatchHasherOptions,
+    results: Vec<String>,
+    /// Documents pushed so far, successfully or not; the index of the next one
+    pushed: usize,
+}
+
+#[wasm_bindgen]
+impl BatchHasher {
+    /// Create a hasher; `options` is `{ algorithm, encoding }` as for
+    /// `batch_hash`, and any other field fails with `invalid_options`
+    #[wasm_bindgen(constructor)]
+    pub fn new(options: &JsValue) -> Result<BatchHasher, ReconError> {
+        Ok(BatchHasher::with_options(parse_options(options)?))
+    }
+
+    /// Hash a string or `Uint8Array` and queue the result
+    pub fn push(&mut self, document: &JsValue) -> Result<(), ReconError> {
+        self.push_entry(&BatchEntry::from_js(document))
+    }
+
+    /// Return the queued hashes and clear the queue
+    pub fn take_results(&mut self) -> Result<JsValue, ReconError> {
+        to_js(&self.take())
+    }
+
+    /// Number of hashes waiting to be taken
+    #[wasm_bindgen(getter)]
+    pub fn pending(&self) -> usize {
+        self.results.len()
+    }
+}
+
+impl BatchHasher {
+    fn with_options(options: BatchHasherOptions) -> BatchHasher {
+        BatchHasher { options, results: Vec::new(), pushed: 0 }
+    }
+
+    fn push_entry(&mut self, entry: &BatchEntry) -> Result<(), ReconError> {
+        let index = self.pushed;
+        self.pushed += 1;
+        if let Ok(bytes) = entry.bytes() {
+            limits::check_input(bytes.len()).map_err(|e| e.at(index))?;
+        }
+        let hash = entry
+            .hash(self.options.algorithm, self.options.encoding)
+            .map_err(|reason| entry_error("BatchHasher", index, &reason))?;
+        self.results.push(hash);
+        Ok(())
+    }
+
+    fn take(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.results)
+    }
+}
+
+// ============================================================================
+// Batch contexts
+// ============================================================================
+
+/// Options accepted by the `BatchContext` constructor: the normalization
+/// options for `normalize_batch` plus `algorithm` and `encoding` for
+/// `hash_batch`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct BatchContextOptions {
+    #[serde(flatten)]
+    normalize: NormalizeOptions,
+    algorithm: HashAlgorithm,
+    encoding: DigestEncoding,
+}
+
+/// Batch hashing and normalization in buffers kept between calls
+///
+/// Create one context per worker and call it for every batch: results are
+/// written into storage reused from the previous call, so linear memory
+/// stops growing once the buffers fit the largest batch. Call `shrink`
+/// after an unusually large batch to hand the space back to the allocator.
+#[wasm_bindgen]
+pub struct BatchContext {
+    options: BatchContextOptions,
+    buffers: BatchBuffers,
+}
+
+#[wasm_bindgen]
+impl BatchContext {
+    /// Create a context; `options` is optional, see `BatchContextOptions`
+    #[wasm_bindgen(constructor)]
+    pub fn new(options: &JsValue) -> Result<BatchContext, ReconError> {
+        Ok(BatchContext { options: parse_options(options)?, buffers: BatchBuffers::new() })
+    }
+
+    /// Hash an array of strings and `Uint8Array`s, as `batch_hash` does
+    pub fn hash_batch(&mut self, documents: &JsValue) -> Result<js_sys::Array, ReconError> {
+        let docs = js_array(documents, "BatchContext.hash_batch")?;
+        let BatchContextOptions { algorithm, encoding, .. } = self.options;
+        self.buffers.clear();
+        for (index, doc) in docs.iter().enumerate() {
+            if let Some(bytes) = doc.dyn_ref::<js_sys::Uint8Array>() {
+                let len = bytes.byte_length() as usize;
+                limits::check_input(len).map_err(|e| e.at(index))?;
+                self.buffers.push_hash_with(len, |input| bytes.copy_to(input), algorithm, encoding);
+            } else if let Some(text) = doc.as_string() {
+                limits::check_input(text.len()).map_err(|e| e.at(index))?;
+                self.buffers.push_hash(text.as_bytes(), algorithm, encoding);
+            } else {
+                let reason = BatchEntry::from_js(&doc).bytes().err().unwrap_or_default();
+                return Err(entry_error("BatchContext.hash_batch", index, &reason));
+            }
+        }
+        Ok(self.results())
+    }
+
+    /// Normalize an array of strings, as `batch_normalize` does
+    pub fn normalize_batch(&mut self, documents: &JsValue) -> Result<js_sys::Array, ReconError> {
+        let docs = js_array(documents, "BatchContext.normalize_batch")?;
+        self.buffers.clear();
+        for (index, doc) in docs.iter().enumerate() {
+            let text = doc.as_string().ok_or_else(|| {
+                ReconError::invalid_input("BatchContext.normalize_batch entries must be strings").at(index)
+            })?;
+            limits::check_input(text.len()).map_err(|e| e.at(index))?;
+            self.buffers.push_normalized(&text, &self.options.normalize);
+        }
+        Ok(self.results())
+    }
+
+    /// Release the buffers, for after an unusually large batch
+    pub fn shrink(&mut self) {
+        self.buffers.shrink();
+    }
+
+    /// `memory_stats` plus this context's buffer capacities as `buffers`:
+    /// `{ text_bytes, results, input_bytes }`
+    pub fn memory_stats(&self) -> Result<JsValue, ReconError> {
+        to_js(&MemoryStats::current(Some(self.buffers.stats())))
+    }
+}
+
+impl BatchContext {
+    /// The buffered results as a JS array, copied straight from the buffers
+    fn results(&self) -> js_sys::Array {
+        self.buffers.iter().map(JsValue::from_str).collect()
+    }
+}
+
+/// Size of WASM linear memory: `{ pages, bytes }`, pages being 64 KiB
+///
+/// Linear memory only grows, so sampling this between batches shows
+/// whether a worker's footprint has settled. Both are 0 outside WebAssembly
+#[wasm_bindgen]
+pub fn memory_stats() -> Result<JsValue, ReconError> {
+    to_js(&MemoryStats::current(None))
+}
+
+/// Result of `memory_stats`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+struct MemoryStats {
+    pages: usize,
+    bytes: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    buffers: Option<BufferStats>,
+}
+
+impl MemoryStats {
+    fn current(buffers: Option<BufferStats>) -> MemoryStats {
+        #[cfg(target_arch = "wasm32")]
+        let pages = ::core::arch::wasm32::memory_size::<0>();
+        #[cfg(not(target_arch = "wasm32"))]
+        let pages = 0;
+        MemoryStats { pages, bytes: pages * 65536, buffers }
+    }
+}
+
+// ============================================================================
+// Merkle trees
+// ============================================================================
+
+/// Merkle tree over fixed-size chunks of a document
+///
+/// Leaves are `SHA-256(0x00 || SHA-256(chunk))`, interior nodes
+/// `SHA-256(0x01 || left || right)`, shaped as in RFC 9162 so odd chunk
+/// counts are handled deterministically. The chunk hash a peer verifies is
+/// simply `hash_bytes(chunk)`.
+#[wasm_bindgen]
+pub struct MerkleTree {
+    tree: merkle::MerkleTree,
+}
+
+#[wasm_bindgen]
+impl MerkleTree {
+    /// Build a tree over `chunk_size`-byte chunks of the UTF-8 content
+    pub fn from_content(content: &str, chunk_size: usize) -> Result<MerkleTree, ReconError> {
+        MerkleTree::from_bytes(content.as_bytes(), chunk_size)
+    }
+
+    /// Build a tree over `chunk_size`-byte chunks of raw bytes
+    pub fn from_bytes(data: &[u8], chunk_size: usize) -> Result<MerkleTree, ReconError> {
+        limits::check_input(data.len())?;
+        let tree = merkle::MerkleTree::from_bytes(data, chunk_size).map_err(ReconError::invalid_input)?;
+        Ok(MerkleTree { tree })
+    }
+
+    /// Root hash as lowercase hex
+    pub fn root(&self) -> String {
+        to_hex(&self.tree.root())
+    }
+
+    /// Number of chunks
+    #[wasm_bindgen(getter)]
+    pub fn chunk_count(&self) -> usize {
+        self.tree.leaf_count()
+    }
+
+    /// Membership proof `{ leaf_count, path: [hex...] }` for a chunk
+    pub fn proof(&self, chunk_index: usize) -> Result<JsValue, ReconError> {
+        let proof = self.tree.proof(chunk_index).ok_or_else(|| {
+            ReconError::invalid_input(format!(
+                "Chunk index {} out of range for {} chunks",
+                chunk_index,
+                self.tree.leaf_count()
+            ))
+        })?;
+        to_js(&proof)
+    }
+}
+
+/// Verify a proof from `MerkleTree.proof` against a root
+///
+/// `chunk_hash` is the SHA-256 hex of the chunk. Returns false for any
+/// malformed input rather than throwing.
+#[wasm_bindgen]
+pub fn verify_merkle_proof(root: &str, chunk_hash: &str, proof: &JsValue, index: usize) -> bool {
+    let Ok(proof) = serde_wasm_bindgen::from_value::<merkle::MerkleProof>(proof.clone()) else {
+        return false;
+    };
+    let (Some(root), Some(chunk_hash)) = (digest_from_hex(root), digest_from_hex(chunk_hash)) else {
+        return false;
+    };
+    let Some(path) = proof.path.iter().map(|h| digest_from_hex(h)).collect::<Option<Vec<_>>>() else {
+        return false;
+    };
+
+    merkle::verify(&root, &chunk_hash, &path, index, proof.leaf_count)
+}
+
+/// Content-defined chunking (FastCDC)
+///
+/// Returns `[{ offset, length, hash }]` where `hash` is the SHA-256 hex of
+/// the chunk. Requires `0 < min <= avg <= max`; input shorter than `min` is
+/// one chunk and empty input gives an empty array
+#[wasm_bindgen]
+pub fn chunk_content(data: &[u8], min: usize, avg: usize, max: usize) -> Result<JsValue, ReconError> {
+    limits::check_input(data.len())?;
+    let chunks = cdc::chunk(data, min, avg, max).map_err(ReconError::invalid_input)?;
+
+    to_js(&chunks)
+}
+
+/// Single root digest over a set of named documents
+///
+/// `entries` is an array of `{ path, content }`. The root does not depend on
+/// input order; duplicate paths are an error. Scheme, with 8-byte
+/// big-endian lengths: `entry = sha256(len(path) || path || sha256(content))`,
+/// `root = sha256(count || entries sorted by path bytes)`
+#[wasm_bindgen]
+pub fn hash_tree(entries: &JsValue) -> Result<String, ReconError> {
+    check_records(entries, &["path", "content"])?;
+    let entries: Vec<tree::TreeEntry> = from_js(entries)?;
+    let root = tree::hash_tree(&entries).map_err(ReconError::invalid_input)?;
+    Ok(to_hex(&root))
+}
+
+// ============================================================================
+// Rolling hashes
+// ============================================================================
+
+/// Rabin-Karp rolling hash over a fixed-size byte window
+#[wasm_bindgen]
+pub struct RollingHash {
+    inner: rolling::RollingHash,
+}
+
+#[wasm_bindgen]
+impl RollingHash {
+    /// Create a rolling hash over `window_size` bytes
+    #[wasm_bindgen(constructor)]
+    pub fn new(window_size: usize) -> Result<RollingHash, ReconError> {
+        let inner = rolling::RollingHash::new(window_size).map_err(ReconError::invalid_input)?;
+        Ok(RollingHash { inner })
+    }
+
+    /// Push a byte and return the hash of the current window
+    pub fn push_byte(&mut self, byte: u8) -> u64 {
+        self.inner.push_byte(byte)
+    }
+
+    /// Whether a full window of bytes has been pushed
+    #[wasm_bindgen(getter)]
+    pub fn full(&self) -> bool {
+        self.inner.is_full()
+    }
+
+    /// Clear the window
+    pub fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+/// Find every (possibly overlapping) occurrence of `needle` in `haystack`
+///
+/// Returns `[{ byte_offset, char_offset }]`. Candidates come from a rolling
+/// hash over the first `window_size` bytes of the needle (default: the whole
+/// needle) and are confirmed by exact comparison, so there are no false
+/// positives. A needle shorter than the window is an error
+#[wasm_bindgen]
+pub fn find_duplicate_windows(haystack: &str, needle: &str, window_size: Option<usize>) -> Result<JsValue, ReconError> {
+    limits::check_input(haystack.len())?;
+    let window = window_size.unwrap_or(needle.len());
+    let matches = rolling::find_matches(haystack, needle, window).map_err(ReconError::invalid_input)?;
+
+    to_js(&matches)
+}
+
+// ============================================================================
+// Diffing
+// ============================================================================
+
+/// Line-level diff between two documents
+///
+/// Returns an array of hunks
+/// `{ op: "equal" | "insert" | "delete", old_start, old_lines, new_start, new_lines, lines }`
+/// with 0-based line indices. Each entry in `lines` keeps its terminator, so
+/// joining the equal and insert hunks reproduces `new` exactly.
+/// `options` is optional: `{ normalize_line_endings: bool, granularity: "line" | "word" | "char" }`,
+/// default true and "line". With word or char granularity the hunks have
+/// the same shape but count and list word or grapheme-cluster tokens
+#[wasm_bindgen]
+pub fn diff_lines(old: &str, new: &str, options: &JsValue) -> Result<JsValue, ReconError> {
+    let options: DiffOptions = parse_options(options)?;
+    diff_at(old, new, &options)
+}
+
+/// Word-level diff, same hunk shape as `diff_lines`
+/// Tokens follow UAX #29 word boundaries; whitespace runs are tokens too
+#[wasm_bindgen]
+pub fn diff_words(old: &str, new: &str) -> Result<JsValue, ReconError> {
+    diff_at(old, new, &DiffOptions { granularity: Granularity::Word, ..DiffOptions::default() })
+}
+
+/// Grapheme-cluster diff, same hunk shape as `diff_lines`
+/// Emoji sequences and combining marks are never split
+#[wasm_bindgen]
+pub fn diff_chars(old: &str, new: &str) -> Result<JsValue, ReconError> {
+    diff_at(old, new, &DiffOptions { granularity: Granularity::Char, ..DiffOptions::default() })
+}
+
+fn diff_at(old: &str, new: &str, options: &DiffOptions) -> Result<JsValue, ReconError> {
+    limits::check_input(old.len() + new.len())?;
+    let hunks = diff::diff_text(old, new, options);
+
+    to_js(&hunks)
+}
+
+/// Unified diff (`diff -u` format) turning `old` into `new`
+///
+/// `context` is the number of unchanged lines around each change, default 3.
+/// Identical documents give an empty patch
+#[wasm_bindgen]
+pub fn create_patch(old: &str, new: &str, context: Option<usize>) -> Result<String, ReconError> {
+    limits::check_input(old.len() + new.len())?;
+    Ok(patch::create_patch(old, new, context.unwrap_or(patch::DEFAULT_CONTEXT)))
+}
+
+/// Apply a unified diff to `old`
+///
+/// Fails with `decode_error` if the patch text is malformed, and with
+/// `patch_rejected`, naming the hunk and line, if a context or removed line
+/// does not match `old` exactly
+#[wasm_bindgen]
+pub fn apply_patch(old: &str, patch: &str) -> Result<String, ReconError> {
+    limits::check_input(old.len() + patch.len())?;
+    patch::Patch::parse(patch)
+        .map_err(ReconError::decode)?
+        .apply(old)
+        .map_err(|e| ReconError::new(ErrorCode::PatchRejected, e))
+}
+
+/// Binary delta turning `old` into `new`, as a `Uint8Array`
+///
+/// For content that is not valid UTF-8 (icons, serialized blobs). The patch
+/// is a compact list of copies from `old` and literal inserts, with the
+/// SHA-256 of `new` embedded: identical inputs give about 45 bytes and a
+/// one-byte edit of a large blob adds a few more
+#[wasm_bindgen]
+pub fn diff_bytes(old: &[u8], new: &[u8]) -> Result<Vec<u8>, ReconError> {
+    limits::check_input(old.len() + new.len())?;
+    Ok(delta::diff(old, new))
+}
+
+/// Rebuild `new` from `old` and a `diff_bytes` patch
+///
+/// Fails with `decode_error` for a malformed patch, `patch_rejected` when
+/// `old` is not the document the patch was made from, and
+/// `integrity_mismatch` when the result does not match the embedded SHA-256,
+/// so corruption is never silently applied
+#[wasm_bindgen]
+pub fn apply_bytes_patch(old: &[u8], patch: &[u8]) -> Result<Vec<u8>, ReconError> {
+    limits::check_input(old.len() + patch.len())?;
+    delta::apply(old, patch)
+}
+
+/// Three-way merge of two edits of the same base document
+///
+/// Returns `{ merged, conflicts }`; each conflict is
+/// `{ base_range, ours_range, theirs_range, base_lines, ours_lines, theirs_lines }`
+/// with 0-based `{ start, end }` line ranges. Without markers, conflicting
+/// regions keep the base text in `merged`.
+/// `options` is optional: `{ mark_conflicts: bool }`, default false
+#[wasm_bindgen]
+pub fn merge_three_way(base: &str, ours: &str, theirs: &str, options: &JsValue) -> Result<JsValue, ReconError> {
+    limits::check_input(base.len() + ours.len() + theirs.len())?;
+    let options: MergeOptions = parse_options(options)?;
+    let result = merge::merge_three_way(base, ours, theirs, &options);
+
+    to_js(&result)
+}
+
+// ============================================================================
+// Similarity
+// ============================================================================
+
+/// Similarity score in [0, 1] between two documents
+///
+/// Jaccard index over 3-word shingles of the normalized content: symmetric,
+/// 1.0 for identical documents, 0.0 for documents sharing no shingles
+#[wasm_bindgen]
+pub fn similarity(a: &str, b: &str) -> Result<f64, ReconError> {
+    limits::check_input(a.len() + b.len())?;
+    Ok(similarity::similarity(a, b))
+}
+
+/// Fraction of `needle`'s word shingles found in `haystack`, in [0, 1]
+///
+/// Asymmetric, unlike `similarity`: an excerpt scores 1.0 against the
+/// document it came from however long that is, while the document scores
+/// low against the excerpt. Shingles are `k` words (default 5) of the
+/// normalized content; a needle shorter than `k` words uses shingles of its
+/// own length
+#[wasm_bindgen]
+pub fn containment(needle: &str, haystack: &str, k: Option<usize>) -> Result<f64, ReconError> {
+    limits::check_input(needle.len() + haystack.len())?;
+    let k = k.unwrap_or(similarity::DEFAULT_CONTAINMENT_SHINGLE_SIZE);
+    Ok(similarity::containment(needle, haystack, k))
+}
+
+/// `containment` of one needle in each of an array of haystacks
+///
+/// The needle is normalized and shingled once; returns the scores in order
+#[wasm_bindgen]
+pub fn batch_containment(needle: &str, haystacks: &JsValue, k: Option<usize>) -> Result<JsValue, ReconError> {
+    limits::check_input(needle.len())?;
+    let haystacks = string_array(haystacks, "batch_containment")?;
+    let k = k.unwrap_or(similarity::DEFAULT_CONTAINMENT_SHINGLE_SIZE);
+
+    to_js(&similarity::containment_many(needle, &haystacks, k))
+}
+
+/// Levenshtein distance between two strings in grapheme clusters
+///
+/// A flag, a ZWJ emoji sequence or a letter with combining accents counts
+/// as one character. Clusters compare as written, so use `normalize_unicode`
+/// with "nfc" first if precomposed and decomposed accents should match
+#[wasm_bindgen]
+pub fn edit_distance(a: &str, b: &str) -> Result<u32, ReconError> {
+    limits::check_input(a.len())?;
+    limits::check_input(b.len())?;
+    Ok(distance::edit_distance(a, b) as u32)
+}
+
+/// `edit_distance` if it is at most `max`, otherwise -1
+///
+/// Only a band of the DP matrix around the diagonal is computed, and
+/// strings whose lengths differ by more than `max` return at once, so a
+/// title can be checked against a long body cheaply
+#[wasm_bindgen]
+pub fn edit_distance_within(a: &str, b: &str, max: u32) -> Result<i32, ReconError> {
+    limits::check_input(a.len())?;
+    limits::check_input(b.len())?;
+    Ok(match distance::edit_distance_within(a, b, max as usize) {
+        Some(distance) => distance as i32,
+        None => -1,
+    })
+}
+
+/// The string in `haystack` closest to `needle` by `edit_distance`
+///
+/// Returns `{ index, distance }` for the nearest candidate within `max`,
+/// the lowest index among equally near ones, or `null` if none is within
+/// `max`
+#[wasm_bindgen]
+pub fn closest_match(needle: &str, haystack: &JsValue, max: u32) -> Result<JsValue, ReconError> {
+    limits::check_input(needle.len())?;
+    let candidates = string_array(haystack, "closest_match")?;
+    let best = distance::closest_match(needle, candidates.iter().map(String::as_str), max as usize);
+    to_js(&best)
+}
+
+/// Match the paragraphs of two versions of a document
+///
+/// Paragraphs are those of `split_paragraphs`. Identical paragraphs match
+/// first with score 1.0; the rest are paired greedily by `similarity`, best
+/// first, so moved and lightly edited paragraphs are found. `options` is
+/// optional: `{ min_score }` (default 0.5). Returns
+/// `{ matches: [{ a_index, b_index, score }], unmatched_a, unmatched_b }`
+#[wasm_bindgen]
+pub fn align_blocks(a: &str, b: &str, options: &JsValue) -> Result<JsValue, ReconError> {
+    limits::check_input(a.len())?;
+    limits::check_input(b.len())?;
+    let options: AlignOptions = parse_options(options)?;
+    let alignment = align::align(a, b, &options).map_err(ReconError::invalid_options)?;
+    to_js(&alignment)
+}
+
+/// Locate the parts of `derived` copied verbatim from `source`
+///
+/// Both documents are normalized as by `normalize_content`. Returns
+/// `{ spans: [{ source_start, derived_start, length }], coverage }`, byte
+/// offsets into the normalized texts: runs of at least `min_match_len`
+/// bytes, tiled greedily from the start of `derived` so that they never
+/// overlap there, each pointing at its earliest occurrence in `source`.
+/// `coverage` is the fraction of normalized derived bytes inside a span.
+/// Runs in time linear in the input size
+#[wasm_bindgen]
+pub fn provenance(source: &str, derived: &str, min_match_len: usize) -> Result<JsValue, ReconError> {
+    let source = normalize_content(source)?;
+    let derived = normalize_content(derived)?;
+    let result = provenance::provenance(&source, &derived, min_match_len).map_err(ReconError::invalid_input)?;
+    to_js(&result)
+}
+
+/// 64-bit SimHash fingerprint of a document, as 16 hex characters
+///
+/// Computed over 3-word shingles of the normalized content, so whitespace
+/// noise does not change it; near-duplicates differ in only a few bits
+#[wasm_bindgen]
+pub fn fingerprint(content: &str) -> Result<String, ReconError> {
+    limits::check_input(content.len())?;
+    Ok(fingerprint::to_hex(fingerprint::simhash(content)))
+}
+
+/// Hamming distance between two fingerprints returned by `fingerprint`
+#[wasm_bindgen]
+pub fn fingerprint_distance(a: &str, b: &str) -> Result<u32, ReconError> {
+    let parse = |hex: &str| {
+        fingerprint::from_hex(hex)
+            .ok_or_else(|| ReconError::decode(format!("Invalid fingerprint: {:?}", hex)))
+    };
+    Ok(fingerprint::hamming_distance(parse(a)?, parse(b)?))
+}
+
+/// Fingerprint multiple documents in one call
+#[wasm_bindgen]
+pub fn batch_fingerprint(documents: &JsValue) -> Result<JsValue, ReconError> {
+    let docs = string_array(documents, "batch_fingerprint")?;
+
+    to_js(&core::batch_fingerprint(&docs))
+}
+
+/// MinHash signature of a document as a `Uint32Array` of `num_hashes` values
+///
+/// Computed over 3-word shingles of the normalized content with a fixed
+/// seed set, so signatures can be stored and compared across releases. A
+/// signature is a prefix of any longer one of the same document
+#[wasm_bindgen]
+pub fn minhash_signature(content: &str, num_hashes: usize) -> Result<Vec<u32>, ReconError> {
+    limits::check_input(content.len())?;
+    minhash::signature(content, num_hashes).map_err(ReconError::invalid_input)
+}
+
+/// Estimated Jaccard index of the documents behind two signatures
+///
+/// The fraction of positions at which they agree; the signatures must have
+/// the same length
+#[wasm_bindgen]
+pub fn estimate_jaccard(sig_a: &[u32], sig_b: &[u32]) -> Result<f64, ReconError> {
+    minhash::estimate_jaccard(sig_a, sig_b).map_err(ReconError::invalid_input)
+}
+
+/// Candidate near-duplicates among an array of `minhash_signature`s
+///
+/// Each signature is cut into `bands` bands of `rows` values, and
+/// `bands * rows` must equal the signature length. Returns the groups of
+/// indices whose values agree on every row of some band, each in ascending
+/// order and reported once. Documents with Jaccard index s share a bucket
+/// with probability 1 - (1 - s^rows)^bands
+#[wasm_bindgen]
+pub fn lsh_buckets(signatures: &JsValue, bands: usize, rows: usize) -> Result<JsValue, ReconError> {
+    js_array(signatures, "lsh_buckets")?;
+    let signatures: Vec<Vec<u32>> = from_js(signatures)?;
+    let groups = minhash::buckets(&signatures, bands, rows).map_err(ReconError::invalid_input)?;
+    to_js(&groups)
+}
+
+// ============================================================================
+// ReconForth WASM bindings
+// ============================================================================
+
+/// Evaluate a ReconForth program
+///
+/// # Arguments
+/// * `program` - ReconForth source code
+///
+/// # Returns
+/// * JSON-encoded result or error
+#[wasm_bindgen]
+pub fn reconforth_eval(program: &str) -> Result<JsValue, ReconError> {
+    limits::check_input(program.len())?;
+    let mut vm = VM::new();
+
+    vm.eval(program)
+        .map_err(|e| ReconError::new(ErrorCode::EvalError, e.to_string()))?;
+
+    // Return validation results
+    let validation = vm.get_validation();
+    to_js(validation)
+}
+
+/// Evaluate a ReconForth program with a bundle
+///
+/// # Arguments
+/// * `program` - ReconForth source code
+/// * `bundle` - JSON-encoded bundle of documents
+///
+/// # Returns
+/// * JSON-encoded validation result
+#[wasm_bindgen]
+pub fn reconforth_eval_bundle(program: &str, bundle: &JsValue) -> Result<JsValue, ReconError> {
+    limits::check_input(program.len())?;
+    let mut vm = VM::new();
+
+    // Parse bundle from JS
+    let bundle: Bundle = from_js(bundle)?;
+
+    // Load bundle into VM
+    vm.load_bundle(bundle);
+
+    // Execute program
+    vm.eval(program)
+        .map_err(|e| ReconError::new(ErrorCode::EvalError, e.to_string()))?;
+
+    // Return validation results
+    let validation = vm.get_validation();
+    to_js(validation)
+}
+
+/// Validate a bundle against a pack specification
+///
+/// # Arguments
+/// * `bundle` - JSON-encoded bundle of documents
+/// * `pack_spec` - ReconForth pack specification code
+///
+/// # Returns
+/// * JSON-encoded validation result
+#[wasm_bindgen]
+pub fn validate_bundle(bundle: &JsValue, pack_spec: &str) -> Result<JsValue, ReconError> {
+    limits::check_input(pack_spec.len())?;
+    let mut vm = VM::new();
+
+    // Parse bundle from JS
+    let bundle: Bundle = from_js(bundle)?;
+
+    // Load bundle
+    vm.load_bundle(bundle);
+
+    // Execute pack spec (should define a pack and validate)
+    vm.eval(pack_spec)
+        .map_err(|e| ReconError::new(ErrorCode::EvalError, e.to_string()))?;
+
+    // Return validation results
+    let validation = vm.get_validation();
+    to_js(validation)
+}
+
+/// Create a document from content and metadata
+///
+/// # Arguments
+/// * `content` - Document content
+/// * `path` - File path
+/// * `doc_type` - Document type (README, LICENSE, etc.)
+///
+/// # Returns
+/// * JSON-encoded document
+#[wasm_bindgen]
+pub fn create_document(content: &str, path: &str, doc_type: &str) -> Result<JsValue, ReconError> {
+    let hash = hash_content(content)?;
+
+    let doc = Document {
+        hash,
+        content: content.to_string(),
+        metadata: reconforth::DocumentMetadata {
+            path: path.to_string(),
+            document_type: doc_type.to_string(),
+            last_modified: js_sys::Date::now(),
+            version: None,
+            canonical_source: "Inferred".to_string(),
+            repository: String::new(),
+            branch: String::new(),
+        },
+        created_at: js_sys::Date::now(),
+    };
+
+    to_js(&doc)
+}
+
+/// Create an empty bundle
+#[wasm_bindgen]
+pub fn create_bundle() -> Result<JsValue, ReconError> {
+    let bundle = Bundle::new();
+    to_js(&bundle)
+}
+
+/// Add a document to a bundle
+///
+/// # Arguments
+/// * `bundle` - JSON-encoded bundle
+/// * `doc` - JSON-encoded document
+///
+/// # Returns
+/// * JSON-encoded updated bundle
+#[wasm_bindgen]
+pub fn bundle_add_document(bundle: &JsValue, doc: &JsValue) -> Result<JsValue, ReconError> {
+    let mut bundle: Bundle = from_js(bundle)?;
+
+    let doc: Document = from_js(doc)?;
+
+    bundle.add(doc);
+
+    to_js(&bundle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::blake3_vector_input;
+
+    #[test]
+    fn test_hash_content() {
+        let content = "Hello, World!";
+        let hash = hash_content(content).unwrap();
+        assert_eq!(hash.len(), 64); // SHA-256 = 64 hex chars
+    }
+
+    #[test]
+    fn test_hash_bytes_empty() {
+        assert_eq!(
+            hash_bytes(&[]).unwrap(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn test_hash_bytes_interior_nuls() {
+        assert_eq!(
+            hash_bytes(b"a\0b\0\0c").unwrap(),
+            "66a2256f272ef4528aa5f2685496a4412d81f1560ad6b1a16bec1560cf23c85d"
+        );
+    }
+
+    #[test]
+    fn test_hash_bytes_multi_megabyte() {
+        let million_a = vec![b'a'; 1_000_000];
+        assert_eq!(
+            hash_bytes(&million_a).unwrap(),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+
+        let pattern: Vec<u8> = (0..4 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        assert_eq!(
+            hash_bytes(&pattern).unwrap(),
+            "a117210941a0b00dcb2d8577e680d84b6fa0eaf760d2afc654c953b9859d54fa"
+        );
+    }
+
+    #[test]
+    fn test_hash_bytes_matches_hash_content() {
+        let content = "Hello, World!";
+        assert_eq!(hash_bytes(content.as_bytes()).unwrap(), hash_content(content).unwrap());
+        assert_eq!(
+            hash_content(content).unwrap(),
+            "dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f"
+        );
+    }
+
+    #[test]
+    fn test_normalize_content() {
+        let content = "  Hello  \r\n\r\n\r\nWorld  ";
+        let normalized = normalize_content(content).unwrap();
+        assert_eq!(normalized, "Hello\n\nWorld");
+    }
+
+    /// Official BLAKE3 test vector input: bytes cycling through 0..251
+    #[test]
+    fn test_blake3_official_vectors() {
+        let vectors = [
+            (0, "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"),
+            (1, "2d3adedff11b61f14c886e35afa036736dcd87a74d27b5c1510225d0f592e213"),
+            (1023, "10108970eeda3eb932baac1428c7a2163b0e924c9a9e25b35bba72b28f70bd11"),
+            (1024, "42214739f095a406f3fc83deb889744ac00df831c10daa55189b5d121c855af7"),
+            (1025, "d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444"),
+            (2048, "e776b6028c7cd22a4d0ba182a8bf62205d2ef576467e838ed6f2529b85fba24a"),
+            (31744, "62b6960e1a44bcc1eb1a611a8d6235b6b4b78f32e7abc4fb4c6cdcce94895c47"),
+            (102400, "bc3e3d41a1146b069abffad3c0d44860cf664390afce4d9661f7902e7943e085"),
+        ];
+        for (len, expected) in vectors {
+            assert_eq!(HashAlgorithm::Blake3.hash(&blake3_vector_input(len)), expected, "len {}", len);
+        }
+    }
+
+    #[test]
+    fn test_hash_content_blake3() {
+        let hash = hash_content_blake3("abc").unwrap();
+        assert_eq!(hash, "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85");
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, hash.to_lowercase());
+        assert_ne!(hash, hash_content("abc").unwrap());
+    }
+
+    #[test]
+    fn test_hash_algorithm_default_is_sha256() {
+        assert_eq!(HashAlgorithm::default(), HashAlgorithm::Sha256);
+        assert_eq!(HashAlgorithm::default().hash(b"abc"), hash_content("abc").unwrap());
+    }
+
+    #[test]
+    fn test_hash_entries_mixed_batch() {
+        let entries = vec![
+            BatchEntry::Text("abc".to_string()),
+            BatchEntry::Invalid("entry is null".to_string()),
+            BatchEntry::Bytes(b"abc".to_vec()),
+            BatchEntry::Invalid("entry is undefined".to_string()),
+            BatchEntry::Invalid("expected string or Uint8Array, got number".to_string()),
+        ];
+        let results = hash_entries(entries.into_iter(), HashAlgorithm::Sha256, DigestEncoding::Hex);
+
+        assert_eq!(results.len(), 5);
+        for (i, result) in results.iter().enumerate() {
+            assert_eq!(result.index, i);
+        }
+        assert_eq!(results[0].hash.as_deref(), Some(hash_content("abc").unwrap().as_str()));
+        assert_eq!(results[0].error, None);
+        assert_eq!(results[1].hash, None);
+        assert_eq!(results[1].error.as_deref(), Some("entry is null"));
+        assert_eq!(results[2].hash, results[0].hash);
+        assert_eq!(results[3].error.as_deref(), Some("entry is undefined"));
+        assert!(results[4].error.as_deref().unwrap().contains("number"));
+    }
+
+    #[test]
+    fn test_hash_entries_all_valid_and_empty() {
+        assert!(hash_entries(std::iter::empty(), HashAlgorithm::Sha256, DigestEncoding::Hex).is_empty());
+
+        let entries = vec![BatchEntry::Text("a".to_string()), BatchEntry::Text("b".to_string())];
+        let results = hash_entries(entries.into_iter(), HashAlgorithm::Blake3, DigestEncoding::Hex);
+        assert!(results.iter().all(|r| r.error.is_none()));
+        assert_eq!(results[1].hash.as_deref(), Some(hash_content_blake3("b").unwrap().as_str()));
+    }
+
+    #[test]
+    fn test_verify_content() {
+        let hash = hash_content("payload").unwrap();
+        assert!(verify_content("payload", &hash).unwrap());
+        assert!(verify_content("payload", &hash.to_uppercase()).unwrap());
+        assert!(!verify_content("payload!", &hash).unwrap());
+        assert!(!verify_content("payload", &format!("  {}  ", hash)).unwrap());
+        assert!(!verify_content("payload", &hash[..56]).unwrap());
+        assert!(!verify_content("payload", &format!("{}00", hash)).unwrap());
+        assert!(!verify_content("payload", "not a digest").unwrap());
+        assert!(!verify_content("", "").unwrap());
+    }
+
+    #[test]
+    fn test_hashes_equal() {
+        let hash = hash_content("abc").unwrap();
+        assert!(hashes_equal(&hash, &hash.to_uppercase()));
+        assert!(!hashes_equal(&hash, &hash_content("abd").unwrap()));
+        assert!(!hashes_equal(&hash, &format!("{}\t", hash)));
+        assert!(!hashes_equal(&hash[..60], &hash[..60]));
+    }
+
+    #[test]
+    fn test_hash_content_encodings() {
+        assert_eq!(hash_content_encoded("abc", "hex").unwrap(), hash_content("abc").unwrap());
+        assert_eq!(hash_content_encoded("abc", "base64").unwrap(), "ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=");
+        assert_eq!(hash_content_encoded("abc", "base64url").unwrap(), "ungWv48Bz-pBQUDeXa4iI7ADYaOWF3qctBD_YfIAFa0");
+        assert_eq!(to_hex(&hash_content_raw("abc").unwrap()), hash_content("abc").unwrap());
+        assert_eq!(hash_content_raw("").unwrap().len(), 32);
+    }
+
+    #[test]
+    fn test_hash_json_canonical_equal_documents() {
+        let a = hash_json_canonical(r#"{"b": [1, 2.50, "x"], "a": {"y": null, "x": true}}"#).unwrap();
+        let b = hash_json_canonical("{ \"a\":{\"x\":true,\"y\":null},\n\"b\":[1.0,25e-1,\"x\"] }").unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a, hash_content(r#"{"a":{"x":true,"y":null},"b":[1,2.5,"x"]}"#).unwrap());
+    }
+
+    #[test]
+    fn test_hash_entries_encoding() {
+        let entries = vec![BatchEntry::Text("abc".to_string()), BatchEntry::Bytes(b"abc".to_vec())];
+        let results = hash_entries(entries.into_iter(), HashAlgorithm::Sha256, DigestEncoding::Base64Url);
+        assert_eq!(results[0].hash.as_deref(), Some("ungWv48Bz-pBQUDeXa4iI7ADYaOWF3qctBD_YfIAFa0"));
+        assert_eq!(results[1].hash, results[0].hash);
+
+        let blake3 = BatchEntry::Text("abc".to_string()).hash(HashAlgorithm::Blake3, DigestEncoding::Hex);
+        assert_eq!(blake3.unwrap(), hash_content_blake3("abc").unwrap());
+    }
+
+    #[test]
+    fn test_batch_hash_options_encoding() {
+        let options: BatchHashOptions = serde_json::from_str(r#"{ "encoding": "base64url" }"#).unwrap();
+        assert_eq!(options.encoding, DigestEncoding::Base64Url);
+        assert_eq!(options.algorithm, HashAlgorithm::Sha256);
+
+        let defaults: BatchHashOptions = serde_json::from_str("{}").unwrap();
+        assert_eq!(defaults.encoding, DigestEncoding::Hex);
+        assert!(serde_json::from_str::<BatchHashOptions>(r#"{ "encoding": "base32" }"#).is_err());
+    }
+
+    #[test]
+    fn test_batch_hash_result_serialization_omits_missing_fields() {
+        let ok = BatchHashResult { index: 0, hash: Some("ab".to_string()), error: None };
+        let err = BatchHashResult { index: 1, hash: None, error: Some("entry is null".to_string()) };
+        assert_eq!(serde_json::to_string(&ok).unwrap(), r#"{"index":0,"hash":"ab"}"#);
+        assert_eq!(serde_json::to_string(&err).unwrap(), r#"{"index":1,"error":"entry is null"}"#);
+    }
+
+    #[test]
+    fn test_normalize_and_hash() {
+        let options = NormalizeOptions::default();
+        assert_eq!(
+            core::normalize_and_hash("  Hello  \r\n\r\n\r\nWorld  ", &options, HashAlgorithm::Sha256),
+            hash_content("Hello\n\nWorld").unwrap()
+        );
+        assert_eq!(
+            core::normalize_and_hash("a\r\nb", &options, HashAlgorithm::Blake3),
+            hash_content_blake3("a\nb").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_normalize_hash_options_flattened() {
+        let options: NormalizeHashOptions =
+            serde_json::from_str(r#"{ "algorithm": "blake3", "line_ending": "crlf", "trim": false }"#).unwrap();
+        assert_eq!(options.algorithm, HashAlgorithm::Blake3);
+        assert_eq!(options.normalize.line_ending, normalize::LineEnding::Crlf);
+        assert!(!options.normalize.trim);
+        assert!(options.normalize.trim_lines);
+
+        let defaults: NormalizeHashOptions = serde_json::from_str("{}").unwrap();
+        assert_eq!(defaults.algorithm, HashAlgorithm::Sha256);
+        assert_eq!(defaults.normalize, NormalizeOptions::default());
+    }
+
+    #[test]
+    fn test_dedupe_options_nested_flatten() {
+        let options: DedupeOptions =
+            serde_json::from_str(r#"{ "include_singletons": true, "algorithm": "blake3", "trim": false }"#).unwrap();
+        assert!(options.include_singletons);
+        assert_eq!(options.hash.algorithm, HashAlgorithm::Blake3);
+        assert!(!options.hash.normalize.trim);
+
+        let defaults: DedupeOptions = serde_json::from_str("{}").unwrap();
+        assert!(!defaults.include_singletons);
+        assert_eq!(defaults.hash.normalize, NormalizeOptions::default());
+    }
+
+    #[test]
+    fn test_progress_interval_options() {
+        let defaults: BatchHashOptions = serde_json::from_str("{}").unwrap();
+        assert_eq!(defaults.progress_interval, progress::DEFAULT_INTERVAL);
+        let options: BatchHashOptions = serde_json::from_str(r#"{ "progress_interval": 50 }"#).unwrap();
+        assert_eq!(options.progress_interval, 50);
+
+        let options: BatchNormalizeOptions =
+            serde_json::from_str(r#"{ "progress_interval": 10, "trim": false }"#).unwrap();
+        assert_eq!(options.progress_interval, 10);
+        assert!(!options.normalize.trim);
+        assert_eq!(BatchNormalizeOptions::default().progress_interval, progress::DEFAULT_INTERVAL);
+    }
+
+    #[test]
+    fn test_entry_stats_normalized_and_raw() {
+        let entry = BatchEntry::Text("  title\r\n\r\n\r\n\r\nbody text   \r\n".to_string());
+
+        let normalized = entry_stats(&entry, &StatsOptions::default()).unwrap();
+        assert_eq!((normalized.lines, normalized.blank_lines, normalized.words), (3, 1, 3));
+        assert_eq!(normalized.bytes, "title\n\nbody text".len());
+        assert_eq!(normalized.max_line_length, 9);
+
+        let options: StatsOptions = serde_json::from_str(r#"{ "raw": true }"#).unwrap();
+        let raw = entry_stats(&entry, &options).unwrap();
+        assert_eq!((raw.lines, raw.blank_lines, raw.words), (5, 3, 3));
+        assert_eq!(raw.max_line_length, 12);
+
+        assert!(entry_stats(&BatchEntry::Invalid("entry is null".to_string()), &options).is_err());
+    }
+
+    #[test]
+    fn test_entry_stats_binary_bytes() {
+        let options: StatsOptions = serde_json::from_str(r#"{ "strip_invisible": true }"#).unwrap();
+        let stats = entry_stats(&BatchEntry::Bytes(vec![b'a', 0, 0xff, b'b']), &options).unwrap();
+        assert!(stats.likely_binary);
+        assert_eq!(stats.graphemes, 3, "NUL stripped, invalid byte decoded as U+FFFD");
+    }
+
+    #[test]
+    fn test_batch_hasher_slices() {
+        let mut hasher = BatchHasher::with_options(BatchHasherOptions::default());
+        hasher.push_entry(&BatchEntry::Text("a".to_string())).unwrap();
+        hasher.push_entry(&BatchEntry::Bytes(b"b".to_vec())).unwrap();
+        assert_eq!(hasher.pending(), 2);
+        assert_eq!(hasher.take(), vec![hash_content("a").unwrap(), hash_content("b").unwrap()]);
+        assert_eq!(hasher.pending(), 0);
+
+        let err = hasher.push_entry(&BatchEntry::Invalid("entry is null".to_string())).unwrap_err();
+        assert!(err.message.ends_with("entry is null"));
+        assert_eq!((err.code, err.index), (ErrorCode::InvalidInput, Some(2)));
+
+        let options = |json: &str| serde_json::from_str::<BatchHasherOptions>(json);
+        assert_eq!(options(r#"{ "algorithm": "blake3" }"#).unwrap().algorithm, HashAlgorithm::Blake3);
+        assert!(options(r#"{ "include_recipe": true }"#).is_err());
+        assert!(options(r#"{ "progress_interval": 10 }"#).is_err());
+        hasher.push_entry(&BatchEntry::Text("c".to_string())).unwrap();
+        assert_eq!(hasher.take(), vec![hash_content("c").unwrap()]);
+        assert!(hasher.take().is_empty());
+    }
+
+    fn keyed(json: &str) -> Result<BTreeMap<String, String>, ReconError> {
+        let documents: KeyedDocuments = serde_json::from_str(json).map_err(|e| ReconError::invalid_input(e.to_string()))?;
+        hash_keyed(documents, HashAlgorithm::Sha256, DigestEncoding::Hex)
+    }
+
+    #[test]
+    fn test_hash_keyed_list_input() {
+        let hashes = keyed(r#"[{ "id": "b", "content": "beta" }, { "id": "a", "content": "alpha" }]"#).unwrap();
+        assert_eq!(hashes.len(), 2);
+        assert_eq!(hashes["a"], hash_content("alpha").unwrap());
+        assert_eq!(hashes["b"], hash_content("beta").unwrap());
+    }
+
+    #[test]
+    fn test_hash_keyed_map_input() {
+        let hashes = keyed(r#"{ "doc-1": "one", "doc-2": "two" }"#).unwrap();
+        assert_eq!(hashes["doc-1"], hash_content("one").unwrap());
+        assert_eq!(hashes["doc-2"], hash_content("two").unwrap());
+        assert!(keyed("{}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_hash_keyed_missing_content_hashes_empty_string() {
+        let hashes = keyed(r#"[{ "id": "x" }, { "id": "y", "content": null }, { "id": "z", "content": "" }]"#).unwrap();
+        assert!(hashes.values().all(|hash| *hash == hash_content("").unwrap()));
+        assert_eq!(keyed(r#"{ "x": null }"#).unwrap()["x"], hash_content("").unwrap());
+    }
+
+    #[test]
+    fn test_hash_keyed_duplicate_id() {
+        let err = keyed(r#"[{ "id": "b", "content": "0" }, { "id": "a", "content": "1" }, { "id": "a", "content": "2" }]"#)
+            .unwrap_err();
+        assert_eq!(err, ReconError::invalid_input("Duplicate document id: a").at(2));
+        assert!(keyed(r#"[{ "content": "no id" }]"#).is_err());
+        assert!(keyed(r#""not documents""#).is_err());
+    }
+
+    fn stream_hash(data: &[u8], chunk_size: usize) -> String {
+        let mut hasher = StreamingHasher::new();
+        for chunk in data.chunks(chunk_size) {
+            hasher.update(chunk).unwrap();
+        }
+        hasher.finalize().unwrap()
+    }
+
+    #[test]
+    fn test_streaming_hasher_chunk_sizes_agree() {
+        let content: String = (0..200_000).map(|i| char::from(b'a' + (i % 26) as u8)).collect();
+        let expected = hash_content(&content).unwrap();
+
+        assert_eq!(stream_hash(content.as_bytes(), 1), expected);
+        assert_eq!(stream_hash(content.as_bytes(), 64 * 1024), expected);
+        assert_eq!(stream_hash(content.as_bytes(), content.len()), expected);
+    }
+
+    #[test]
+    fn test_streaming_hasher_empty() {
+        let mut hasher = StreamingHasher::new();
+        assert_eq!(hasher.finalize().unwrap(), hash_content("").unwrap());
+    }
+
+    #[test]
+    fn test_streaming_hasher_reset_and_reuse() {
+        let mut hasher = StreamingHasher::new();
+        hasher.update(b"first document").unwrap();
+        hasher.finalize().unwrap();
+        assert!(hasher.finalized());
+
+        hasher.reset();
+        assert!(!hasher.finalized());
+        hasher.update(b"second ").unwrap();
+        hasher.update(b"document").unwrap();
+        assert_eq!(hasher.finalize().unwrap(), hash_content("second document").unwrap());
+    }
+
+    #[test]
+    fn test_streaming_hasher_update_after_finalize() {
+        let mut hasher = StreamingHasher::new();
+        hasher.finalize().unwrap();
+        assert_eq!(hasher.update(b"late"), Err(finalized_error()));
+        assert_eq!(hasher.finalize().unwrap_err().code, ErrorCode::InvalidState);
+    }
+
+    #[test]
+    fn test_four_shards_reassemble_to_the_serial_hashes() {
+        let entries: Vec<BatchEntry> = (0..23)
+            .map(|i| {
+                if i % 3 == 0 {
+                    BatchEntry::Bytes(vec![i as u8; i])
+                } else {
+                    BatchEntry::Text(format!("document {}", i))
+                }
+            })
+            .collect();
+        let options = BatchHashOptions { algorithm: HashAlgorithm::Blake3, ..BatchHashOptions::default() };
+        let serial: Vec<String> =
+            entries.iter().map(|entry| entry.hash(options.algorithm, options.encoding).unwrap()).collect();
+
+        let mut reassembled = Vec::new();
+        for shard in 0..4 {
+            let range = shard::shard_range(entries.len(), shard, 4).unwrap();
+            assert_eq!(range.start, reassembled.len());
+            let shard_entries = entries[range.clone()].iter().cloned();
+            reassembled.extend(hash_range(range.start, shard_entries, &options, "batch_hash_sharded").unwrap());
+        }
+        assert_eq!(reassembled, serial);
+
+        // Errors report the position in the whole batch
+        let bad = vec![BatchEntry::Text("ok".to_string()), BatchEntry::Invalid("entry is null".to_string())];
+        let err = hash_range(12, bad.into_iter(), &options, "batch_hash_sharded").unwrap_err();
+        assert_eq!(err.index, Some(13));
+    }
+
+    #[test]
+    fn test_match_key_hashes_the_folded_form() {
+        assert_eq!(match_key("Straße").unwrap(), hash_content("strasse").unwrap());
+        assert_eq!(match_key("  ＳＴＲＡＳＳＥ! ").unwrap(), match_key("strasse").unwrap());
+        assert_ne!(match_key("ısı").unwrap(), match_key("isi").unwrap());
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let code = |result: Result<String, ReconError>| result.unwrap_err().code;
+
+        assert_eq!(code(hash_content_encoded("x", "base32")), ErrorCode::InvalidInput);
+        assert_eq!(code(hash_content_multihash("x", Some("md5".to_string()))), ErrorCode::InvalidInput);
+        assert_eq!(code(normalize_unicode("x", "nfx")), ErrorCode::InvalidInput);
+        assert_eq!(code(hash_prefix("x", 0)), ErrorCode::InvalidInput);
+        assert_eq!(code(hash_prefix("x", 65)), ErrorCode::InvalidInput);
+        assert_eq!(code(hash_content_keyed(&[0; 16], "x")), ErrorCode::InvalidInput);
+        assert_eq!(derive_tenant_key(&[0; 31], "t").unwrap_err().code, ErrorCode::InvalidInput);
+        assert_eq!(MerkleTree::from_bytes(b"abc", 0).err().unwrap().code, ErrorCode::InvalidInput);
+        assert_eq!(RollingHash::new(0).err().unwrap().code, ErrorCode::InvalidInput);
+
+        assert_eq!(code(multihash_to_hex("not base58!")), ErrorCode::DecodeError);
+        assert_eq!(code(hash_json_canonical("{")), ErrorCode::DecodeError);
+        assert_eq!(fingerprint_distance("xyz", "0").unwrap_err().code, ErrorCode::DecodeError);
+        assert_eq!(code(apply_patch("a\n", "@@ -x +1 @@\n")), ErrorCode::DecodeError);
+        assert_eq!(code(apply_patch("a\n", "@@ -1 +1 @@\n-b\n+c\n")), ErrorCode::PatchRejected);
+
+        let mut hasher = StreamingHasher::new();
+        hasher.finalize().unwrap();
+        assert_eq!(hasher.update(b"x").unwrap_err().code, ErrorCode::InvalidState);
+    }
+
+    #[test]
+    fn test_batch_entry_errors_carry_index() {
+        let err = entry_error("batch_hash", 4, "entry is null");
+        assert_eq!(err.code, ErrorCode::InvalidInput);
+        assert_eq!(err.index, Some(4));
+        assert_eq!(err.message, "batch_hash entries must be strings or Uint8Arrays: entry is null");
+    }
+
+    #[test]
+    fn test_size_limits_reject_single_inputs() {
+        limits::set(limits::Limits { max_input_bytes: 16, max_batch_items: 0 });
+        assert_eq!(hash_json_canonical("[1, 2, 3]").unwrap(), hash_content("[1,2,3]").unwrap());
+
+        let err = hash_json_canonical("[1, 2, 3, 4, 5, 6, 7]").unwrap_err();
+        assert_eq!(err.code, ErrorCode::SizeLimit);
+        assert_eq!(apply_patch("0123456789", "0123456789").unwrap_err().code, ErrorCode::SizeLimit);
+        assert_eq!(MerkleTree::from_bytes(&[0; 17], 4).err().unwrap().code, ErrorCode::SizeLimit);
+        assert_eq!(to_utf8(&[b'a'; 17], "windows-1252").unwrap_err().code, ErrorCode::SizeLimit);
+
+        let long = "x".repeat(17);
+        let rejected = [
+            hash_content(&long).err(),
+            hash_bytes(long.as_bytes()).err(),
+            hash_content_encoded(&long, "hex").err(),
+            hash_content_multihash(&long, None).err(),
+            normalize_content(&long).err(),
+            hash_prefix(&long, 8).err(),
+            create_patch("12345678", "123456789", None).err(),
+            similarity("12345678", "123456789").err(),
+            fingerprint(&long).err(),
+            containment("12345678", "123456789", None).err(),
+            edit_distance_within("1", &long, 100).err(),
+            quick_digest(long.as_bytes()).err(),
+        ];
+        for (i, err) in rejected.into_iter().enumerate() {
+            assert_eq!(err.map(|e| e.code), Some(ErrorCode::SizeLimit), "export {}", i);
+        }
+        assert!(fingerprint("0123456789abcdef").is_ok());
+    }
+
+    #[test]
+    fn test_size_limits_reject_batches() {
+        limits::set(limits::Limits { max_input_bytes: 8, max_batch_items: 2 });
+        assert!(BatchBudget::new(3).is_err());
+
+        // Three UTF-16 units each, so both pass `js_array`, but 10 bytes of UTF-8
+        let mut budget = BatchBudget::new(2).unwrap();
+        BatchEntry::Text("ééé".to_string()).within(0, &mut budget).unwrap();
+        let err = BatchEntry::Text("éé".to_string()).within(1, &mut budget).unwrap_err();
+        assert_eq!((err.code, err.index), (ErrorCode::SizeLimit, Some(1)));
+
+        let mut hasher = BatchHasher::with_options(BatchHasherOptions::default());
+        hasher.push_entry(&BatchEntry::Text("12345678".to_string())).unwrap();
+        let err = hasher.push_entry(&BatchEntry::Bytes(vec![0; 9])).unwrap_err();
+        assert_eq!((err.code, err.index), (ErrorCode::SizeLimit, Some(1)));
+        assert_eq!(hasher.pending(), 1);
+    }
+
+    #[test]
+    fn test_hash_with_recipe() {
+        let content = "  Hello  \r\n\r\n\r\nWorld  ";
+        assert_eq!(current_recipe(), "norm-v1+sha256");
+        assert_eq!(
+            hash_with_recipe(content, &current_recipe()).unwrap(),
+            core::normalize_and_hash(content, &NormalizeOptions::default(), HashAlgorithm::Sha256)
+        );
+        assert_eq!(hash_with_recipe(content, "raw+blake3").unwrap(), HashAlgorithm::Blake3.hash(content.as_bytes()));
+        assert_eq!(hash_with_recipe(content, "norm-v9+sha256").unwrap_err().code, ErrorCode::UnsupportedVersion);
+    }
+
+    #[test]
+    fn test_hash_bytes_with_recipe() {
+        // A Uint8Array batch entry tagged "raw+<algorithm>" is reproducible
+        let bytes = vec![0xff, 0x00, b'a', b'\r', b'\n'];
+        for algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Blake3] {
+            let recipe = requested_recipe(true, None, algorithm, DigestEncoding::Hex).unwrap().unwrap();
+            assert_eq!(
+                hash_bytes_with_recipe(&bytes, &recipe.to_string()).unwrap(),
+                BatchEntry::Bytes(bytes.clone()).hash(algorithm, DigestEncoding::Hex).unwrap()
+            );
+        }
+        let normalized = hash_with_recipe("a\r\nb", "norm-v1+sha256").unwrap();
+        assert_eq!(hash_bytes_with_recipe(b"a\r\nb", "norm-v1+sha256").unwrap(), normalized);
+        assert_eq!(hash_bytes_with_recipe(&bytes, "norm-v1+sha256").unwrap_err().code, ErrorCode::DecodeError);
+        assert_eq!(hash_bytes_with_recipe(&bytes, "raw+md5").unwrap_err().code, ErrorCode::UnsupportedVersion);
+    }
+
+    #[test]
+    fn test_requested_recipe() {
+        let defaults = NormalizeOptions::default();
+        let hex = DigestEncoding::Hex;
+        assert_eq!(requested_recipe(false, Some(&defaults), HashAlgorithm::Sha256, hex), Ok(None));
+        assert_eq!(requested_recipe(true, Some(&defaults), HashAlgorithm::Sha256, hex), Ok(Some(Recipe::current())));
+        let raw = requested_recipe(true, None, HashAlgorithm::Blake3, hex).unwrap().unwrap();
+        assert_eq!(raw.to_string(), "raw+blake3");
+
+        let custom = NormalizeOptions { collapse_spaces: true, ..defaults };
+        let err = requested_recipe(true, Some(&custom), HashAlgorithm::Sha256, hex).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidOptions);
+
+        // Base64 hashes cannot be checked against a recipe
+        for encoding in [DigestEncoding::Base64, DigestEncoding::Base64Url] {
+            let err = requested_recipe(true, None, HashAlgorithm::Sha256, encoding).unwrap_err();
+            assert_eq!(err.code, ErrorCode::InvalidOptions);
+            assert_eq!(requested_recipe(false, None, HashAlgorithm::Sha256, encoding), Ok(None));
+        }
+    }
+
+    #[test]
+    fn test_edit_distance_within_sentinel() {
+        assert_eq!(edit_distance("🇩🇪", "🇫🇷").unwrap(), 1);
+        assert_eq!(edit_distance_within("kitten", "sitting", 3).unwrap(), 3);
+        assert_eq!(edit_distance_within("kitten", "sitting", 2).unwrap(), -1);
+    }
+
+    #[test]
+    fn test_minhash_errors_are_invalid_input() {
+        let a = minhash_signature("one two three four", 16).unwrap();
+        let b = minhash_signature("one two three five", 16).unwrap();
+        assert!(estimate_jaccard(&a, &b).unwrap() < 1.0);
+        assert_eq!(estimate_jaccard(&a, &a).unwrap(), 1.0);
+        assert_eq!(estimate_jaccard(&a, &b[..8]).unwrap_err().code, ErrorCode::InvalidInput);
+        assert_eq!(minhash_signature("one", 0).unwrap_err().code, ErrorCode::InvalidInput);
+    }
+
+    #[test]
+    fn test_reconforth_basic() {
+        let mut vm = VM::new();
+        vm.eval("5 3 +").unwrap();
+    }
+
+    #[test]
+    fn test_reconforth_validation() {
+        let mut vm = VM::new();
+        vm.eval("\"Missing README\" error!").unwrap();
+        assert!(!vm.get_validation().success);
+        assert_eq!(vm.get_validation().errors.len(), 1);
+    }
+}
@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// BatchContext under a real WASM heap
+//
+// Run with `cargo test --target wasm32-unknown-unknown` and
+// wasm-bindgen-test-runner as the target runner.
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use recon_wasm::wasm::{memory_stats, BatchContext};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn stat(stats: &JsValue, path: &[&str]) -> f64 {
+    let value = path.iter().fold(stats.clone(), |value, key| js_sys::Reflect::get(&value, &(*key).into()).unwrap());
+    value.as_f64().unwrap()
+}
+
+fn batch(number: usize, size: usize) -> js_sys::Array {
+    (0..size).map(|i| JsValue::from_str(&format!("  batch {} document {}\r\n", number, i))).collect()
+}
+
+#[wasm_bindgen_test]
+fn test_repeated_batches_do_not_grow_linear_memory() {
+    let mut ctx = BatchContext::new(&JsValue::UNDEFINED).unwrap();
+    for number in 0..5 {
+        ctx.hash_batch(&batch(number, 1000)).unwrap();
+        ctx.normalize_batch(&batch(number, 1000)).unwrap();
+    }
+    let settled = ctx.memory_stats().unwrap();
+
+    for number in 5..1000 {
+        let docs = batch(number, 1000);
+        assert_eq!(ctx.hash_batch(&docs).unwrap().length(), 1000);
+        assert_eq!(ctx.normalize_batch(&docs).unwrap().length(), 1000);
+    }
+    let after = ctx.memory_stats().unwrap();
+    assert_eq!(stat(&after, &["pages"]), stat(&settled, &["pages"]));
+    assert_eq!(stat(&after, &["buffers", "text_bytes"]), stat(&settled, &["buffers", "text_bytes"]));
+}
+
+#[wasm_bindgen_test]
+fn test_results_and_shrink() {
+    let mut ctx = BatchContext::new(&JsValue::UNDEFINED).unwrap();
+    let docs: js_sys::Array = ["abc", "  x  "].into_iter().map(JsValue::from_str).collect();
+    docs.push(&js_sys::Uint8Array::from(&b"abc"[..]));
+    let hashes = ctx.hash_batch(&docs).unwrap();
+    assert_eq!(hashes.get(0).as_string().unwrap(), recon_wasm::wasm::hash_content("abc").unwrap());
+    assert_eq!(hashes.get(2), hashes.get(0));
+    assert!(ctx.normalize_batch(&docs).is_err());
+
+    ctx.hash_batch(&batch(0, 10_000)).unwrap();
+    assert!(stat(&ctx.memory_stats().unwrap(), &["buffers", "text_bytes"]) >= 640_000.0);
+    ctx.shrink();
+    assert_eq!(stat(&ctx.memory_stats().unwrap(), &["buffers", "text_bytes"]), 0.0);
+    assert!(stat(&memory_stats().unwrap(), &["pages"]) > 0.0);
+}
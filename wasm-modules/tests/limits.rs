@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Size limits on JS inputs that are checked before they are copied
+//
+// Run with `cargo test --target wasm32-unknown-unknown` and
+// wasm-bindgen-test-runner as the target runner.
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use recon_wasm::error::ErrorCode;
+use recon_wasm::limits;
+use recon_wasm::wasm::{batch_hash_keyed, batch_hmac, build_manifest, hash_tree, set_limits};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn js(json: &str) -> JsValue {
+    js_sys::JSON::parse(json).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn test_records_are_checked_before_copying() {
+    set_limits(8, 2);
+    let keyed = |json: &str| batch_hash_keyed(&js(json), &JsValue::UNDEFINED);
+    assert!(keyed(r#"{ "a": "1234", "b": "12" }"#).is_ok());
+    let err = keyed(r#"{ "a": "1", "b": "2", "c": "3" }"#).unwrap_err();
+    assert_eq!((err.code, err.index), (ErrorCode::SizeLimit, None));
+    let err = keyed(r#"[{ "id": "a", "content": "1234" }, { "id": "b", "content": "12345" }]"#).unwrap_err();
+    assert_eq!((err.code, err.index), (ErrorCode::SizeLimit, Some(1)));
+
+    let err = build_manifest(&js(r#"[{ "id": "a", "hash": "1234" }, { "id": "b", "hash": "12345" }]"#)).unwrap_err();
+    assert_eq!((err.code, err.index), (ErrorCode::SizeLimit, Some(1)));
+    let err = build_manifest(&js(r#"{ "a": "1234567", "b": "1" }"#)).unwrap_err();
+    assert_eq!((err.code, err.index), (ErrorCode::SizeLimit, Some(1)));
+    let err =
+        hash_tree(&js(r#"[{ "path": "a", "content": "1" }, { "path": "b", "content": "1234567" }]"#)).unwrap_err();
+    assert_eq!((err.code, err.index), (ErrorCode::SizeLimit, Some(1)));
+
+    // Strings pass the UTF-16 check up front, then fail on their UTF-8 size
+    let err = batch_hmac(b"key", &js(r#"["ééé", "éé"]"#)).unwrap_err();
+    assert_eq!((err.code, err.index), (ErrorCode::SizeLimit, Some(1)));
+    limits::set(limits::Limits::default());
+}